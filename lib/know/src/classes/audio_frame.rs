@@ -87,7 +87,7 @@ where
     use serde::Deserialize;
     let data_url = String::deserialize(deserializer)?;
     let data = STANDARD
-        .decode(data_url.split(',').last().unwrap())
+        .decode(data_url.split(',').next_back().unwrap())
         .map_err(serde::de::Error::custom)?;
     Ok(data)
 }
@@ -0,0 +1,491 @@
+// This is free and unencumbered software released into the public domain.
+
+use super::{InstantMessage, ThingLike};
+use crate::{
+    datatypes::{DateTime, InstantMessageHandle, InstantMessagePlatform, InstantMessageRecipient},
+    formatters::{DisplayConcise, DisplayDetailed, DisplayInline, DisplayJsonLd, DisplayOneliner},
+    prelude::Name,
+    traits,
+};
+use alloc::{collections::BTreeMap, fmt};
+
+#[cfg(feature = "irc")]
+pub(crate) mod irc_log;
+
+#[cfg(feature = "slack")]
+pub(crate) mod slack;
+
+#[cfg(feature = "sms-backup")]
+pub(crate) mod sms_backup;
+
+#[cfg(feature = "telegram")]
+pub(crate) mod telegram;
+
+#[cfg(feature = "whatsapp")]
+pub(crate) mod whatsapp;
+
+/// A chat between participants on an instant messaging platform, holding
+/// its messages in chronological order.
+///
+/// See: https://schema.org/Conversation
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(default, tag = "@type", rename_all = "camelCase")
+)]
+pub struct Conversation {
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "@id", skip_serializing_if = "Option::is_none")
+    )]
+    pub id: Option<String>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub name: Option<Name>,
+
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub platform: Option<InstantMessagePlatform>,
+
+    /// The senders and recipients of the messages, in order of appearance.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub participants: Vec<InstantMessageHandle>,
+
+    /// The messages, in chronological order.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub messages: Vec<InstantMessage>,
+}
+
+/// A message in a conversation together with the replies to it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConversationThread<'a> {
+    pub message: &'a InstantMessage,
+    pub replies: Vec<ConversationThread<'a>>,
+}
+
+impl Conversation {
+    /// Adds a message, keeping the messages in chronological order and
+    /// taking the platform, name and identifier of a group chat from the
+    /// first message that has them.
+    pub fn push(&mut self, message: InstantMessage) {
+        if self.platform.is_none() {
+            self.platform = message.platform.clone();
+        }
+        if let Some(group) = message.to.as_group() {
+            if self.id.is_none() {
+                self.id = message.group().and_then(|group| group.id);
+            }
+            if self.name.is_none() {
+                self.name = group.name.clone();
+            }
+        }
+        let handles = core::iter::once(&message.from).chain(match message.to {
+            InstantMessageRecipient::Direct(ref handle) => core::slice::from_ref(handle),
+            InstantMessageRecipient::Group(ref group)
+            | InstantMessageRecipient::Channel(ref group) => group.members.as_slice(),
+        });
+        for handle in handles {
+            if !self.participants.contains(handle) {
+                self.participants.push(handle.clone());
+            }
+        }
+        let index = self
+            .messages
+            .partition_point(|other| other.date <= message.date);
+        self.messages.insert(index, message);
+    }
+
+    /// Returns the date of the first message.
+    pub fn start(&self) -> Option<&DateTime> {
+        self.messages.first().map(|message| &message.date)
+    }
+
+    /// Returns the date of the last message.
+    pub fn end(&self) -> Option<&DateTime> {
+        self.messages.last().map(|message| &message.date)
+    }
+
+    /// Reconstructs the reply trees from the messages' `references`.
+    ///
+    /// Messages that don't reply to an earlier message of the conversation
+    /// are roots, and replies are ordered chronologically.
+    pub fn threads(&self) -> Vec<ConversationThread<'_>> {
+        let mut indexes = BTreeMap::new();
+        for (index, message) in self.messages.iter().enumerate() {
+            if let Some(ref id) = message.id {
+                indexes.entry(id).or_insert(index);
+            }
+        }
+        let parents: Vec<Option<usize>> = self
+            .messages
+            .iter()
+            .enumerate()
+            .map(|(index, message)| {
+                let parent = indexes.get(message.references.as_ref()?)?;
+                (*parent < index).then_some(*parent)
+            })
+            .collect();
+
+        // Parents precede their replies, so building the nodes back to
+        // front completes every reply before its parent needs it.
+        let mut replies: Vec<Vec<ConversationThread>> = vec![Vec::new(); self.messages.len()];
+        let mut roots = Vec::new();
+        for (index, message) in self.messages.iter().enumerate().rev() {
+            let mut children = core::mem::take(&mut replies[index]);
+            children.reverse();
+            let thread = ConversationThread {
+                message,
+                replies: children,
+            };
+            match parents[index] {
+                Some(parent) => replies[parent].push(thread),
+                None => roots.push(thread),
+            }
+        }
+        roots.reverse();
+        roots
+    }
+
+    pub fn inline(&self) -> DisplayInline<'_, Conversation> {
+        DisplayInline(self)
+    }
+
+    pub fn oneliner(&self) -> DisplayOneliner<'_, Conversation> {
+        DisplayOneliner(self)
+    }
+
+    pub fn concise(&self) -> DisplayConcise<'_, Conversation> {
+        DisplayConcise(self)
+    }
+
+    pub fn detailed(&self) -> DisplayDetailed<'_, Conversation> {
+        DisplayDetailed(self)
+    }
+
+    pub fn jsonld(&self) -> DisplayJsonLd<'_, Conversation> {
+        DisplayJsonLd(self)
+    }
+
+    fn write_header(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.inline())?;
+        if let Some(ref platform) = self.platform {
+            writeln!(f, "\tPlatform: {}", platform)?;
+        }
+        if !self.participants.is_empty() {
+            writeln!(
+                f,
+                "\tParticipants: {}",
+                self.participants
+                    .iter()
+                    .map(|handle| handle.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?;
+        }
+        if let Some(start) = self.start() {
+            writeln!(f, "\tStart: {}", start.inline())?;
+        }
+        if let Some(end) = self.end() {
+            writeln!(f, "\tEnd: {}", end.inline())?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes a transcript line, indenting continuation lines of the content,
+/// the attachments and the reactions under its first line.
+fn write_message(f: &mut fmt::Formatter, message: &InstantMessage, indent: &str) -> fmt::Result {
+    let prefix = format!(
+        "[{}] {}: ",
+        message.date.as_zoned().strftime("%Y-%m-%d %H:%M"),
+        message.from
+    );
+    let content = match message.content.as_str() {
+        "" if message.deleted => "🗑️ (deleted)",
+        content => content,
+    };
+    let mut lines = content.lines();
+    write!(
+        f,
+        "{}{}{}",
+        indent,
+        prefix,
+        lines.next().unwrap_or_default()
+    )?;
+    if message.edited.is_some() {
+        write!(f, " (edited)")?;
+    }
+    writeln!(f)?;
+    let width = indent.chars().count() + prefix.chars().count();
+    for line in lines {
+        writeln!(f, "{:width$}{}", "", line)?;
+    }
+    for attachment in &message.attachments {
+        writeln!(f, "{:width$}{}", "", attachment.inline())?;
+    }
+    if !message.reactions.is_empty() {
+        writeln!(
+            f,
+            "{:width$}{}",
+            "",
+            message
+                .reaction_counts()
+                .iter()
+                .map(|(emoji, count)| format!("{} {}", emoji, count))
+                .collect::<Vec<_>>()
+                .join(" ")
+        )?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Conversation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.oneliner())
+    }
+}
+
+impl fmt::Display for DisplayInline<'_, Conversation> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.0.name, &self.0.id) {
+            (Some(name), _) => write!(f, "💬 {}", name)?,
+            (None, Some(id)) => write!(f, "💬 {}", id)?,
+            (None, None) => write!(
+                f,
+                "💬 {}",
+                self.0
+                    .participants
+                    .iter()
+                    .map(|handle| handle.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )?,
+        }
+        match self.0.messages.len() {
+            1 => write!(f, " (1 message)"),
+            count => write!(f, " ({} messages)", count),
+        }
+    }
+}
+
+impl fmt::Display for DisplayOneliner<'_, Conversation> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.0.inline())
+    }
+}
+
+impl fmt::Display for DisplayConcise<'_, Conversation> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.write_header(f)?;
+        for message in &self.0.messages {
+            write_message(f, message, "")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DisplayDetailed<'_, Conversation> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.write_header(f)?;
+        let threads = self.0.threads();
+        let mut stack: Vec<(usize, &ConversationThread)> =
+            threads.iter().rev().map(|thread| (0, thread)).collect();
+        while let Some((depth, thread)) = stack.pop() {
+            let indent = match depth {
+                0 => String::new(),
+                _ => format!("{}↳ ", "  ".repeat(depth - 1)),
+            };
+            write_message(f, thread.message, &indent)?;
+            stack.extend(thread.replies.iter().rev().map(|reply| (depth + 1, reply)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for DisplayJsonLd<'_, Conversation> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use crate::traits::ToJsonLd;
+        let json = self.0.to_jsonld().unwrap(); // infallible
+        if cfg!(feature = "pretty") {
+            let mut w = crate::formatters::WriteToFormatter::new(f);
+            colored_json::write_colored_json(&json, &mut w).map_err(|_| fmt::Error)?;
+            writeln!(f)
+        } else {
+            writeln!(f, "{}", json)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl traits::ToJsonLd for Conversation {
+    fn to_jsonld(&self) -> serde_json::Result<serde_json::Value> {
+        use serde_json::json;
+        let mut participants = Vec::new();
+        for participant in &self.participants {
+            participants.push(participant.to_jsonld()?);
+        }
+        let mut messages = Vec::new();
+        for message in &self.messages {
+            messages.push(message.to_jsonld()?);
+        }
+        Ok(json!({
+            "@type": "Conversation",
+            "@id": self.id.as_deref().unwrap_or("_:conversation"),
+            "name": self.name,
+            "platform": self.platform.as_ref().map(InstantMessagePlatform::as_str),
+            "participant": participants,
+            "startDate": self.start().map(|date| date.to_string()),
+            "endDate": self.end().map(|date| date.to_string()),
+            "hasPart": messages,
+        }))
+    }
+}
+
+impl ThingLike for Conversation {
+    fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    fn name(&self) -> Option<&Name> {
+        self.name.as_ref()
+    }
+}
+
+impl Extend<InstantMessage> for Conversation {
+    fn extend<I: IntoIterator<Item = InstantMessage>>(&mut self, messages: I) {
+        for message in messages {
+            self.push(message);
+        }
+    }
+}
+
+impl FromIterator<InstantMessage> for Conversation {
+    fn from_iter<I: IntoIterator<Item = InstantMessage>>(messages: I) -> Self {
+        let mut conversation = Self::default();
+        conversation.extend(messages);
+        conversation
+    }
+}
+
+#[cfg(feature = "matrix")]
+include!("conversation/matrix.rs");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::datatypes::InstantMessageGroup;
+
+    fn message(
+        id: &str,
+        date: i64,
+        from: &str,
+        references: Option<&str>,
+        content: &str,
+    ) -> InstantMessage {
+        let to = InstantMessageRecipient::Group(InstantMessageGroup {
+            id: "family".into(),
+            platform: Some(InstantMessagePlatform::WhatsApp),
+            name: Some("Family".into()),
+            members: vec!["alice".parse().unwrap(), "bob".parse().unwrap()],
+        });
+        InstantMessage {
+            id: Some(id.into()),
+            platform: Some(InstantMessagePlatform::WhatsApp),
+            references: references.map(Into::into),
+            ..InstantMessage::new(date.into(), from.parse().unwrap(), to, content)
+        }
+    }
+
+    #[test]
+    fn test_conversation() {
+        let conversation: Conversation = [
+            message("3", 1_700_000_120, "alice", Some("1"), "Sure"),
+            message("1", 1_700_000_000, "bob", None, "Dinner tonight?"),
+            message("4", 1_700_000_180, "carol", Some("3"), "Me too\nSee you"),
+            message("2", 1_700_000_060, "carol", Some("missing"), "Hi all"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(conversation.id.as_deref(), Some("im-group:whatsapp:family"));
+        assert_eq!(
+            conversation.platform,
+            Some(InstantMessagePlatform::WhatsApp)
+        );
+        assert_eq!(
+            conversation
+                .participants
+                .iter()
+                .map(|handle| handle.as_str())
+                .collect::<Vec<_>>(),
+            ["alice", "bob", "carol"]
+        );
+        assert_eq!(conversation.start(), Some(&1_700_000_000.into()));
+        assert_eq!(conversation.end(), Some(&1_700_000_180.into()));
+
+        let threads = conversation.threads();
+        assert_eq!(threads.len(), 2);
+        assert_eq!(threads[0].message.content, "Dinner tonight?");
+        assert_eq!(
+            threads[0].replies[0].replies[0].message.content,
+            "Me too\nSee you"
+        );
+        assert_eq!(threads[1].message.content, "Hi all");
+
+        assert_eq!(
+            conversation.concise().to_string(),
+            "💬 Family (4 messages)\n\
+             \tPlatform: whatsapp\n\
+             \tParticipants: alice, bob, carol\n\
+             \tStart: 📅 2023-11-14 22:13:20 (+00:00)\n\
+             \tEnd: 📅 2023-11-14 22:16:20 (+00:00)\n\
+             [2023-11-14 22:13] bob: Dinner tonight?\n\
+             [2023-11-14 22:14] carol: Hi all\n\
+             [2023-11-14 22:15] alice: Sure\n\
+             [2023-11-14 22:16] carol: Me too\n\
+             \x20                         See you\n"
+        );
+        assert!(conversation.detailed().to_string().ends_with(
+            "[2023-11-14 22:13] bob: Dinner tonight?\n\
+             ↳ [2023-11-14 22:15] alice: Sure\n\
+             \x20 ↳ [2023-11-14 22:16] carol: Me too\n\
+             \x20                             See you\n\
+             [2023-11-14 22:14] carol: Hi all\n"
+        ));
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn test_from_matrix() {
+        let events: Vec<serde_json::Value> =
+            serde_json::from_str(include_str!("../../test/matrix/timeline.json")).unwrap();
+        let conversation = Conversation::from_matrix(&events).unwrap();
+        assert_eq!(
+            conversation.id.as_deref(),
+            Some("im-group:matrix:!ops:example.org")
+        );
+        assert_eq!(conversation.platform, Some(InstantMessagePlatform::Matrix));
+        assert_eq!(conversation.messages.len(), 5);
+
+        let hello = &conversation.messages[0];
+        assert_eq!(hello.content, "Hello everyone");
+        assert_eq!(hello.edited, Some(1_704_103_320.into()));
+        // Bob's replacement of Alice's message is ignored:
+        assert_eq!(hello.revisions.len(), 1);
+        assert_eq!(hello.revisions[0].content, "Hello **team**");
+        assert_eq!(conversation.threads()[0].replies.len(), 1);
+
+        let image = &conversation.messages[2];
+        // Bob's 🎉 reaction was redacted:
+        assert_eq!(image.reaction_counts(), [("👍", 1)]);
+        assert_eq!(
+            image.reactions[0].from,
+            InstantMessageHandle::Matrix("@alice:example.org".into())
+        );
+
+        let voice = &conversation.messages[3];
+        assert!(voice.deleted);
+        assert!(voice.attachments.is_empty());
+        assert!(conversation.messages[4].deleted);
+    }
+}
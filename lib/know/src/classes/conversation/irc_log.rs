@@ -0,0 +1,544 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{
+    classes::{Conversation, InstantMessage},
+    datatypes::{
+        DateTime, InstantMessageGroup, InstantMessageHandle, InstantMessagePlatform,
+        InstantMessageRecipient,
+    },
+};
+use jiff::civil;
+use std::path::Path;
+
+/// The format of an IRC log, which depends on the client or bouncer that
+/// wrote it.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum IrcLogFormat {
+    /// E.g., `10:01 <@alice> hello`, with `--- Day changed` markers.
+    Irssi,
+    /// E.g., `2024-01-01 10:01:00\t@alice\thello`.
+    WeeChat,
+    /// E.g., `[10:01:00] <alice> hello`, with one file per day.
+    Znc,
+    /// E.g., `[2024-01-01 10:01:00] <alice> hello`, as ZNC's `log` module
+    /// writes with a `-timestamp` that includes the date.
+    ZncLog,
+}
+
+/// Options for importing an IRC log.
+#[derive(Clone, Debug, Default)]
+pub struct IrcLogOptions {
+    /// The format of the log, detected from its first lines if `None`.
+    pub format: Option<IrcLogFormat>,
+
+    /// The channel of the log, e.g., `#rust`, which not all formats
+    /// contain.
+    pub channel: Option<String>,
+
+    /// The date of the log's first line, for formats whose timestamps
+    /// lack the date until a day-change marker.
+    pub date: Option<civil::Date>,
+
+    /// The time zone the client was logging in, for converting the log's
+    /// wall-clock times. Defaults to UTC.
+    pub time_zone: Option<jiff::tz::TimeZone>,
+}
+
+/// An event in an IRC log.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum IrcEvent {
+    /// A message sent to the channel.
+    Message(InstantMessage),
+    /// A `/me` action, e.g., `* alice waves`, whose content is `waves`.
+    Action(InstantMessage),
+    Join {
+        date: DateTime,
+        nick: InstantMessageHandle,
+    },
+    Part {
+        date: DateTime,
+        nick: InstantMessageHandle,
+        reason: Option<String>,
+    },
+    Quit {
+        date: DateTime,
+        nick: InstantMessageHandle,
+        reason: Option<String>,
+    },
+    NickChange {
+        date: DateTime,
+        from: InstantMessageHandle,
+        to: InstantMessageHandle,
+    },
+}
+
+/// The events of an IRC channel's log, as written by irssi, WeeChat or
+/// ZNC.
+#[derive(Clone, Debug, Default)]
+pub struct IrcLog {
+    pub channel: Option<String>,
+    pub events: Vec<IrcEvent>,
+}
+
+/// A log line, before its timestamp is resolved.
+enum Line<'a> {
+    Message(&'a str, &'a str),
+    Action(&'a str, &'a str),
+    /// A join, with the channel if the line has it.
+    Join(&'a str, Option<&'a str>),
+    Part(&'a str, Option<&'a str>, Option<&'a str>),
+    Quit(&'a str, Option<&'a str>),
+    NickChange(&'a str, &'a str),
+    /// A line that changes the date, e.g., `--- Day changed Tue Jan 02 2024`.
+    Date(civil::Date),
+}
+
+enum Timestamp {
+    Time(civil::Time),
+    DateTime(civil::DateTime),
+}
+
+impl IrcEvent {
+    pub fn date(&self) -> &DateTime {
+        match self {
+            IrcEvent::Message(message) | IrcEvent::Action(message) => &message.date,
+            IrcEvent::Join { date, .. }
+            | IrcEvent::Part { date, .. }
+            | IrcEvent::Quit { date, .. }
+            | IrcEvent::NickChange { date, .. } => date,
+        }
+    }
+}
+
+impl IrcLog {
+    /// Imports a log file, taking the channel and date from the file name
+    /// unless they're given in the options, e.g., `#rust.log` for irssi,
+    /// `irc.libera.#rust.weechatlog` for WeeChat, and `#rust_20240101.log`
+    /// or `#rust/2024-01-01.log` for ZNC.
+    pub fn open(path: impl AsRef<Path>, options: &IrcLogOptions) -> Result<Self, crate::Error> {
+        let path = path.as_ref();
+        let mut options = options.clone();
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or_default();
+        let (stem, date) = match stem.rsplit_once('_') {
+            Some((channel, date)) => match civil::Date::strptime("%Y%m%d", date) {
+                Ok(date) => (channel, Some(date)),
+                Err(_) => (stem, None),
+            },
+            None => (stem, stem.parse().ok()),
+        };
+        if options.date.is_none() {
+            options.date = date;
+        }
+        if options.channel.is_none() {
+            let parent = path
+                .parent()
+                .and_then(|parent| parent.file_name())
+                .and_then(|name| name.to_str());
+            options.channel = [Some(stem), parent]
+                .into_iter()
+                .flatten()
+                .find_map(|name| name.find(['#', '&']).map(|start| name[start..].into()));
+        }
+        Self::parse(&std::fs::read_to_string(path)?, &options)
+    }
+
+    /// Parses the text of a log, dating the lines whose timestamps lack
+    /// the date by the last day-change marker before them.
+    pub fn parse(input: &str, options: &IrcLogOptions) -> Result<Self, crate::Error> {
+        let lines = input.lines().map(|line| line.trim_end_matches('\r'));
+        let format = match options.format {
+            Some(format) => format,
+            None => lines
+                .clone()
+                .find_map(detect_format)
+                .ok_or_else(|| crate::Error::InvalidProperty("format", "unknown".into()))?,
+        };
+        let parse_line = match format {
+            IrcLogFormat::Irssi => parse_irssi,
+            IrcLogFormat::WeeChat => parse_weechat,
+            IrcLogFormat::Znc | IrcLogFormat::ZncLog => parse_znc,
+        };
+
+        let time_zone = options.time_zone.clone().unwrap_or(jiff::tz::TimeZone::UTC);
+        let mut day = options.date;
+        let mut entries = Vec::new();
+        for line in lines {
+            let Some((timestamp, line)) = parse_line(line) else {
+                continue;
+            };
+            let date = match (timestamp, &line) {
+                (_, Line::Date(date)) => {
+                    day = Some(*date);
+                    continue;
+                },
+                (Timestamp::DateTime(date), _) => {
+                    day = Some(date.date());
+                    date
+                },
+                (Timestamp::Time(time), _) => day
+                    .ok_or(crate::Error::MissingProperty("date"))?
+                    .to_datetime(time),
+            };
+            let date = date
+                .to_zoned(time_zone.clone())
+                .map_err(|error| crate::Error::InvalidProperty("date", error.to_string()))?;
+            entries.push((DateTime::from(date), line));
+        }
+
+        let channel = options.channel.clone().or_else(|| {
+            entries.iter().find_map(|(_, line)| match line {
+                Line::Join(_, channel) | Line::Part(_, channel, _) => channel.map(String::from),
+                _ => None,
+            })
+        });
+        let to = InstantMessageRecipient::Channel(InstantMessageGroup {
+            id: channel.clone().unwrap_or_default(),
+            platform: Some(InstantMessagePlatform::Irc),
+            name: channel.clone(),
+            members: Vec::new(),
+        });
+        let message = |date: DateTime, nick: &str, text: &str| {
+            let mut message = InstantMessage::new(date, handle(nick), to.clone(), text);
+            message.platform = Some(InstantMessagePlatform::Irc);
+            message
+        };
+        let events = entries
+            .into_iter()
+            .filter_map(|(date, line)| match line {
+                Line::Message(nick, text) => Some(IrcEvent::Message(message(date, nick, text))),
+                Line::Action(nick, text) => Some(IrcEvent::Action(message(date, nick, text))),
+                Line::Join(nick, _) => Some(IrcEvent::Join {
+                    date,
+                    nick: handle(nick),
+                }),
+                Line::Part(nick, _, reason) => Some(IrcEvent::Part {
+                    date,
+                    nick: handle(nick),
+                    reason: reason.map(String::from),
+                }),
+                Line::Quit(nick, reason) => Some(IrcEvent::Quit {
+                    date,
+                    nick: handle(nick),
+                    reason: reason.map(String::from),
+                }),
+                Line::NickChange(from, to) => Some(IrcEvent::NickChange {
+                    date,
+                    from: handle(from),
+                    to: handle(to),
+                }),
+                Line::Date(_) => None,
+            })
+            .collect();
+        Ok(Self { channel, events })
+    }
+
+    /// Returns the messages and actions of the log as a conversation.
+    pub fn conversation(&self) -> Conversation {
+        let mut output = Conversation {
+            name: self.channel.clone(),
+            ..Default::default()
+        };
+        output.extend(self.events.iter().filter_map(|event| match event {
+            IrcEvent::Message(message) | IrcEvent::Action(message) => Some(message.clone()),
+            _ => None,
+        }));
+        output
+    }
+}
+
+fn detect_format(line: &str) -> Option<IrcLogFormat> {
+    if line.starts_with("--- Log opened ") || line.starts_with("--- Day changed ") {
+        return Some(IrcLogFormat::Irssi);
+    }
+    if let Some(rest) = line.strip_prefix('[') {
+        let (timestamp, _) = rest.split_once("] ")?;
+        if timestamp.parse::<civil::DateTime>().is_ok() {
+            return Some(IrcLogFormat::ZncLog);
+        }
+        return parse_time(timestamp).map(|_| IrcLogFormat::Znc);
+    }
+    if let Some((timestamp, _)) = line.split_once('\t') {
+        return timestamp
+            .parse::<civil::DateTime>()
+            .ok()
+            .map(|_| IrcLogFormat::WeeChat);
+    }
+    let (timestamp, _) = line.split_once(' ')?;
+    parse_time(timestamp).map(|_| IrcLogFormat::Irssi)
+}
+
+/// Parses an irssi line, e.g., `10:01 <@alice> hello`, `10:02  * alice
+/// waves` or `10:03 -!- bob [~bob@host] has joined #rust`.
+fn parse_irssi(line: &str) -> Option<(Timestamp, Line<'_>)> {
+    if let Some(rest) = line.strip_prefix("--- Log opened ") {
+        let date = civil::DateTime::strptime("%a %b %d %H:%M:%S %Y", rest).ok()?;
+        return Some((Timestamp::DateTime(date), Line::Date(date.date())));
+    }
+    if let Some(rest) = line.strip_prefix("--- Day changed ") {
+        let date = civil::Date::strptime("%a %b %d %Y", rest).ok()?;
+        return Some((Timestamp::DateTime(date.into()), Line::Date(date)));
+    }
+    let (time, rest) = line.split_once(' ')?;
+    let time = Timestamp::Time(parse_time(time)?);
+    let line = if let Some(rest) = rest.strip_prefix('<') {
+        let (nick, text) = rest.split_once('>')?;
+        Line::Message(nick, text.strip_prefix(' ').unwrap_or(text))
+    } else if let Some(rest) = rest.strip_prefix(" * ") {
+        let (nick, text) = rest.split_once(' ').unwrap_or((rest, ""));
+        Line::Action(nick, text)
+    } else {
+        parse_event(rest.strip_prefix("-!- ")?)?
+    };
+    Some((time, line))
+}
+
+/// Parses a WeeChat line, e.g., `2024-01-01 10:01:00\t@alice\thello`.
+fn parse_weechat(line: &str) -> Option<(Timestamp, Line<'_>)> {
+    let mut fields = line.splitn(3, '\t');
+    let date = fields.next()?.parse().ok()?;
+    let prefix = fields.next()?;
+    let text = fields.next().unwrap_or_default();
+    let line = match prefix {
+        "-->" | "<--" | "--" => parse_event(text)?,
+        " *" | "*" => {
+            let (nick, text) = text.split_once(' ').unwrap_or((text, ""));
+            Line::Action(nick, text)
+        },
+        "" | "=!=" => return None,
+        nick => Line::Message(nick, text),
+    };
+    Some((Timestamp::DateTime(date), line))
+}
+
+/// Parses a ZNC line, e.g., `[10:01:00] <alice> hello`, `[10:02:00] *
+/// alice waves` or `[10:03:00] *** Joins: bob (~bob@host)`.
+fn parse_znc(line: &str) -> Option<(Timestamp, Line<'_>)> {
+    let (timestamp, rest) = line.strip_prefix('[')?.split_once("] ")?;
+    let timestamp = match timestamp.parse() {
+        Ok(date) => Timestamp::DateTime(date),
+        Err(_) => Timestamp::Time(parse_time(timestamp)?),
+    };
+    let line = if let Some(rest) = rest.strip_prefix("*** ") {
+        if let Some((from, to)) = rest.split_once(" is now known as ") {
+            Line::NickChange(from, to)
+        } else if let Some(rest) = rest.strip_prefix("Joins: ") {
+            Line::Join(rest.split_once(' ').map_or(rest, |(nick, _)| nick), None)
+        } else if let Some(rest) = rest.strip_prefix("Parts: ") {
+            let (nick, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+            Line::Part(nick, None, reason(skip_host(rest)))
+        } else if let Some(rest) = rest.strip_prefix("Quits: ") {
+            let (nick, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+            Line::Quit(nick, reason(skip_host(rest)))
+        } else {
+            return None;
+        }
+    } else if let Some(rest) = rest.strip_prefix("* ") {
+        let (nick, text) = rest.split_once(' ').unwrap_or((rest, ""));
+        Line::Action(nick, text)
+    } else {
+        let (nick, text) = rest.strip_prefix('<')?.split_once('>')?;
+        Line::Message(nick, text.strip_prefix(' ').unwrap_or(text))
+    };
+    Some((timestamp, line))
+}
+
+/// Parses the text of an irssi or WeeChat event, e.g., `bob [~bob@host]
+/// has left #rust [Leaving]` or `alice is now known as alice_`, ignoring
+/// the other events, e.g., mode and topic changes.
+fn parse_event(text: &str) -> Option<Line<'_>> {
+    if let Some((from, to)) = text.split_once(" is now known as ") {
+        return Some(Line::NickChange(from, to));
+    }
+    let (nick, rest) = text.split_once(' ')?;
+    let rest = skip_host(rest);
+    if let Some(channel) = rest.strip_prefix("has joined ") {
+        return Some(Line::Join(nick, Some(channel.trim())));
+    }
+    if let Some(rest) = rest.strip_prefix("has left ") {
+        let (channel, rest) = rest.split_once(' ').unwrap_or((rest, ""));
+        return Some(Line::Part(nick, Some(channel), reason(rest)));
+    }
+    let rest = rest.strip_prefix("has quit")?;
+    Some(Line::Quit(nick, reason(rest)))
+}
+
+/// Skips the `user@host` after a nick, in brackets or parentheses.
+fn skip_host(input: &str) -> &str {
+    let close = match input.chars().next() {
+        Some('[') => ']',
+        Some('(') => ')',
+        _ => return input,
+    };
+    input
+        .split_once(close)
+        .map_or(input, |(_, rest)| rest.trim_start())
+}
+
+/// Returns the reason of a part or quit, in brackets or parentheses.
+fn reason(input: &str) -> Option<&str> {
+    let input = input.trim();
+    let input = input
+        .strip_prefix(['[', '('])
+        .and_then(|input| input.strip_suffix([']', ')']))
+        .unwrap_or(input);
+    Some(input).filter(|input| !input.is_empty())
+}
+
+/// Parses the time of a timestamp, with or without the seconds.
+fn parse_time(input: &str) -> Option<civil::Time> {
+    civil::Time::strptime("%H:%M:%S", input)
+        .or_else(|_| civil::Time::strptime("%H:%M", input))
+        .ok()
+}
+
+/// Returns the handle of a nick, without its channel mode prefix, e.g.,
+/// `@` for operators and `+` for voiced users.
+fn handle(nick: &str) -> InstantMessageHandle {
+    let nick = nick.trim().trim_start_matches(['~', '&', '@', '%', '+']);
+    InstantMessageHandle::Username(nick.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/test/irc");
+
+    fn nick(nick: &str) -> InstantMessageHandle {
+        InstantMessageHandle::Username(nick.into())
+    }
+
+    #[test]
+    fn test_irssi() {
+        let log = IrcLog::open(format!("{TEST_DIR}/#rust.log"), &IrcLogOptions::default()).unwrap();
+        assert_eq!(log.channel.as_deref(), Some("#rust"));
+        assert_eq!(log.events.len(), 7);
+        assert_eq!(
+            log.events[0],
+            IrcEvent::Join {
+                date: "2024-01-01T23:58:00Z".parse().unwrap(),
+                nick: nick("alice"),
+            }
+        );
+        let IrcEvent::Message(ref message) = log.events[1] else {
+            panic!("expected a message");
+        };
+        assert_eq!(message.from, nick("bob"));
+        assert_eq!(message.to.inline().to_string(), "📢 #rust");
+        assert_eq!(message.content, "hi alice <3");
+        // After the day-change marker:
+        let IrcEvent::Action(ref action) = log.events[2] else {
+            panic!("expected an action");
+        };
+        assert_eq!(action.date, "2024-01-02T00:01:00Z".parse().unwrap());
+        assert_eq!(action.content, "waves at bob");
+        assert_eq!(
+            log.events[3],
+            IrcEvent::NickChange {
+                date: "2024-01-02T00:02:00Z".parse().unwrap(),
+                from: nick("alice"),
+                to: nick("alice_"),
+            }
+        );
+        let IrcEvent::Message(ref message) = log.events[4] else {
+            panic!("expected a message");
+        };
+        assert_eq!(message.from, nick("alice_"));
+        assert_eq!(
+            log.events[5],
+            IrcEvent::Part {
+                date: "2024-01-02T00:05:00Z".parse().unwrap(),
+                nick: nick("bob"),
+                reason: Some("see you".into()),
+            }
+        );
+        assert!(matches!(log.events[6], IrcEvent::Quit { reason: None, .. }));
+        assert_eq!(log.conversation().messages.len(), 3);
+    }
+
+    #[test]
+    fn test_weechat() {
+        let log = IrcLog::open(
+            format!("{TEST_DIR}/irc.libera.#rust.weechatlog"),
+            &IrcLogOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(log.channel.as_deref(), Some("#rust"));
+        assert_eq!(log.events.len(), 6);
+        assert!(matches!(log.events[0], IrcEvent::Join { .. }));
+        let IrcEvent::Message(ref message) = log.events[1] else {
+            panic!("expected a message");
+        };
+        assert_eq!(message.from, nick("bob"));
+        assert_eq!(message.date, "2024-01-02T00:00:30Z".parse().unwrap());
+        assert_eq!(message.content, "hi\talice");
+        assert!(matches!(log.events[2], IrcEvent::Action(_)));
+        assert!(matches!(log.events[3], IrcEvent::NickChange { .. }));
+        assert_eq!(
+            log.events[4],
+            IrcEvent::Part {
+                date: "2024-01-02T00:05:00Z".parse().unwrap(),
+                nick: nick("bob"),
+                reason: Some("see you".into()),
+            }
+        );
+        assert_eq!(
+            log.events[5],
+            IrcEvent::Quit {
+                date: "2024-01-02T00:06:00Z".parse().unwrap(),
+                nick: nick("alice_"),
+                reason: Some("Quit: bye".into()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_znc() {
+        let log = IrcLog::open(
+            format!("{TEST_DIR}/#rust_20240102.log"),
+            &IrcLogOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(log.channel.as_deref(), Some("#rust"));
+        assert_eq!(log.events.len(), 6);
+        assert_eq!(
+            log.events[0],
+            IrcEvent::Join {
+                date: "2024-01-02T00:00:00Z".parse().unwrap(),
+                nick: nick("alice"),
+            }
+        );
+        let IrcEvent::Action(ref action) = log.events[2] else {
+            panic!("expected an action");
+        };
+        assert_eq!(action.from, nick("alice"));
+        assert_eq!(action.content, "waves at bob");
+        assert!(matches!(log.events[4], IrcEvent::Part { reason: None, .. }));
+        assert!(matches!(
+            log.events[5],
+            IrcEvent::Quit { ref reason, .. } if reason.as_deref() == Some("Ping timeout: 240 seconds")
+        ));
+
+        let input = "[2024-01-01 23:59:59] <alice> good night\n\
+                     [2024-01-02 00:00:01] <bob> good morning\n";
+        let options = IrcLogOptions {
+            channel: Some("#rust".into()),
+            ..Default::default()
+        };
+        let log = IrcLog::parse(input, &options).unwrap();
+        assert_eq!(log.events.len(), 2);
+        assert_eq!(
+            *log.events[1].date(),
+            "2024-01-02T00:00:01Z".parse().unwrap()
+        );
+        assert!(IrcLog::parse("[10:00:00] <alice> hi", &options).is_err());
+
+        // Without a channel, the conversation has no identifier:
+        let log = IrcLog::parse(input, &IrcLogOptions::default()).unwrap();
+        assert_eq!(log.channel, None);
+        assert_eq!(log.conversation().id, None);
+    }
+}
@@ -0,0 +1,95 @@
+// This is free and unencumbered software released into the public domain.
+
+impl Conversation {
+    /// Converts the events of a Matrix room timeline, applying edits,
+    /// redactions and reactions to the messages they relate to and
+    /// ignoring state events, as well as edits by anyone but the sender.
+    ///
+    /// See: https://spec.matrix.org/latest/client-server-api/#event-relationships
+    pub fn from_matrix(events: &[serde_json::Value]) -> Result<Self, crate::Error> {
+        use serde_json::Value;
+
+        fn message<'a>(output: &'a mut Conversation, id: &str) -> Option<&'a mut InstantMessage> {
+            output
+                .messages
+                .iter_mut()
+                .find(|message| message.id.as_ref().is_some_and(|other| other.as_str() == id))
+        }
+
+        let mut output = Self::default();
+        // The annotated message, key and sender of each reaction event:
+        let mut reactions: BTreeMap<String, (String, &str, InstantMessageHandle)> =
+            BTreeMap::new();
+        for event in events {
+            let target = |pointer: &str| event.pointer(pointer).and_then(Value::as_str);
+            match event.get("type").and_then(Value::as_str) {
+                Some("m.room.message" | "m.sticker") => {
+                    let edit = InstantMessage::from_matrix(event)?;
+                    let original = match target("/content/m.relates_to/rel_type") {
+                        Some("m.replace") => target("/content/m.relates_to/event_id"),
+                        _ => None,
+                    };
+                    match original.and_then(|id| message(&mut output, id)) {
+                        // Only the original sender may replace a message:
+                        Some(original) if original.from != edit.from => {},
+                        Some(original) => {
+                            original.edit(edit.date, edit.content);
+                            if !edit.attachments.is_empty() {
+                                original.attachments = edit.attachments;
+                            }
+                        },
+                        None if original.is_some() => {}, // the original isn't in the timeline
+                        None => output.push(edit),
+                    }
+                },
+                Some("m.room.redaction") => {
+                    // The `redacts` key moved into the content in room version 11:
+                    let redacts = target("/redacts").or(target("/content/redacts"));
+                    // Redacting a reaction removes it from the message:
+                    if let Some((annotated, key, sender)) =
+                        redacts.and_then(|id| reactions.remove(id))
+                    {
+                        if let Some(annotated) = message(&mut output, &annotated) {
+                            let position = annotated.reactions.iter().position(|reaction| {
+                                reaction.emoji == key && reaction.from == sender
+                            });
+                            if let Some(position) = position {
+                                annotated.reactions.remove(position);
+                            }
+                        }
+                        continue;
+                    }
+                    if let Some(redacted) = redacts.and_then(|id| message(&mut output, id)) {
+                        redacted.deleted = true;
+                        redacted.content.clear();
+                        redacted.revisions.clear();
+                        redacted.attachments.clear();
+                    }
+                },
+                Some("m.reaction") => {
+                    let Some(key) = target("/content/m.relates_to/key") else {
+                        continue;
+                    };
+                    let Some(sender) = target("/sender") else {
+                        continue;
+                    };
+                    let Some(annotated_id) = target("/content/m.relates_to/event_id") else {
+                        continue;
+                    };
+                    if let Some(annotated) = message(&mut output, annotated_id) {
+                        let sender = InstantMessageHandle::Matrix(sender.into());
+                        let mut reaction =
+                            crate::datatypes::InstantMessageReaction::new(key, sender.clone());
+                        reaction.date = Some(super::instant_message::matrix_timestamp(event)?);
+                        annotated.reactions.push(reaction);
+                        if let Some(id) = target("/event_id") {
+                            reactions.insert(id.to_string(), (annotated_id.to_string(), key, sender));
+                        }
+                    }
+                },
+                _ => continue,
+            }
+        }
+        Ok(output)
+    }
+}
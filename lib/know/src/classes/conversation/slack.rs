@@ -0,0 +1,460 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{
+    classes::{Conversation, File, InstantMessage, Person},
+    datatypes::{
+        DateTime, InstantMessageAttachment, InstantMessageGroup, InstantMessageHandle,
+        InstantMessagePlatform, InstantMessageReaction, InstantMessageRecipient,
+    },
+};
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::Path};
+
+/// A Slack workspace export, i.e., a directory with `users.json`, the
+/// channel lists, and a directory of per-day JSON files for each channel.
+///
+/// See: https://slack.com/help/articles/220556107
+#[derive(Clone, Debug, Default)]
+pub struct SlackExport {
+    /// The members of the workspace, by user ID.
+    pub users: BTreeMap<String, Person>,
+
+    /// The channels, private channels, group DMs and DMs.
+    pub conversations: Vec<Conversation>,
+}
+
+#[derive(Deserialize)]
+struct User {
+    id: String,
+    name: String,
+    #[serde(default)]
+    real_name: Option<String>,
+    #[serde(default)]
+    profile: Profile,
+}
+
+#[derive(Default, Deserialize)]
+struct Profile {
+    #[serde(default)]
+    display_name: String,
+    #[serde(default)]
+    email: Option<String>,
+    #[serde(default)]
+    phone: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Channel {
+    id: String,
+    name: Option<String>,
+    #[serde(default)]
+    members: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    #[serde(default)]
+    subtype: Option<String>,
+    user: Option<String>,
+    username: Option<String>,
+    bot_id: Option<String>,
+    #[serde(default)]
+    text: String,
+    ts: String,
+    thread_ts: Option<String>,
+    edited: Option<Edited>,
+    #[serde(default)]
+    files: Vec<SlackFile>,
+    #[serde(default)]
+    reactions: Vec<Reaction>,
+}
+
+#[derive(Deserialize)]
+struct Edited {
+    ts: String,
+}
+
+#[derive(Deserialize)]
+struct SlackFile {
+    id: Option<String>,
+    name: Option<String>,
+    mimetype: Option<String>,
+    #[serde(default)]
+    size: u64,
+    url_private: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Reaction {
+    name: String,
+    #[serde(default)]
+    users: Vec<String>,
+}
+
+/// The kind of a conversation, which depends on the list it's in.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Kind {
+    Channel,
+    Group,
+    Direct,
+}
+
+impl SlackExport {
+    /// Imports an export directory, which is the unzipped export archive.
+    pub fn open(directory: impl AsRef<Path>) -> Result<Self, crate::Error> {
+        let directory = directory.as_ref();
+        let users: Vec<User> = read_json(&directory.join("users.json"))?.unwrap_or_default();
+        let mut output = Self::default();
+        let mut names = BTreeMap::new();
+        for user in users {
+            // Usernames can change, so users are identified by their IDs:
+            let handle = InstantMessageHandle::Slack(user.id.clone());
+            names.insert(user.id.clone(), user.name.clone());
+            let person = Person {
+                id: Some(handle.to_uri()),
+                name: user.real_name.as_deref().unwrap_or(&user.name).into(),
+                nicknames: Some(user.profile.display_name)
+                    .filter(|name| !name.is_empty())
+                    .map(Into::into)
+                    .into_iter()
+                    .collect(),
+                emails: user
+                    .profile
+                    .email
+                    .and_then(|email| email.parse().ok())
+                    .into_iter()
+                    .collect(),
+                phones: user
+                    .profile
+                    .phone
+                    .filter(|phone| !phone.is_empty())
+                    .map(Into::into)
+                    .into_iter()
+                    .collect(),
+                accounts: vec![user.name],
+                ..Default::default()
+            };
+            output.users.insert(user.id, person);
+        }
+
+        let mut channels = Vec::new();
+        for (file, kind) in [
+            ("channels.json", Kind::Channel),
+            ("groups.json", Kind::Channel),
+            ("mpims.json", Kind::Group),
+            ("dms.json", Kind::Direct),
+        ] {
+            let list: Vec<Channel> = read_json(&directory.join(file))?.unwrap_or_default();
+            channels.extend(list.into_iter().map(|channel| (channel, kind)));
+        }
+        let channel_names: BTreeMap<&str, &str> = channels
+            .iter()
+            .filter_map(|(channel, _)| Some((channel.id.as_str(), channel.name.as_deref()?)))
+            .collect();
+        let resolver = Resolver {
+            names: &names,
+            channels: &channel_names,
+        };
+
+        for (channel, kind) in &channels {
+            let folder = directory.join(channel.name.as_deref().unwrap_or(&channel.id));
+            let Ok(entries) = std::fs::read_dir(&folder) else {
+                continue;
+            };
+            let mut days: Vec<_> = entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.extension()
+                        .is_some_and(|extension| extension == "json")
+                })
+                .collect();
+            days.sort();
+            let mut messages = Vec::new();
+            for day in days {
+                let day: Vec<Message> = read_json(&day)?.unwrap_or_default();
+                messages.extend(day);
+            }
+            output
+                .conversations
+                .push(resolver.conversation(channel, *kind, messages)?);
+        }
+        Ok(output)
+    }
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<Option<T>, crate::Error> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let input = std::fs::read_to_string(path)?;
+    serde_json::from_str(&input).map(Some).map_err(|error| {
+        crate::Error::InvalidProperty("json", format!("{}: {}", path.display(), error))
+    })
+}
+
+struct Resolver<'a> {
+    names: &'a BTreeMap<String, String>,
+    channels: &'a BTreeMap<&'a str, &'a str>,
+}
+
+impl Resolver<'_> {
+    fn handle(&self, user: &str) -> InstantMessageHandle {
+        InstantMessageHandle::Slack(user.into())
+    }
+
+    fn conversation(
+        &self,
+        channel: &Channel,
+        kind: Kind,
+        messages: Vec<Message>,
+    ) -> Result<Conversation, crate::Error> {
+        let members: Vec<InstantMessageHandle> = channel
+            .members
+            .iter()
+            .map(|user| self.handle(user))
+            .collect();
+        let group = InstantMessageGroup {
+            id: channel.id.clone(),
+            platform: Some(InstantMessagePlatform::Slack),
+            name: channel.name.clone(),
+            members: members.clone(),
+        };
+        let mut conversation = Conversation {
+            id: (kind != Kind::Direct).then(|| group.to_uri()),
+            name: channel.name.clone(),
+            platform: Some(InstantMessagePlatform::Slack),
+            participants: members.clone(),
+            ..Default::default()
+        };
+        for message in messages {
+            let subtype = message.subtype.as_deref().unwrap_or_default();
+            // Skip joins, leaves and renames, which aren't messages:
+            if subtype.starts_with("channel_") || subtype.starts_with("group_") {
+                continue;
+            }
+            let from = match (&message.user, &message.bot_id, &message.username) {
+                (Some(user), _, _) => self.handle(user),
+                (None, Some(bot), _) => InstantMessageHandle::Slack(bot.clone()),
+                (None, None, Some(username)) => InstantMessageHandle::Slack(username.clone()),
+                (None, None, None) => continue,
+            };
+            let to = match kind {
+                Kind::Channel => InstantMessageRecipient::Channel(group.clone()),
+                Kind::Group => InstantMessageRecipient::Group(group.clone()),
+                Kind::Direct => InstantMessageRecipient::Direct(
+                    members
+                        .iter()
+                        .find(|member| **member != from)
+                        .unwrap_or(&from)
+                        .clone(),
+                ),
+            };
+            let mut output = InstantMessage::new(
+                timestamp(&message.ts)?,
+                from,
+                to,
+                self.mrkdwn_to_text(&message.text),
+            );
+            output.id = Some(message.ts.as_str().into());
+            output.platform = Some(InstantMessagePlatform::Slack);
+            output.references = message
+                .thread_ts
+                .filter(|thread| *thread != message.ts)
+                .map(Into::into);
+            output.deleted = subtype == "tombstone";
+            if let Some(edited) = message.edited {
+                output.edited = Some(timestamp(&edited.ts)?);
+            }
+            for reaction in message.reactions {
+                for user in reaction.users {
+                    let emoji = format!(":{}:", reaction.name);
+                    let reaction = InstantMessageReaction::new(emoji, self.handle(&user));
+                    output.reactions.push(reaction);
+                }
+            }
+            for file in message.files {
+                output
+                    .attachments
+                    .push(InstantMessageAttachment::File(File {
+                        id: file.url_private.or(file.id),
+                        name: file.name,
+                        size: file.size,
+                        media_type: file.mimetype,
+                        ..Default::default()
+                    }));
+            }
+            conversation.push(output);
+        }
+        Ok(conversation)
+    }
+
+    /// Converts Slack's `mrkdwn` to plain text, resolving mentions and
+    /// removing the formatting markers.
+    ///
+    /// See: https://docs.slack.dev/messaging/formatting-message-text
+    fn mrkdwn_to_text(&self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len());
+        let mut rest = input;
+        while let Some(start) = rest.find('<') {
+            let Some(end) = rest[start..].find('>') else {
+                break;
+            };
+            output.push_str(&rest[..start]);
+            let (target, label) = match rest[start + 1..start + end].split_once('|') {
+                Some((target, label)) => (target, Some(label)),
+                None => (&rest[start + 1..start + end], None),
+            };
+            match (target.chars().next(), label) {
+                (Some('@'), Some(label)) => output.push_str(&format!("@{}", label)),
+                (Some('@'), None) => {
+                    let name = self.names.get(&target[1..]).map(String::as_str);
+                    output.push_str(&format!("@{}", name.unwrap_or(&target[1..])));
+                },
+                (Some('#'), Some(label)) => output.push_str(&format!("#{}", label)),
+                (Some('#'), None) => {
+                    let name = self.channels.get(&target[1..]).copied();
+                    output.push_str(&format!("#{}", name.unwrap_or(&target[1..])));
+                },
+                (Some('!'), Some(label)) => output.push_str(label),
+                (Some('!'), None) => output.push_str(&format!("@{}", &target[1..])),
+                (_, Some(label)) if label != target => {
+                    output.push_str(&format!("{} ({})", label, target))
+                },
+                _ => output.push_str(target.strip_prefix("mailto:").unwrap_or(target)),
+            }
+            rest = &rest[start + end + 1..];
+        }
+        output.push_str(rest);
+        strip_formatting(&output)
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&")
+    }
+}
+
+/// Parses a Slack timestamp, e.g., `1704103200.000200`, which is also the
+/// message's identifier in its channel.
+fn timestamp(ts: &str) -> Result<DateTime, crate::Error> {
+    let (seconds, micros) = ts.split_once('.').unwrap_or((ts, "0"));
+    let seconds: i64 = seconds
+        .parse()
+        .map_err(|_| crate::Error::InvalidProperty("ts", ts.into()))?;
+    if !micros.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(crate::Error::InvalidProperty("ts", ts.into()));
+    }
+    let micros: i32 = format!("{:0<6}", micros)[..6]
+        .parse()
+        .map_err(|_| crate::Error::InvalidProperty("ts", ts.into()))?;
+    jiff::Timestamp::new(seconds, micros * 1000)
+        .map(DateTime::from)
+        .map_err(|_| crate::Error::InvalidProperty("ts", ts.into()))
+}
+
+/// Removes the `*bold*`, `_italic_` and `~strike~` markers that enclose
+/// words, leaving code spans and markers inside words, e.g., in
+/// `snake_case`, as they are.
+fn strip_formatting(input: &str) -> String {
+    let chars: Vec<char> = input.chars().collect();
+    let mut skip = vec![false; chars.len()];
+    let mut in_code = false;
+    for (i, &c) in chars.iter().enumerate() {
+        if c == '`' {
+            in_code = !in_code;
+        } else if !in_code && matches!(c, '*' | '_' | '~') {
+            let opens = (i == 0 || !chars[i - 1].is_alphanumeric())
+                && chars
+                    .get(i + 1)
+                    .is_some_and(|next| !next.is_whitespace() && *next != c);
+            let close = opens
+                .then(|| {
+                    (i + 2..chars.len())
+                        .take_while(|j| chars[*j] != '\n')
+                        .find(|j| {
+                            chars[*j] == c
+                                && !chars[j - 1].is_whitespace()
+                                && chars.get(j + 1).is_none_or(|next| !next.is_alphanumeric())
+                        })
+                })
+                .flatten();
+            if let Some(close) = close {
+                skip[i] = true;
+                skip[close] = true;
+            }
+        }
+    }
+    chars
+        .into_iter()
+        .zip(skip)
+        .filter_map(|(c, skip)| (!skip).then_some(c))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classes::{PersonLike, ThingLike};
+
+    #[test]
+    fn test_open() {
+        let export = SlackExport::open(concat!(env!("CARGO_MANIFEST_DIR"), "/test/slack")).unwrap();
+        let alice = &export.users["U0A11CE"];
+        assert_eq!(alice.id(), Some("im:slack:U0A11CE"));
+        assert_eq!(alice.accounts, ["alice"]);
+        assert_eq!(alice.name.as_str(), "Alice Smith");
+        assert_eq!(alice.email().unwrap().as_str(), "alice@example.com");
+
+        let general = &export.conversations[0];
+        assert_eq!(general.name.as_deref(), Some("general"));
+        assert_eq!(general.messages.len(), 2);
+        let message = &general.messages[0];
+        assert_eq!(message.to.inline().to_string(), "📢 general");
+        assert_eq!(
+            message.content,
+            "Hi @bob, the Q4 report is in #general & on the wiki (https://example.com/q4)"
+        );
+        assert_eq!(message.date.to_string(), "2024-01-01T10:00:00.000+00:00");
+        assert_eq!(message.edited, Some(1_704_103_260.into()));
+        assert_eq!(message.reaction_counts(), [(":tada:", 1)]);
+        let reply = &general.messages[1];
+        assert_eq!(reply.references, message.id);
+        assert_eq!(
+            reply.content,
+            "Thanks! Here are my notes, see `snake_case_name` for details"
+        );
+        let InstantMessageAttachment::File(ref file) = reply.attachments[0] else {
+            panic!("expected a file");
+        };
+        assert_eq!(file.name.as_deref(), Some("notes.pdf"));
+        assert_eq!(file.size, 2048);
+
+        let dm = &export.conversations[1];
+        assert_eq!(
+            dm.messages[0].to,
+            InstantMessageRecipient::Direct(InstantMessageHandle::Slack("U0A11CE".into()))
+        );
+        assert_eq!(dm.messages[0].content, "Lunch tomorrow?\n> quoted");
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(
+            timestamp("1704103200.000200").unwrap().to_string(),
+            "2024-01-01T10:00:00.000+00:00"
+        );
+        assert_eq!(
+            timestamp("1704103200.5").unwrap(),
+            timestamp("1704103200.500").unwrap()
+        );
+        assert!(timestamp("1704103200.00000é").is_err());
+        assert!(timestamp("1704103200.+5").is_err());
+    }
+
+    #[test]
+    fn test_strip_formatting() {
+        assert_eq!(strip_formatting("*bold* _it_ ~no~"), "bold it no");
+        assert_eq!(strip_formatting("2 * 3 * 4"), "2 * 3 * 4");
+        assert_eq!(strip_formatting("snake_case_name"), "snake_case_name");
+        assert_eq!(strip_formatting("`*code*` *x*"), "`*code*` x");
+    }
+}
@@ -0,0 +1,435 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{
+    classes::{Conversation, File, Image, InstantMessage},
+    datatypes::{
+        DateTime, InstantMessageAttachment, InstantMessageGroup, InstantMessageHandle,
+        InstantMessagePlatform, InstantMessageRecipient, PhoneNumber,
+    },
+};
+use alloc::borrow::Cow;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use roxmltree::Node;
+use std::path::Path;
+
+/// Options for importing an SMS Backup & Restore file.
+#[derive(Clone, Debug, Default)]
+pub struct SmsBackupOptions {
+    /// The phone number of the backed-up phone, which sent the outgoing
+    /// messages, detected from its sent MMS if `None`. Importing a backup
+    /// without any fails unless this is set.
+    pub phone: Option<PhoneNumber>,
+
+    /// Whether to import draft messages, which were never sent.
+    pub drafts: bool,
+}
+
+/// Conversations imported from an Android "SMS Backup & Restore" XML file,
+/// i.e., an `<smses>` element with `<sms>` and `<mms>` elements, one
+/// conversation for each set of correspondents.
+///
+/// See: https://www.synctech.com.au/sms-backup-restore/fields-in-xml-backup-files/
+#[derive(Clone, Debug, Default)]
+pub struct SmsBackup {
+    pub conversations: Vec<Conversation>,
+}
+
+/// The direction of a message, from the `type` attribute of `<sms>` and
+/// the `msg_box` attribute of `<mms>`.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Direction {
+    Received,
+    Sent,
+    Draft,
+}
+
+/// The `type` attribute of an `<addr>` in an `<mms>`.
+const MMS_FROM: &str = "137";
+
+/// The placeholder an `<mms>` being sent has for the phone's own number.
+const MMS_OWN_ADDRESS: &str = "insert-address-token";
+
+impl SmsBackup {
+    /// Imports an `sms-*.xml` backup file.
+    pub fn open(path: impl AsRef<Path>, options: &SmsBackupOptions) -> Result<Self, crate::Error> {
+        Self::parse(&std::fs::read_to_string(path)?, options)
+    }
+
+    /// Parses the contents of a backup file.
+    pub fn parse(input: &str, options: &SmsBackupOptions) -> Result<Self, crate::Error> {
+        let input = decode_surrogates(input);
+        let document = roxmltree::Document::parse(&input)?;
+        let root = document.root_element();
+        let me = match options.phone {
+            Some(ref phone) => phone.clone(),
+            None => detect_phone(root).ok_or(crate::Error::MissingProperty("phone"))?,
+        };
+
+        let mut output = Self::default();
+        let mut keys: Vec<Vec<PhoneNumber>> = Vec::new();
+        for node in root.children().filter(Node::is_element) {
+            let message = match node.tag_name().name() {
+                "sms" => convert_sms(node, &me, options)?,
+                "mms" => convert_mms(node, &me, options)?,
+                _ => None,
+            };
+            let Some((mut others, message)) = message else {
+                continue;
+            };
+            others.sort();
+            let index = match keys.iter().position(|key| *key == others) {
+                Some(index) => index,
+                None => {
+                    keys.push(others);
+                    output.conversations.push(Conversation {
+                        name: contact_name(node),
+                        ..Default::default()
+                    });
+                    keys.len() - 1
+                },
+            };
+            output.conversations[index].push(message);
+        }
+        Ok(output)
+    }
+}
+
+/// Converts an `<sms>`, returning the other correspondent and the message.
+fn convert_sms(
+    node: Node,
+    me: &PhoneNumber,
+    options: &SmsBackupOptions,
+) -> Result<Option<(Vec<PhoneNumber>, InstantMessage)>, crate::Error> {
+    let direction = match required(node, "type")? {
+        "1" => Direction::Received,
+        "3" => Direction::Draft,
+        _ => Direction::Sent,
+    };
+    if direction == Direction::Draft && !options.drafts {
+        return Ok(None);
+    }
+    let address = phone(required(node, "address")?);
+    let (from, to) = match direction {
+        Direction::Received => (address.clone(), me.clone()),
+        _ => (me.clone(), address.clone()),
+    };
+    let mut message = InstantMessage::new(
+        date(node)?,
+        InstantMessageHandle::Phone(from),
+        InstantMessageRecipient::Direct(InstantMessageHandle::Phone(to)),
+        node.attribute("body").unwrap_or_default(),
+    );
+    message.platform = Some(InstantMessagePlatform::Sms);
+    Ok(Some((vec![address], message)))
+}
+
+/// Converts an `<mms>`, returning its correspondents other than the phone
+/// itself and the message.
+fn convert_mms(
+    node: Node,
+    me: &PhoneNumber,
+    options: &SmsBackupOptions,
+) -> Result<Option<(Vec<PhoneNumber>, InstantMessage)>, crate::Error> {
+    let direction = match required(node, "msg_box")? {
+        "1" => Direction::Received,
+        "3" => Direction::Draft,
+        _ => Direction::Sent,
+    };
+    if direction == Direction::Draft && !options.drafts {
+        return Ok(None);
+    }
+    // Skip delivery and read reports, which aren't messages:
+    if !matches!(attribute(node, "m_type"), None | Some("128" | "132")) {
+        return Ok(None);
+    }
+
+    let mut sender = None;
+    let mut members: Vec<PhoneNumber> = Vec::new();
+    let addrs: Vec<Node> = children(node, "addrs").collect();
+    if addrs.is_empty() {
+        members.extend(required(node, "address")?.split('~').map(phone));
+    }
+    for addr in addrs {
+        let address = required(addr, "address")?;
+        if address == MMS_OWN_ADDRESS {
+            continue;
+        }
+        let address = phone(address);
+        if addr.attribute("type") == Some(MMS_FROM) && sender.is_none() {
+            sender = Some(address.clone());
+        }
+        if !members.contains(&address) {
+            members.push(address);
+        }
+    }
+    let from = match direction {
+        Direction::Received => sender.unwrap_or_default(),
+        _ => me.clone(),
+    };
+    let others: Vec<PhoneNumber> = members
+        .iter()
+        .filter(|member| *member != me)
+        .cloned()
+        .collect();
+    if !me.as_str().is_empty() && !members.contains(me) {
+        members.push(me.clone());
+    }
+    let to = match others.as_slice() {
+        [other] => InstantMessageRecipient::Direct(InstantMessageHandle::Phone(match direction {
+            Direction::Received => me.clone(),
+            _ => other.clone(),
+        })),
+        _ => {
+            let mut id: Vec<&str> = others.iter().map(PhoneNumber::as_str).collect();
+            id.sort();
+            InstantMessageRecipient::Group(InstantMessageGroup {
+                id: id.join("~"),
+                platform: Some(InstantMessagePlatform::Sms),
+                name: contact_name(node),
+                members: members
+                    .into_iter()
+                    .map(InstantMessageHandle::Phone)
+                    .collect(),
+            })
+        },
+    };
+
+    let mut message = InstantMessage::new(date(node)?, InstantMessageHandle::Phone(from), to, "");
+    message.platform = Some(InstantMessagePlatform::Sms);
+    let mut texts = Vec::new();
+    for part in children(node, "parts") {
+        match required(part, "ct")? {
+            "application/smil" => continue,
+            "text/plain" => texts.extend(part.attribute("text")),
+            media_type => message.attachments.push(attachment(part, media_type)?),
+        }
+    }
+    message.content = texts.join("\n");
+    Ok(Some((others, message)))
+}
+
+/// Converts a non-text part of an `<mms>`, whose contents are in Base64.
+fn attachment(part: Node, media_type: &str) -> Result<InstantMessageAttachment, crate::Error> {
+    let data = match part.attribute("data") {
+        Some(data) => STANDARD
+            .decode(data)
+            .map_err(|error| crate::Error::InvalidProperty("data", error.to_string()))?,
+        None => Vec::new(),
+    };
+    let name = attribute(part, "cl")
+        .or_else(|| attribute(part, "name"))
+        .or_else(|| attribute(part, "fn"));
+    if media_type.starts_with("image/") {
+        return Ok(InstantMessageAttachment::Image(Image {
+            data,
+            ..Default::default()
+        }));
+    }
+    Ok(InstantMessageAttachment::File(File {
+        name: name.map(Into::into),
+        size: data.len() as u64,
+        media_type: Some(media_type.into()),
+        data,
+        ..Default::default()
+    }))
+}
+
+/// Returns the phone's own number from the sender of the first sent
+/// `<mms>` that has it.
+fn detect_phone(root: Node) -> Option<PhoneNumber> {
+    root.children()
+        .filter(|node| node.has_tag_name("mms") && node.attribute("msg_box") == Some("2"))
+        .flat_map(|node| children(node, "addrs"))
+        .filter(|addr| addr.attribute("type") == Some(MMS_FROM))
+        .filter_map(|addr| addr.attribute("address"))
+        .find(|address| *address != MMS_OWN_ADDRESS)
+        .map(phone)
+}
+
+/// Returns the child elements of an element's `<parts>` or `<addrs>`.
+fn children<'a, 'input>(
+    node: Node<'a, 'input>,
+    container: &'static str,
+) -> impl Iterator<Item = Node<'a, 'input>> {
+    node.children()
+        .filter(move |child| child.has_tag_name(container))
+        .flat_map(|child| child.children().filter(Node::is_element))
+}
+
+/// Returns an optional attribute, which the backups write as `null` when
+/// it's absent.
+fn attribute<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attribute(name)
+        .filter(|value| !value.is_empty() && *value != "null")
+}
+
+fn required<'a>(node: Node<'a, '_>, name: &'static str) -> Result<&'a str, crate::Error> {
+    node.attribute(name)
+        .ok_or(crate::Error::MissingProperty(name))
+}
+
+/// Returns the name of the correspondent from the phone's contacts, which
+/// the backups write as `(Unknown)` when there's none.
+fn contact_name(node: Node) -> Option<String> {
+    attribute(node, "contact_name")
+        .filter(|name| *name != "(Unknown)")
+        .map(|name| name.replace('~', ", "))
+}
+
+/// Parses the `date` of a message, in milliseconds since the Unix epoch.
+fn date(node: Node) -> Result<DateTime, crate::Error> {
+    let input = required(node, "date")?;
+    input
+        .parse::<i64>()
+        .ok()
+        .and_then(|millis| jiff::Timestamp::from_millisecond(millis).ok())
+        .map(DateTime::from)
+        .ok_or_else(|| crate::Error::InvalidProperty("date", input.into()))
+}
+
+/// Normalizes a phone number by removing its punctuation, e.g., from
+/// `+1 (555) 123-4567` to `+15551234567`, keeping alphanumeric sender
+/// names, e.g., `AMAZON`, as they are.
+fn phone(input: &str) -> PhoneNumber {
+    let input = input.trim();
+    if input.chars().any(|c| c.is_alphabetic()) {
+        return input.into();
+    }
+    input
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == '+')
+        .collect::<String>()
+        .into()
+}
+
+/// Decodes the character references to UTF-16 surrogate pairs that the
+/// backups write for characters outside the Basic Multilingual Plane,
+/// e.g., `&#55357;&#56832;` for 😀, which aren't valid XML.
+fn decode_surrogates(input: &str) -> Cow<'_, str> {
+    fn reference(input: &str) -> Option<(u32, &str)> {
+        let (digits, rest) = input.strip_prefix("&#")?.split_once(';')?;
+        Some((digits.parse().ok()?, rest))
+    }
+
+    if !input.contains("&#55") && !input.contains("&#56") && !input.contains("&#57") {
+        return Cow::Borrowed(input);
+    }
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("&#") {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match reference(rest) {
+            Some((high @ 0xD800..=0xDBFF, after)) => {
+                let (c, after) = match reference(after) {
+                    Some((low @ 0xDC00..=0xDFFF, after)) => {
+                        let c = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                        (char::from_u32(c), after)
+                    },
+                    _ => (None, after),
+                };
+                output.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+                rest = after;
+            },
+            Some((0xDC00..=0xDFFF, after)) => {
+                output.push(char::REPLACEMENT_CHARACTER);
+                rest = after;
+            },
+            _ => {
+                output.push_str("&#");
+                rest = &rest[2..];
+            },
+        }
+    }
+    output.push_str(rest);
+    Cow::Owned(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let backup = SmsBackup::parse(
+            include_str!("../../../test/sms/sms-20240102.xml"),
+            &SmsBackupOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(backup.conversations.len(), 2);
+
+        let chat = &backup.conversations[0];
+        assert_eq!(chat.name.as_deref(), Some("Bob"));
+        assert_eq!(chat.messages.len(), 3);
+        let received = &chat.messages[0];
+        assert_eq!(received.platform, Some(InstantMessagePlatform::Sms));
+        assert_eq!(received.date, 1_704_103_200.into());
+        assert_eq!(
+            received.from,
+            InstantMessageHandle::Phone("+15551234567".into())
+        );
+        assert_eq!(
+            received.to,
+            InstantMessageRecipient::Direct(InstantMessageHandle::Phone("+15550000000".into()))
+        );
+        assert_eq!(received.content, "Hi! Lunch today? 😀");
+        let sent = &chat.messages[1];
+        assert_eq!(
+            sent.from,
+            InstantMessageHandle::Phone("+15550000000".into())
+        );
+        assert_eq!(sent.content, "Sure, <noon> & the usual place");
+        let photo = &chat.messages[2];
+        assert_eq!(photo.content, "Here");
+        let InstantMessageAttachment::Image(ref image) = photo.attachments[0] else {
+            panic!("expected an image");
+        };
+        assert_eq!(image.data, b"GIF89a");
+
+        let group = &backup.conversations[1];
+        let message = &group.messages[0];
+        assert_eq!(
+            message.from,
+            InstantMessageHandle::Phone("+15557654321".into())
+        );
+        let InstantMessageRecipient::Group(ref to) = message.to else {
+            panic!("expected a group");
+        };
+        assert_eq!(to.id, "+15551234567~+15557654321");
+        assert_eq!(to.members.len(), 3);
+        assert_eq!(group.name.as_deref(), Some("Bob, Carol"));
+        let InstantMessageAttachment::File(ref file) = message.attachments[0] else {
+            panic!("expected a file");
+        };
+        assert_eq!(file.name.as_deref(), Some("carol.vcf"));
+        assert_eq!(file.media_type.as_deref(), Some("text/x-vCard"));
+        assert_eq!(file.size, 11);
+    }
+
+    #[test]
+    fn test_parse_without_own_number() {
+        let input = r#"<smses count="1">
+  <sms address="+15551234567" date="1704103200000" type="1" body="Hi" />
+</smses>"#;
+        assert!(matches!(
+            SmsBackup::parse(input, &SmsBackupOptions::default()),
+            Err(crate::Error::MissingProperty("phone"))
+        ));
+        let options = SmsBackupOptions {
+            phone: Some("+15550000000".into()),
+            ..Default::default()
+        };
+        let backup = SmsBackup::parse(input, &options).unwrap();
+        assert_eq!(
+            backup.conversations[0].messages[0].to,
+            InstantMessageRecipient::Direct(InstantMessageHandle::Phone("+15550000000".into()))
+        );
+    }
+
+    #[test]
+    fn test_decode_surrogates() {
+        assert_eq!(decode_surrogates("a &amp; b"), "a &amp; b");
+        assert_eq!(decode_surrogates("&#55357;&#56832;!"), "😀!");
+        assert_eq!(decode_surrogates("&#55357; &#233;"), "\u{fffd} &#233;");
+    }
+}
@@ -0,0 +1,528 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{
+    classes::{Conversation, File, Image, InstantMessage, Person},
+    datatypes::{
+        DateTime, InstantMessageAttachment, InstantMessageGroup, InstantMessageHandle,
+        InstantMessageId, InstantMessagePlatform, InstantMessageReaction, InstantMessageRecipient,
+    },
+};
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::Path};
+
+/// Chats imported from a Telegram Desktop "Export Telegram data" in the
+/// machine-readable JSON format, i.e., a `result.json` file and the media
+/// directories next to it.
+///
+/// See: https://telegram.org/blog/export-and-more
+#[derive(Clone, Debug, Default)]
+pub struct TelegramExport {
+    /// The owner of the account and the senders, by Telegram ID, e.g.,
+    /// `user123456789`.
+    pub users: BTreeMap<String, Person>,
+
+    pub chats: Vec<TelegramChat>,
+}
+
+/// A chat in a Telegram export.
+#[derive(Clone, Debug, Default)]
+pub struct TelegramChat {
+    pub conversation: Conversation,
+    pub service_messages: Vec<TelegramServiceMessage>,
+}
+
+/// A service message in a Telegram chat, e.g., a member joining, a pinned
+/// message, or a changed title.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct TelegramServiceMessage {
+    pub date: DateTime,
+    pub actor: Option<InstantMessageHandle>,
+
+    /// The kind of event, e.g., `invite_members`, `pin_message` or
+    /// `edit_group_title`.
+    pub action: String,
+
+    /// The new title of the chat, if the action set one.
+    pub title: Option<String>,
+
+    /// The display names of the members the action was about.
+    pub members: Vec<String>,
+
+    /// The message the action was about, e.g., the pinned message.
+    pub references: Option<InstantMessageId>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Export {
+    Full {
+        personal_information: Option<PersonalInformation>,
+        chats: ChatList,
+    },
+    Chat(Chat),
+}
+
+#[derive(Deserialize)]
+struct PersonalInformation {
+    user_id: i64,
+    #[serde(default)]
+    first_name: String,
+    #[serde(default)]
+    last_name: String,
+    #[serde(default)]
+    phone_number: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ChatList {
+    list: Vec<Chat>,
+}
+
+#[derive(Deserialize)]
+struct Chat {
+    name: Option<String>,
+    r#type: String,
+    id: i64,
+    #[serde(default)]
+    messages: Vec<Message>,
+}
+
+#[derive(Deserialize)]
+struct Message {
+    id: i64,
+    r#type: String,
+    date: String,
+    date_unixtime: Option<String>,
+    edited_unixtime: Option<String>,
+    from: Option<String>,
+    from_id: Option<String>,
+    actor: Option<String>,
+    actor_id: Option<String>,
+    action: Option<String>,
+    title: Option<String>,
+    #[serde(default)]
+    members: Vec<Option<String>>,
+    message_id: Option<i64>,
+    reply_to_message_id: Option<i64>,
+    forwarded_from: Option<String>,
+    #[serde(default)]
+    text_entities: Vec<TextEntity>,
+    photo: Option<String>,
+    width: Option<usize>,
+    height: Option<usize>,
+    file: Option<String>,
+    media_type: Option<String>,
+    mime_type: Option<String>,
+    #[serde(default)]
+    reactions: Vec<Reaction>,
+}
+
+#[derive(Deserialize)]
+struct TextEntity {
+    r#type: String,
+    text: String,
+    href: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Reaction {
+    emoji: Option<String>,
+    document_id: Option<String>,
+    #[serde(default)]
+    recent: Vec<ReactionSender>,
+}
+
+#[derive(Deserialize)]
+struct ReactionSender {
+    from: Option<String>,
+    from_id: Option<String>,
+    date: Option<String>,
+}
+
+impl TelegramExport {
+    /// Imports an export directory, or a `result.json` file in one,
+    /// linking the attachments to the media files that were exported.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, crate::Error> {
+        let path = path.as_ref();
+        let (directory, file) = match path.is_dir() {
+            true => (path.to_path_buf(), path.join("result.json")),
+            false => (
+                path.parent().map(Path::to_path_buf).unwrap_or_default(),
+                path.to_path_buf(),
+            ),
+        };
+        let mut output = Self::parse(&std::fs::read_to_string(file)?)?;
+        output.link_media(&directory)?;
+        Ok(output)
+    }
+
+    /// Parses the contents of a `result.json` file, which is either a full
+    /// export or a single chat's export.
+    pub fn parse(input: &str) -> Result<Self, crate::Error> {
+        let export: Export = serde_json::from_str(input)
+            .map_err(|error| crate::Error::InvalidProperty("result.json", error.to_string()))?;
+        let mut output = Self::default();
+        let (me, chats) = match export {
+            Export::Full {
+                personal_information,
+                chats,
+            } => (personal_information, chats.list),
+            Export::Chat(chat) => (None, vec![chat]),
+        };
+        let me = me.map(|me| {
+            let id = format!("user{}", me.user_id);
+            let handle = handle(&id);
+            let name = format!("{} {}", me.first_name, me.last_name);
+            let person = Person {
+                id: Some(handle.to_uri()),
+                name: name.trim().into(),
+                phones: me
+                    .phone_number
+                    .filter(|phone| !phone.is_empty())
+                    .map(Into::into)
+                    .into_iter()
+                    .collect(),
+                accounts: me
+                    .username
+                    .filter(|username| !username.is_empty())
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            };
+            output.users.insert(id, person);
+            handle
+        });
+        for chat in chats {
+            output.add_users(&chat);
+            output.chats.push(convert_chat(chat, me.as_ref())?);
+        }
+        Ok(output)
+    }
+
+    /// Records the display names of the senders of a chat, keeping the
+    /// last one seen when they were renamed.
+    fn add_users(&mut self, chat: &Chat) {
+        let senders = chat.messages.iter().flat_map(|message| {
+            let reactions = message
+                .reactions
+                .iter()
+                .flat_map(|reaction| &reaction.recent);
+            let reactions = reactions.map(|sender| (&sender.from, &sender.from_id));
+            [
+                (&message.from, &message.from_id),
+                (&message.actor, &message.actor_id),
+            ]
+            .into_iter()
+            .chain(reactions)
+        });
+        for (name, id) in senders {
+            let (Some(name), Some(id)) = (name, id) else {
+                continue;
+            };
+            if name.is_empty() {
+                continue;
+            }
+            let person = self.users.entry(id.clone()).or_insert_with(|| Person {
+                id: Some(handle(id).to_uri()),
+                ..Default::default()
+            });
+            person.name = name.as_str().into();
+        }
+    }
+
+    /// Fills in the sizes of the file attachments that were exported.
+    fn link_media(&mut self, directory: &Path) -> Result<(), crate::Error> {
+        let conversations = self.chats.iter_mut().map(|chat| &mut chat.conversation);
+        let messages = conversations.flat_map(|conversation| &mut conversation.messages);
+        for attachment in messages.flat_map(|message| &mut message.attachments) {
+            let InstantMessageAttachment::File(file) = attachment else {
+                continue;
+            };
+            let Some(path) = file.name.as_deref().map(|name| directory.join(name)) else {
+                continue;
+            };
+            if path.is_file() {
+                file.size = std::fs::metadata(path)?.len();
+            }
+        }
+        Ok(())
+    }
+}
+
+fn convert_chat(
+    chat: Chat,
+    me: Option<&InstantMessageHandle>,
+) -> Result<TelegramChat, crate::Error> {
+    let group = InstantMessageGroup {
+        id: chat.id.to_string(),
+        platform: Some(InstantMessagePlatform::Telegram),
+        name: chat.name.clone(),
+        members: Vec::new(),
+    };
+    let (messages, services): (Vec<Message>, Vec<Message>) = chat
+        .messages
+        .into_iter()
+        .filter(|message| matches!(message.r#type.as_str(), "message" | "service"))
+        .partition(|message| message.r#type == "message");
+    let mut members: Vec<InstantMessageHandle> = Vec::new();
+    for message in &messages {
+        let sender = sender(message.from.as_deref(), message.from_id.as_deref());
+        if !members.contains(&sender) {
+            members.push(sender);
+        }
+    }
+    // The ID of a personal chat is the ID of the other user:
+    let chat_handle = handle(&format!("user{}", chat.id));
+    let to = |sender: &InstantMessageHandle| match chat.r#type.as_str() {
+        "personal_chat" | "bot_chat" | "saved_messages" => {
+            let other = members
+                .iter()
+                .chain(me)
+                .chain([&chat_handle])
+                .find(|other| *other != sender)
+                .unwrap_or(sender);
+            InstantMessageRecipient::Direct(other.clone())
+        },
+        "private_channel" | "public_channel" => {
+            InstantMessageRecipient::Channel(InstantMessageGroup {
+                members: members.clone(),
+                ..group.clone()
+            })
+        },
+        _ => InstantMessageRecipient::Group(InstantMessageGroup {
+            members: members.clone(),
+            ..group.clone()
+        }),
+    };
+
+    let mut service_messages = Vec::new();
+    for service in services {
+        service_messages.push(TelegramServiceMessage {
+            date: date(service.date_unixtime.as_deref(), &service.date)?,
+            actor: service
+                .actor_id
+                .is_some()
+                .then(|| sender(service.actor.as_deref(), service.actor_id.as_deref())),
+            action: service.action.unwrap_or_default(),
+            title: service.title,
+            members: service.members.into_iter().flatten().collect(),
+            references: service.message_id.map(|id| id.to_string().into()),
+        });
+    }
+
+    let mut conversation = Conversation {
+        name: chat.name.clone(),
+        ..Default::default()
+    };
+    for message in &messages {
+        let from = sender(message.from.as_deref(), message.from_id.as_deref());
+        let mut output = InstantMessage::new(
+            date(message.date_unixtime.as_deref(), &message.date)?,
+            from.clone(),
+            to(&from),
+            text(&message.text_entities),
+        );
+        output.id = Some(message.id.to_string().into());
+        output.platform = Some(InstantMessagePlatform::Telegram);
+        output.references = message.reply_to_message_id.map(|id| id.to_string().into());
+        output.forwarded_from = message
+            .forwarded_from
+            .as_deref()
+            .map(|name| sender(Some(name), None));
+        output.edited = match message.edited_unixtime.as_deref() {
+            Some(edited) => Some(date(Some(edited), "")?),
+            None => None,
+        };
+        output.attachments.extend(attachment(message));
+        for reaction in &message.reactions {
+            let Some(emoji) = reaction.emoji.as_ref().or(reaction.document_id.as_ref()) else {
+                continue;
+            };
+            for recent in &reaction.recent {
+                let mut output_reaction = InstantMessageReaction::new(
+                    emoji.as_str(),
+                    sender(recent.from.as_deref(), recent.from_id.as_deref()),
+                );
+                output_reaction.date = match recent.date.as_deref() {
+                    Some(date) => Some(local_date(date)?),
+                    None => None,
+                };
+                output.reactions.push(output_reaction);
+            }
+        }
+        conversation.push(output);
+    }
+    Ok(TelegramChat {
+        conversation,
+        service_messages,
+    })
+}
+
+/// Returns the handle of a sender, preferring their stable identifier,
+/// e.g., `user123456789`, to their display name, which only forwarded
+/// messages lack an identifier for.
+fn sender(name: Option<&str>, id: Option<&str>) -> InstantMessageHandle {
+    match id {
+        Some(id) => handle(id),
+        None => InstantMessageHandle::Username(name.unwrap_or_default().into()),
+    }
+}
+
+/// Returns the handle of a Telegram ID, e.g., `user123456789` as the
+/// numeric user ID. Other peers, e.g., `channel123456789`, keep their
+/// prefix, as their numbers can coincide with user IDs.
+fn handle(id: &str) -> InstantMessageHandle {
+    match id.strip_prefix("user") {
+        Some(number) if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) => {
+            InstantMessageHandle::Telegram(number.into())
+        },
+        _ => InstantMessageHandle::Telegram(id.into()),
+    }
+}
+
+/// Parses a message timestamp, preferring the Unix time to the exporting
+/// computer's local time, which lacks a time zone.
+fn date(unixtime: Option<&str>, local: &str) -> Result<DateTime, crate::Error> {
+    match unixtime {
+        Some(unixtime) => unixtime
+            .parse::<i64>()
+            .ok()
+            .and_then(|seconds| jiff::Timestamp::from_second(seconds).ok())
+            .map(DateTime::from)
+            .ok_or_else(|| crate::Error::InvalidProperty("date_unixtime", unixtime.into())),
+        None => local_date(local),
+    }
+}
+
+fn local_date(input: &str) -> Result<DateTime, crate::Error> {
+    jiff::civil::DateTime::strptime("%FT%T", input)
+        .and_then(|date| date.to_zoned(jiff::tz::TimeZone::UTC))
+        .map(DateTime::from)
+        .map_err(|_| crate::Error::InvalidProperty("date", input.into()))
+}
+
+/// Flattens the rich text entities, keeping the targets of links whose
+/// text differs from them.
+fn text(entities: &[TextEntity]) -> String {
+    let mut output = String::new();
+    for entity in entities {
+        output.push_str(&entity.text);
+        if entity.r#type == "text_link"
+            && let Some(ref href) = entity.href
+            && *href != entity.text
+        {
+            output.push_str(&format!(" ({})", href));
+        }
+    }
+    output
+}
+
+/// Returns the photo or file of a message, ignoring the placeholders of
+/// media that wasn't exported.
+fn attachment(message: &Message) -> Option<InstantMessageAttachment> {
+    let exported = |path: &&String| !path.starts_with("(File not included");
+    if let Some(photo) = message.photo.as_ref() {
+        return Some(InstantMessageAttachment::Image(Image {
+            width: message.width,
+            height: message.height,
+            source: Some(photo).filter(exported).cloned(),
+            ..Default::default()
+        }));
+    }
+    let file = message.file.as_ref()?;
+    match message.media_type.as_deref() {
+        Some("sticker") => Some(InstantMessageAttachment::Sticker(Image {
+            width: message.width,
+            height: message.height,
+            source: Some(file).filter(exported).cloned(),
+            ..Default::default()
+        })),
+        _ => Some(InstantMessageAttachment::File(File {
+            name: Some(file).filter(exported).cloned(),
+            media_type: message.mime_type.clone(),
+            ..Default::default()
+        })),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classes::{PersonLike, ThingLike};
+
+    #[test]
+    fn test_open() {
+        let export =
+            TelegramExport::open(concat!(env!("CARGO_MANIFEST_DIR"), "/test/telegram")).unwrap();
+        assert_eq!(export.chats.len(), 3);
+        let alice = &export.users["user111111111"];
+        assert_eq!(alice.id(), Some("tg://user?id=111111111"));
+        assert_eq!(alice.name.as_str(), "Alice Smith");
+        assert_eq!(alice.accounts, ["@alice"]);
+        assert_eq!(export.users["user222222222"].name.as_str(), "Bob");
+
+        let chat = &export.chats[0].conversation;
+        assert_eq!(chat.name.as_deref(), Some("Bob"));
+        let reply = &chat.messages[1];
+        assert_eq!(reply.platform, Some(InstantMessagePlatform::Telegram));
+        assert_eq!(
+            reply.to,
+            InstantMessageRecipient::Direct(InstantMessageHandle::Telegram("222222222".into()))
+        );
+        assert_eq!(
+            chat.messages[0].to,
+            InstantMessageRecipient::Direct(InstantMessageHandle::Telegram("111111111".into()))
+        );
+        assert_eq!(reply.references.as_ref().unwrap().as_str(), "101");
+        assert_eq!(
+            reply.content,
+            "Hi! Have you seen this (https://telegram.org/blog)?"
+        );
+        assert_eq!(reply.edited, Some(1_704_103_290.into()));
+
+        let service = &export.chats[1].service_messages[0];
+        assert_eq!(service.action, "create_group");
+        assert_eq!(
+            service.actor,
+            Some(InstantMessageHandle::Telegram("111111111".into()))
+        );
+        assert_eq!(service.title.as_deref(), Some("Family"));
+        assert_eq!(service.members, ["Alice Smith", "Bob", "Carol"]);
+        let chat = &export.chats[1].conversation;
+        assert_eq!(chat.id.as_deref(), Some("im-group:telegram:1333333333"));
+        assert_eq!(chat.messages.len(), 3);
+        let photo = &chat.messages[0];
+        assert_eq!(
+            photo.to.r#type(),
+            crate::datatypes::InstantMessageRecipientType::Group
+        );
+        assert_eq!(photo.reaction_counts(), [("❤", 2)]);
+        assert_eq!(
+            photo.attachments[0].inline().to_string(),
+            "🖼️ photos/photo_1@01-01-2024_10-02-00.jpg"
+        );
+        let voice = &chat.messages[1];
+        let InstantMessageAttachment::File(ref file) = voice.attachments[0] else {
+            panic!("expected a file");
+        };
+        assert_eq!(file.size, 12);
+        assert_eq!(file.media_type.as_deref(), Some("audio/ogg"));
+        assert_eq!(chat.threads()[0].replies[0].message, voice);
+        let forward = &chat.messages[2];
+        assert_eq!(forward.content, "New: stories for channels");
+        assert_eq!(
+            forward.forwarded_from,
+            Some(InstantMessageHandle::Username("Telegram News".into()))
+        );
+
+        let chat = &export.chats[2].conversation;
+        assert_eq!(chat.messages[0].to.inline().to_string(), "📢 Telegram News");
+        assert_eq!(
+            chat.messages[0].from,
+            InstantMessageHandle::Telegram("channel1444444444".into())
+        );
+        assert_eq!(chat.messages[0].attachments[0].inline().to_string(), "🏷️");
+    }
+}
@@ -0,0 +1,629 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{
+    classes::{Conversation, File, InstantMessage},
+    datatypes::{
+        DateTime, InstantMessageAttachment, InstantMessageGroup, InstantMessageHandle,
+        InstantMessagePlatform, InstantMessageRecipient,
+    },
+};
+use std::{
+    io::{Read, Seek},
+    path::Path,
+};
+
+/// The order of the day, month and year in the timestamps of a WhatsApp
+/// chat export, which follows the exporting phone's locale.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum WhatsAppDateOrder {
+    /// E.g., `31/12/2023` or `31.12.23`.
+    DayMonthYear,
+    /// E.g., `12/31/23`.
+    MonthDayYear,
+    /// E.g., `2023-12-31`.
+    YearMonthDay,
+}
+
+/// Options for importing a WhatsApp chat export.
+#[derive(Clone, Debug, Default)]
+pub struct WhatsAppOptions {
+    /// The name of the chat, e.g., `Family` from the export's file name
+    /// `WhatsApp Chat with Family.txt`, which the export doesn't contain.
+    pub chat_name: Option<String>,
+
+    /// Whether the chat is a group chat, detected from its senders if
+    /// `None`.
+    pub group: Option<bool>,
+
+    /// The order of the date components, detected from the timestamps if
+    /// `None`.
+    pub date_order: Option<WhatsAppDateOrder>,
+
+    /// The phone's time zone when the chat was exported, as the export's
+    /// timestamps are wall-clock times. Defaults to UTC.
+    pub time_zone: Option<jiff::tz::TimeZone>,
+
+    /// Whether to read the contents of media attachments from an archive,
+    /// rather than only their names, sizes and media types.
+    pub load_media: bool,
+}
+
+/// A system message in a WhatsApp chat, e.g., `Alice added Bob`.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct WhatsAppSystemMessage {
+    pub date: DateTime,
+    pub text: String,
+}
+
+/// A chat imported from WhatsApp's "Export chat", either as a `.txt` file
+/// or as a `.zip` archive with the media.
+///
+/// See: https://faq.whatsapp.com/1180414079177245
+#[derive(Clone, Debug, Default)]
+pub struct WhatsAppChat {
+    pub conversation: Conversation,
+    pub system_messages: Vec<WhatsAppSystemMessage>,
+}
+
+/// A line that starts a message, before its timestamp is resolved.
+struct Entry {
+    date: [u16; 3],
+    time: [u8; 3],
+    text: String,
+}
+
+const MEDIA_OMITTED: &[&str] = &[
+    "<Media omitted>",
+    "audio omitted",
+    "document omitted",
+    "GIF omitted",
+    "image omitted",
+    "sticker omitted",
+    "video omitted",
+];
+
+const DELETED: &[&str] = &["This message was deleted", "You deleted this message"];
+
+const EDITED: &str = "<This message was edited>";
+
+/// The phrases of Android system messages that may quote a subject or a
+/// description containing `: `, which isn't then a sender's separator.
+const SYSTEM_PHRASES: &[&str] = &[
+    " changed the subject ",
+    " changed the group description",
+    " changed this group's ",
+    " created group ",
+];
+
+impl WhatsAppChat {
+    /// Imports an exported `.txt` or `.zip` file, taking the chat name from
+    /// the file name unless it's given in the options.
+    pub fn open(path: impl AsRef<Path>, options: &WhatsAppOptions) -> Result<Self, crate::Error> {
+        let path = path.as_ref();
+        let mut options = options.clone();
+        if options.chat_name.is_none() {
+            options.chat_name = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(chat_name);
+        }
+        let is_zip = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"));
+        if is_zip {
+            Self::read_zip(std::fs::File::open(path)?, &options)
+        } else {
+            Ok(Self::parse(&std::fs::read_to_string(path)?, &options))
+        }
+    }
+
+    /// Imports a `.zip` export, linking the attachments to the archive's
+    /// media files.
+    pub fn read_zip(
+        reader: impl Read + Seek,
+        options: &WhatsAppOptions,
+    ) -> Result<Self, crate::Error> {
+        let mut archive = zip::ZipArchive::new(reader)?;
+        let chat_file = archive
+            .file_names()
+            .filter(|name| name.ends_with(".txt"))
+            .min_by_key(|name| !name.ends_with("_chat.txt"))
+            .map(String::from)
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::NotFound, "no chat .txt in archive")
+            })?;
+        let mut options = options.clone();
+        if options.chat_name.is_none() {
+            options.chat_name = chat_name(chat_file.trim_end_matches(".txt"));
+        }
+        let mut input = String::new();
+        archive.by_name(&chat_file)?.read_to_string(&mut input)?;
+        let mut output = Self::parse(&input, &options);
+
+        for message in &mut output.conversation.messages {
+            for attachment in &mut message.attachments {
+                let InstantMessageAttachment::File(file) = attachment else {
+                    continue;
+                };
+                let Some(ref name) = file.name else {
+                    continue;
+                };
+                let Some(index) = archive.index_for_name(name) else {
+                    continue;
+                };
+                let entry = archive.by_index(index)?;
+                file.size = entry.size();
+                // The size is read from the archive, so it only bounds
+                // reading rather than sizing the buffer up front:
+                if options.load_media {
+                    entry.take(file.size).read_to_end(&mut file.data)?;
+                }
+            }
+        }
+        Ok(output)
+    }
+
+    /// Parses the text of a chat export.
+    pub fn parse(input: &str, options: &WhatsAppOptions) -> Self {
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut pm_used = false;
+        let mut dotted = false;
+        for line in input.trim_start_matches('\u{feff}').lines() {
+            let line = line.trim_start_matches(['\u{200e}', '\u{200f}']);
+            match parse_header(line) {
+                Some((date, separator, time, pm, text)) => {
+                    pm_used |= pm.is_some();
+                    dotted |= separator == '.';
+                    entries.push(Entry {
+                        date,
+                        time: to_24_hour(time, pm),
+                        text: text.into(),
+                    });
+                },
+                None => {
+                    if let Some(entry) = entries.last_mut() {
+                        entry.text.push('\n');
+                        entry.text.push_str(line);
+                    }
+                },
+            }
+        }
+
+        let date_order = options
+            .date_order
+            .unwrap_or_else(|| detect_date_order(&entries, dotted, pm_used));
+        let chat_name = options.chat_name.as_deref();
+        let mut output = Self::default();
+        let mut messages = Vec::new();
+        for entry in entries {
+            let Some(date) = to_datetime(entry.date, entry.time, date_order, options) else {
+                continue;
+            };
+            let split = entry.text.split_once(": ");
+            let split = split.filter(|(sender, _)| !is_system_message(sender));
+            let Some((sender, text)) = split else {
+                output.system_messages.push(WhatsAppSystemMessage {
+                    date,
+                    text: entry.text.replace('\u{200e}', ""),
+                });
+                continue;
+            };
+            // Group system messages on iOS are attributed to the group:
+            if Some(sender) == chat_name && text.starts_with('\u{200e}') {
+                output.system_messages.push(WhatsAppSystemMessage {
+                    date,
+                    text: text.replace('\u{200e}', ""),
+                });
+                continue;
+            }
+            messages.push((date, handle(sender), text.replace('\u{200e}', "")));
+        }
+
+        let mut senders: Vec<&InstantMessageHandle> = Vec::new();
+        for (_, sender, _) in &messages {
+            if !senders.contains(&sender) {
+                senders.push(sender);
+            }
+        }
+        let is_group = options.group.unwrap_or(senders.len() > 2);
+        // The export has no group ID, so without a chat name the group is
+        // identified by its members:
+        let id = match chat_name {
+            Some(chat_name) => chat_name.into(),
+            None => {
+                let mut id: Vec<&str> = senders.iter().map(|sender| sender.as_str()).collect();
+                id.sort();
+                id.join("~")
+            },
+        };
+        let group = InstantMessageGroup {
+            id,
+            platform: Some(InstantMessagePlatform::WhatsApp),
+            name: chat_name.map(String::from),
+            members: senders.iter().map(|sender| (*sender).clone()).collect(),
+        };
+        // The exporting user's own handle isn't known if they never wrote,
+        // so direct messages go to the other sender or the chat's name:
+        let chat_handle = chat_name.map(handle);
+        let recipient = |sender: &InstantMessageHandle| {
+            let other = match senders.iter().find(|other| **other != sender) {
+                Some(other) => Some(*other),
+                None => chat_handle.as_ref().filter(|other| *other != sender),
+            };
+            match other {
+                Some(other) if !is_group => InstantMessageRecipient::Direct(other.clone()),
+                _ => InstantMessageRecipient::Group(group.clone()),
+            }
+        };
+        let messages: Vec<InstantMessage> = messages
+            .iter()
+            .map(|(date, sender, text)| {
+                let mut message =
+                    InstantMessage::new(date.clone(), sender.clone(), recipient(sender), "");
+                message.platform = Some(InstantMessagePlatform::WhatsApp);
+                parse_text(&mut message, text);
+                message
+            })
+            .collect();
+        output.conversation.extend(messages);
+        output
+    }
+}
+
+/// Returns whether the text before the first `: ` of an Android line is
+/// part of a system message, e.g., `Alice changed the subject to "Re`.
+fn is_system_message(sender: &str) -> bool {
+    SYSTEM_PHRASES.iter().any(|phrase| sender.contains(phrase))
+}
+
+/// Extracts the chat name from an export's file name, e.g., `WhatsApp Chat
+/// with Family` or `WhatsApp Chat - Family`.
+fn chat_name(stem: &str) -> Option<String> {
+    let stem = stem.rsplit('/').next()?;
+    ["WhatsApp Chat with ", "WhatsApp Chat - "]
+        .iter()
+        .find_map(|prefix| stem.strip_prefix(prefix))
+        .map(|name| name.trim().into())
+        .filter(|name: &String| !name.is_empty())
+}
+
+/// Parses the timestamp at the start of a line, returning its date, the
+/// date separator, its time, whether it's PM if it has an AM/PM marker,
+/// and the rest of the line.
+///
+/// The iOS format is `[31/12/2023, 23:59:59] ...` and the Android format
+/// is `31/12/2023, 23:59 - ...`.
+#[allow(clippy::type_complexity)]
+fn parse_header(line: &str) -> Option<([u16; 3], char, [u8; 3], Option<bool>, &str)> {
+    let (bracketed, rest) = match line.strip_prefix('[') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    let (date, separator, rest) = parse_date(rest)?;
+    let rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    let (time, rest) = parse_time(rest)?;
+    let (pm, rest) = parse_meridiem(rest);
+    let rest = match bracketed {
+        true => rest.strip_prefix("] ")?,
+        false => rest.trim_start().strip_prefix("- ")?,
+    };
+    Some((date, separator, time, pm, rest))
+}
+
+fn parse_number(input: &str, max_digits: usize) -> Option<(u16, &str)> {
+    let end = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    if end == 0 || end > max_digits {
+        return None;
+    }
+    Some((input[..end].parse().ok()?, &input[end..]))
+}
+
+fn parse_date(input: &str) -> Option<([u16; 3], char, &str)> {
+    let (a, rest) = parse_number(input, 4)?;
+    let separator = rest
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '/' | '.' | '-'))?;
+    let (b, rest) = parse_number(&rest[1..], 2)?;
+    let (c, rest) = parse_number(rest.strip_prefix(separator)?, 4)?;
+    let rest = rest.strip_prefix('.').unwrap_or(rest);
+    Some(([a, b, c], separator, rest))
+}
+
+fn parse_time(input: &str) -> Option<([u8; 3], &str)> {
+    let (hour, rest) = parse_number(input, 2)?;
+    let (minute, rest) = parse_number(rest.strip_prefix([':', '.'])?, 2)?;
+    let (second, rest) = match rest.strip_prefix(':') {
+        Some(rest) => parse_number(rest, 2)?,
+        None => (0, rest),
+    };
+    Some(([hour as u8, minute as u8, second as u8], rest))
+}
+
+/// Parses an optional AM/PM marker, e.g., ` PM`, `\u{202f}pm` or ` p. m.`.
+fn parse_meridiem(input: &str) -> (Option<bool>, &str) {
+    let trimmed = input.trim_start_matches([' ', '\u{a0}', '\u{202f}']);
+    let pm = match trimmed.chars().next() {
+        Some('a' | 'A') => false,
+        Some('p' | 'P') => true,
+        _ => return (None, input),
+    };
+    let rest = trimmed[1..].trim_start_matches(['.', ' ']);
+    match rest.strip_prefix(['m', 'M']) {
+        Some(rest) => (Some(pm), rest.strip_prefix('.').unwrap_or(rest)),
+        None => (None, input),
+    }
+}
+
+fn to_24_hour([hour, minute, second]: [u8; 3], pm: Option<bool>) -> [u8; 3] {
+    let hour = match pm {
+        Some(false) if hour == 12 => 0,
+        Some(true) if hour < 12 => hour + 12,
+        _ => hour,
+    };
+    [hour, minute, second]
+}
+
+/// Detects the date order from the components that can't be a month,
+/// falling back to the locale conventions of the separator and the clock.
+fn detect_date_order(entries: &[Entry], dotted: bool, pm_used: bool) -> WhatsAppDateOrder {
+    use WhatsAppDateOrder::*;
+    if entries.iter().any(|entry| entry.date[0] > 31) {
+        YearMonthDay
+    } else if entries.iter().any(|entry| entry.date[0] > 12) {
+        DayMonthYear
+    } else if entries.iter().any(|entry| entry.date[1] > 12) || (pm_used && !dotted) {
+        MonthDayYear
+    } else {
+        DayMonthYear
+    }
+}
+
+fn to_datetime(
+    [a, b, c]: [u16; 3],
+    [hour, minute, second]: [u8; 3],
+    order: WhatsAppDateOrder,
+    options: &WhatsAppOptions,
+) -> Option<DateTime> {
+    let (year, month, day) = match order {
+        WhatsAppDateOrder::DayMonthYear => (c, b, a),
+        WhatsAppDateOrder::MonthDayYear => (c, a, b),
+        WhatsAppDateOrder::YearMonthDay => (a, b, c),
+    };
+    let year = if year < 100 { year + 2000 } else { year };
+    jiff::civil::DateTime::new(
+        year as i16,
+        month as i8,
+        day as i8,
+        hour as i8,
+        minute as i8,
+        second as i8,
+        0,
+    )
+    .ok()?
+    .to_zoned(options.time_zone.clone().unwrap_or(jiff::tz::TimeZone::UTC))
+    .ok()
+    .map(DateTime::from)
+}
+
+/// Returns the handle of a sender, which is a phone number for senders
+/// that aren't in the exporting phone's contacts.
+fn handle(sender: &str) -> InstantMessageHandle {
+    let sender: String = sender
+        .chars()
+        .filter(|c| !matches!(c, '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}'))
+        .map(|c| if c == '\u{a0}' { ' ' } else { c })
+        .collect();
+    let sender = sender.trim();
+    let is_phone = sender.starts_with('+')
+        && sender[1..]
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '(' | ')' | '.'));
+    if is_phone {
+        let digits: String = sender.chars().filter(|c| c.is_ascii_digit()).collect();
+        InstantMessageHandle::Phone(format!("+{}", digits).into())
+    } else {
+        InstantMessageHandle::Username(sender.into())
+    }
+}
+
+/// Fills in the content, attachments and markers of a message.
+fn parse_text(message: &mut InstantMessage, text: &str) {
+    let mut text = text.trim_end();
+    if let Some(rest) = text.strip_suffix(EDITED) {
+        // The export doesn't say when the message was edited:
+        message.edited = Some(message.date.clone());
+        text = rest.trim_end();
+    }
+    if DELETED.contains(&text) {
+        message.deleted = true;
+        return;
+    }
+    let (first, caption) = text.split_once('\n').unwrap_or((text, ""));
+    let attachment = if let Some(name) = first
+        .strip_prefix("<attached: ")
+        .and_then(|rest| rest.strip_suffix('>'))
+    {
+        Some(Some(name))
+    } else if let Some(name) = first.strip_suffix(" (file attached)") {
+        Some(Some(name))
+    } else if MEDIA_OMITTED.contains(&first) {
+        Some(None)
+    } else {
+        None
+    };
+    match attachment {
+        Some(name) => {
+            message
+                .attachments
+                .push(InstantMessageAttachment::File(File {
+                    name: name.map(|name| name.trim().into()),
+                    media_type: name.and_then(media_type).map(String::from),
+                    ..Default::default()
+                }));
+            message.content = caption.into();
+        },
+        None => message.content = text.into(),
+    }
+}
+
+/// Guesses the media type of an attachment from its file extension.
+fn media_type(name: &str) -> Option<&'static str> {
+    let (_, extension) = name.rsplit_once('.')?;
+    Some(match extension.to_ascii_lowercase().as_str() {
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "mp4" => "video/mp4",
+        "3gp" => "video/3gpp",
+        "opus" | "ogg" => "audio/ogg",
+        "m4a" => "audio/mp4",
+        "aac" => "audio/aac",
+        "mp3" => "audio/mpeg",
+        "pdf" => "application/pdf",
+        "vcf" => "text/vcard",
+        "txt" => "text/plain",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_android() {
+        let input = "12/31/23, 11:58 PM - Messages and calls are end-to-end encrypted.\n\
+                     12/31/23, 11:58 PM - Alice: Happy new year\n\
+                     everyone!\n\
+                     12/31/23, 11:59 PM - \u{202a}+1 555\u{a0}123 4567\u{202c}: IMG-20231231-WA0001.jpg (file attached)\n\
+                     Fireworks\n\
+                     1/1/24, 12:00 AM - Bob: This message was deleted\n\
+                     1/1/24, 12:01 AM - Alice: See you soon <This message was edited>\n\
+                     1/1/24, 12:02 AM - Bob: <Media omitted>\n";
+        let chat = WhatsAppChat::parse(
+            input,
+            &WhatsAppOptions {
+                chat_name: Some("Family".into()),
+                ..Default::default()
+            },
+        );
+        assert_eq!(chat.system_messages.len(), 1);
+        let messages = &chat.conversation.messages;
+        assert_eq!(messages.len(), 5);
+        assert_eq!(
+            messages[0].date.to_string(),
+            "2023-12-31T23:58:00.000+00:00"
+        );
+        assert_eq!(messages[0].content, "Happy new year\neveryone!");
+        assert_eq!(
+            messages[1].from,
+            InstantMessageHandle::Phone("+15551234567".into())
+        );
+        assert_eq!(messages[1].content, "Fireworks");
+        let InstantMessageAttachment::File(ref file) = messages[1].attachments[0] else {
+            panic!("expected a file");
+        };
+        assert_eq!(file.name.as_deref(), Some("IMG-20231231-WA0001.jpg"));
+        assert_eq!(file.media_type.as_deref(), Some("image/jpeg"));
+        assert_eq!(
+            messages[2].date.to_string(),
+            "2024-01-01T00:00:00.000+00:00"
+        );
+        assert!(messages[2].deleted);
+        assert_eq!(messages[3].content, "See you soon");
+        assert!(messages[3].edited.is_some());
+        assert_eq!(messages[4].attachments.len(), 1);
+        assert_eq!(
+            chat.conversation.id.as_deref(),
+            Some("im-group:whatsapp:Family")
+        );
+    }
+
+    #[test]
+    fn test_parse_android_system_messages() {
+        let input = "12/31/23, 11:58 PM - Alice: Hi\n\
+                     12/31/23, 11:59 PM - Alice changed the subject to \"Re: plans\"\n\
+                     1/1/24, 12:00 AM - Bob: Hey\n";
+        let chat = WhatsAppChat::parse(input, &WhatsAppOptions::default());
+        assert_eq!(
+            chat.system_messages[0].text,
+            "Alice changed the subject to \"Re: plans\""
+        );
+        let messages = &chat.conversation.messages;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].to,
+            InstantMessageRecipient::Direct(InstantMessageHandle::Username("Bob".into()))
+        );
+    }
+
+    #[test]
+    fn test_parse_ios() {
+        let input = "[31.12.23, 23:59:30] Alice: Hallo\n\
+                     [01.01.24, 00:00:05] Bob: \u{200e}<attached: 00000012-PHOTO-2024-01-01-00-00-05.jpg>\n";
+        let chat = WhatsAppChat::parse(input, &WhatsAppOptions::default());
+        let messages = &chat.conversation.messages;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[0].date.to_string(),
+            "2023-12-31T23:59:30.000+00:00"
+        );
+        assert_eq!(
+            messages[0].to,
+            InstantMessageRecipient::Direct(InstantMessageHandle::Username("Bob".into()))
+        );
+        assert_eq!(messages[1].content, "");
+        assert_eq!(
+            messages[1].attachments[0].inline().to_string(),
+            "📎 00000012-PHOTO-2024-01-01-00-00-05.jpg"
+        );
+
+        let options = WhatsAppOptions {
+            group: Some(true),
+            ..Default::default()
+        };
+        let chat = WhatsAppChat::parse(input, &options);
+        assert_eq!(
+            chat.conversation.id.as_deref(),
+            Some("im-group:whatsapp:Alice~Bob")
+        );
+    }
+
+    #[test]
+    fn test_read_zip() {
+        use std::io::{Cursor, Write};
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::SimpleFileOptions::default();
+        writer
+            .start_file("WhatsApp Chat with Bob.txt", options)
+            .unwrap();
+        writer
+            .write_all(b"2023-12-31, 23:59 - Alice: VID-20231231-WA0002.mp4 (file attached)\n")
+            .unwrap();
+        writer
+            .start_file("VID-20231231-WA0002.mp4", options)
+            .unwrap();
+        writer.write_all(&[0; 16]).unwrap();
+        let archive = writer.finish().unwrap();
+
+        let options = WhatsAppOptions {
+            load_media: true,
+            ..Default::default()
+        };
+        let chat = WhatsAppChat::read_zip(Cursor::new(archive.into_inner()), &options).unwrap();
+        let message = &chat.conversation.messages[0];
+        assert_eq!(
+            message.to,
+            InstantMessageRecipient::Direct(InstantMessageHandle::Username("Bob".into()))
+        );
+        let InstantMessageAttachment::File(ref file) = message.attachments[0] else {
+            panic!("expected a file");
+        };
+        assert_eq!(file.size, 16);
+        assert_eq!(file.data.len(), 16);
+    }
+}
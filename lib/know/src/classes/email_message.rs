@@ -1,12 +1,13 @@
 // This is free and unencumbered software released into the public domain.
 
 use crate::{
-    datatypes::{DateTime, EmailAddress, EmailMessageId},
+    classes::{File, Group},
+    datatypes::{DateTime, EmailAddress, EmailMessageFlags, EmailMessageId, MailingList},
     formatters::{
         DisplayConcise, DisplayDetailed, DisplayInline, DisplayJsonLd, DisplayMime, DisplayOneliner,
     },
 };
-use alloc::fmt;
+use alloc::{collections::BTreeMap, fmt};
 
 /// See: https://datatracker.ietf.org/doc/html/rfc5322#section-3.6
 #[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
@@ -20,6 +21,13 @@ pub struct EmailMessage {
     /// See: https://datatracker.ietf.org/doc/html/draft-ietf-emailcore-rfc5322bis-12#name-the-origination-date-field
     pub date: DateTime,
 
+    /// When the message was delivered, e.g., the IMAP `INTERNALDATE` or the
+    /// JMAP `receivedAt`.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc8621#section-4.1.1
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub received: Option<DateTime>,
+
     /// See: https://datatracker.ietf.org/doc/html/draft-ietf-emailcore-rfc5322bis-12#name-originator-fields
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
     pub from: Vec<EmailAddress>,
@@ -44,6 +52,13 @@ pub struct EmailMessage {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
     pub bcc: Vec<EmailAddress>,
 
+    /// The display names of the addresses above, e.g., `Alice` for
+    /// `Alice <alice@example.org>`.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/draft-ietf-emailcore-rfc5322bis-12#name-addressing
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "BTreeMap::is_empty"))]
+    pub names: BTreeMap<EmailAddress, String>,
+
     /// See: https://datatracker.ietf.org/doc/html/draft-ietf-emailcore-rfc5322bis-12#name-informational-fields
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub subject: Option<String>,
@@ -60,12 +75,91 @@ pub struct EmailMessage {
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
     pub references: Vec<EmailMessageId>,
 
+    /// The mailing list that distributed the message.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc2919
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub mailing_list: Option<Box<MailingList>>,
+
+    /// The plain text body.
+    ///
     /// See: https://datatracker.ietf.org/doc/html/draft-ietf-emailcore-rfc5322bis-12#name-body
     #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
     pub body: Option<String>,
+
+    /// Whether the plain text body was rendered from the HTML body, in which
+    /// case it isn't written out as a part of its own.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "core::ops::Not::not"))]
+    pub body_from_html: bool,
+
+    /// The HTML alternative to the plain text body.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc2046#section-5.1.4
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub html_body: Option<String>,
+
+    /// Inline parts referenced from the HTML body, such as images.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc2387
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub inline_parts: Vec<File>,
+
+    /// See: https://datatracker.ietf.org/doc/html/rfc2183
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub attachments: Vec<File>,
+
+    /// The IDs or names of the mailboxes that contain the message, e.g.,
+    /// the JMAP `mailboxIds`.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc8621#section-4.1.1
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub mailboxes: Vec<String>,
+
+    /// See: https://cr.yp.to/proto/maildir.html
+    #[cfg_attr(
+        feature = "serde",
+        serde(skip_serializing_if = "EmailMessageFlags::is_empty")
+    )]
+    pub flags: EmailMessageFlags,
+
+    /// The domains whose DKIM signatures of the message were verified.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc6376
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub signed_by: Vec<String>,
+
+    /// The domains that sealed the message's verified ARC chain, from the
+    /// first intermediary to the last.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc8617
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub arc_sealed_by: Vec<String>,
 }
 
 impl EmailMessage {
+    /// Returns the display name of one of the message's addresses.
+    pub fn display_name(&self, addr: &EmailAddress) -> Option<&str> {
+        self.names.get(addr).map(String::as_str)
+    }
+
+    /// Returns the group of messages distributed by the same mailing list,
+    /// identified by its `List-Id`.
+    pub fn group(&self) -> Option<Group> {
+        let list = self.mailing_list.as_ref()?;
+        let id = list.id.as_ref()?;
+        Some(Group {
+            id: Some(format!("list-id:{}", id)),
+            name: list.name.clone().unwrap_or_else(|| id.clone()),
+            ..Default::default()
+        })
+    }
+
+    /// Splits the plain-text body into the new text, the quoted messages,
+    /// and the signature.
+    pub fn body_parts(&self) -> Option<crate::helpers::EmailBody> {
+        self.body.as_deref().map(crate::helpers::EmailBody::parse)
+    }
+
     pub fn inline(&self) -> DisplayInline<'_, EmailMessage> {
         DisplayInline(self)
     }
@@ -177,6 +271,28 @@ impl fmt::Display for DisplayConcise<'_, EmailMessage> {
         if let Some(ref id) = self.0.id {
             writeln!(f, "\tMessage-ID: {}", id.inline())?;
         }
+        if !self.0.attachments.is_empty() {
+            writeln!(
+                f,
+                "\tAttachments: {}",
+                &self
+                    .0
+                    .attachments
+                    .iter()
+                    .map(|file| format!("📎 {}", file.name.as_deref().unwrap_or("<unnamed>")))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            )?;
+        }
+        if !self.0.flags.is_empty() {
+            writeln!(f, "\tFlags: {}", self.0.flags.inline())?;
+        }
+        if !self.0.signed_by.is_empty() {
+            writeln!(f, "\tSigned-By: {}", self.0.signed_by.join(", "))?;
+        }
+        if !self.0.arc_sealed_by.is_empty() {
+            writeln!(f, "\tARC-Sealed-By: {}", self.0.arc_sealed_by.join(", "))?;
+        }
         Ok(())
     }
 }
@@ -187,6 +303,9 @@ impl fmt::Display for DisplayDetailed<'_, EmailMessage> {
             writeln!(f, "✉️  {}", subject)?;
         }
         writeln!(f, "\tDate: {}", self.0.date.inline())?;
+        if let Some(ref received) = self.0.received {
+            writeln!(f, "\tReceived: {}", received.inline())?;
+        }
         for addr in &self.0.from {
             writeln!(f, "\tFrom: {}", addr.inline())?;
         }
@@ -208,43 +327,307 @@ impl fmt::Display for DisplayDetailed<'_, EmailMessage> {
         for references in &self.0.references {
             writeln!(f, "\tReferences: {}", references.inline())?;
         }
+        if let Some(ref list) = self.0.mailing_list {
+            writeln!(f, "\tList: {}", list.inline())?;
+        }
+        for file in &self.0.attachments {
+            write!(
+                f,
+                "\tAttachment: 📎 {}",
+                file.name.as_deref().unwrap_or("<unnamed>")
+            )?;
+            match file.media_type {
+                Some(ref media_type) => writeln!(f, " ({}, {} bytes)", media_type, file.size)?,
+                None => writeln!(f, " ({} bytes)", file.size)?,
+            }
+        }
+        if !self.0.mailboxes.is_empty() {
+            writeln!(f, "\tMailboxes: {}", self.0.mailboxes.join(", "))?;
+        }
+        if !self.0.flags.is_empty() {
+            writeln!(f, "\tFlags: {}", self.0.flags.inline())?;
+        }
+        if !self.0.signed_by.is_empty() {
+            writeln!(f, "\tSigned-By: {}", self.0.signed_by.join(", "))?;
+        }
+        if !self.0.arc_sealed_by.is_empty() {
+            writeln!(f, "\tARC-Sealed-By: {}", self.0.arc_sealed_by.join(", "))?;
+        }
         Ok(())
     }
 }
 
 impl fmt::Display for DisplayMime<'_, EmailMessage> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        writeln!(f, "Date: {}", self.0.date.mime())?;
-        for addr in &self.0.from {
-            writeln!(f, "From: {}", addr.as_str())?;
+        use crate::formatters::mime::{encode_unstructured, write_header};
+        let message = self.0;
+        write_header(f, "Date", &[message.date.mime().to_string()])?;
+        write_addresses(f, "From", &message.from, &message.names)?;
+        write_addresses(f, "Sender", message.sender.as_slice(), &message.names)?;
+        write_addresses(f, "Reply-To", &message.reply_to, &message.names)?;
+        write_addresses(f, "To", &message.to, &message.names)?;
+        write_addresses(f, "Cc", &message.cc, &message.names)?;
+        write_addresses(f, "Bcc", &message.bcc, &message.names)?;
+        if let Some(ref subject) = message.subject {
+            write_header(f, "Subject", &encode_unstructured(subject))?;
         }
-        for addr in &self.0.to {
-            writeln!(f, "To: {}", addr.as_str())?;
+        write_message_ids(f, "Message-ID", message.id.as_slice())?;
+        write_message_ids(f, "In-Reply-To", &message.in_reply_to)?;
+        write_message_ids(f, "References", &message.references)?;
+        if let Some(ref list) = message.mailing_list {
+            write_mailing_list(f, list)?;
         }
-        for addr in &self.0.cc {
-            writeln!(f, "Cc: {}", addr.as_str())?;
+        write_header(f, "MIME-Version", &["1.0"])?;
+        message
+            .mime_part()
+            .write(f, &message.mime_boundary(), &mut 0)
+    }
+}
+
+impl EmailMessage {
+    /// Renders the HTML body as the plain-text body of an HTML-only message,
+    /// so that all integrations fill the body the same way.
+    #[cfg(any(
+        feature = "imap-proto",
+        feature = "jmap",
+        feature = "mail-parser",
+        feature = "mailparse"
+    ))]
+    fn fill_body_from_html(&mut self) {
+        if self.body.is_none() && self.html_body.is_some() {
+            self.body = self.html_body.as_deref().map(crate::helpers::html_to_text);
+            self.body_from_html = true;
         }
-        for addr in &self.0.bcc {
-            writeln!(f, "Bcc: {}", addr.as_str())?;
+    }
+
+    /// Returns a multipart boundary prefix that is stable for a given message.
+    ///
+    /// The boundary contains `=_`, which can't occur in base64 or
+    /// quoted-printable encoded content.
+    fn mime_boundary(&self) -> String {
+        use core::hash::{Hash, Hasher};
+
+        /// The 64-bit FNV-1a hash, which, unlike the standard library's
+        /// hasher, is the same across Rust releases.
+        ///
+        /// See: https://datatracker.ietf.org/doc/html/draft-eastlake-fnv
+        struct Fnv1a(u64);
+
+        impl Hasher for Fnv1a {
+            fn finish(&self) -> u64 {
+                self.0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                for byte in bytes {
+                    self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3);
+                }
+            }
         }
-        if let Some(ref subject) = self.0.subject {
-            writeln!(f, "Subject: {}", subject)?;
+
+        let mut hasher = Fnv1a(0xcbf2_9ce4_8422_2325);
+        self.hash(&mut hasher);
+        format!("=_know_{:016x}", hasher.finish())
+    }
+
+    /// Arranges the bodies and files of this message into a MIME tree.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc2046#section-5.1
+    fn mime_part(&self) -> MimePart<'_> {
+        let text = match self.body_from_html && self.html_body.is_some() {
+            true => None,
+            false => self.body.as_deref().map(MimePart::Text),
+        };
+        let html = self.html_body.as_deref().map(MimePart::Html);
+        let has_related = html.is_some() && !self.inline_parts.is_empty();
+        let html = match html {
+            Some(html) if has_related => Some(MimePart::Multipart(
+                "related",
+                core::iter::once(html)
+                    .chain(self.inline_parts.iter().map(MimePart::Inline))
+                    .collect(),
+            )),
+            html => html,
+        };
+        let body = match (text, html) {
+            (Some(text), Some(html)) => MimePart::Multipart("alternative", vec![text, html]),
+            (Some(body), None) | (None, Some(body)) => body,
+            (None, None) => MimePart::Text(""),
+        };
+        let mut parts = vec![body];
+        if !has_related {
+            parts.extend(self.inline_parts.iter().map(MimePart::Inline));
         }
-        if let Some(ref id) = self.0.id {
-            writeln!(f, "Message-ID: <{}>", id.as_str())?;
+        parts.extend(self.attachments.iter().map(MimePart::Attachment));
+        match parts.len() {
+            1 => parts.remove(0),
+            _ => MimePart::Multipart("mixed", parts),
         }
-        for in_reply_to in &self.0.in_reply_to {
-            writeln!(f, "In-Reply-To: {}", in_reply_to.inline())?;
+    }
+}
+
+/// A MIME entity in a message being generated.
+enum MimePart<'a> {
+    Text(&'a str),
+    Html(&'a str),
+    Inline(&'a File),
+    Attachment(&'a File),
+    Multipart(&'static str, Vec<MimePart<'a>>),
+}
+
+impl MimePart<'_> {
+    /// Writes the entity's MIME headers and body.
+    fn write(&self, f: &mut fmt::Formatter, boundary: &str, count: &mut usize) -> fmt::Result {
+        use crate::formatters::mime::*;
+        match self {
+            MimePart::Text(text) | MimePart::Html(text) => {
+                let subtype = match self {
+                    MimePart::Html(_) => "text/html;",
+                    _ => "text/plain;",
+                };
+                write_header(f, "Content-Type", &[subtype, "charset=utf-8"])?;
+                if is_7bit(text) && !text.contains("=_") {
+                    write_header(f, "Content-Transfer-Encoding", &["7bit"])?;
+                    f.write_str(CRLF)?;
+                    write_7bit(f, text)
+                } else {
+                    write_header(f, "Content-Transfer-Encoding", &["quoted-printable"])?;
+                    f.write_str(CRLF)?;
+                    write_quoted_printable(f, text)
+                }
+            },
+            MimePart::Inline(file) | MimePart::Attachment(file) => {
+                let media_type = file
+                    .media_type
+                    .as_deref()
+                    .unwrap_or("application/octet-stream");
+                let disposition = match self {
+                    MimePart::Inline(_) => "inline",
+                    _ => "attachment",
+                };
+                match file.name {
+                    Some(ref name) => {
+                        let content_type = format!("{};", media_type);
+                        let disposition = format!("{};", disposition);
+                        write_header(
+                            f,
+                            "Content-Type",
+                            &[&content_type, &encode_parameter("name", name)],
+                        )?;
+                        write_header(
+                            f,
+                            "Content-Disposition",
+                            &[&disposition, &encode_parameter("filename", name)],
+                        )?;
+                    },
+                    None => {
+                        write_header(f, "Content-Type", &[media_type])?;
+                        write_header(f, "Content-Disposition", &[disposition])?;
+                    },
+                }
+                if let Some(ref content_id) = file.content_id {
+                    write_header(f, "Content-ID", &[encode_angle_addr(content_id)])?;
+                }
+                write_header(f, "Content-Transfer-Encoding", &["base64"])?;
+                f.write_str(CRLF)?;
+                write_base64(f, &file.data)
+            },
+            MimePart::Multipart(subtype, parts) => {
+                *count += 1;
+                let boundary_here = format!("{}_{}", boundary, count);
+                write_header(
+                    f,
+                    "Content-Type",
+                    &[
+                        format!("multipart/{};", subtype),
+                        format!("boundary=\"{}\"", boundary_here),
+                    ],
+                )?;
+                f.write_str(CRLF)?;
+                for (i, part) in parts.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(CRLF)?;
+                    }
+                    write!(f, "--{}{}", boundary_here, CRLF)?;
+                    part.write(f, boundary, count)?;
+                }
+                write!(f, "{}--{}--{}", CRLF, boundary_here, CRLF)
+            },
         }
-        for references in &self.0.references {
-            writeln!(f, "References: {}", references.inline())?;
+    }
+}
+
+fn write_addresses(
+    f: &mut fmt::Formatter,
+    name: &str,
+    addrs: &[EmailAddress],
+    names: &BTreeMap<EmailAddress, String>,
+) -> fmt::Result {
+    use crate::formatters::mime::{encode_phrase, write_header};
+    if addrs.is_empty() {
+        return Ok(());
+    }
+    let mut words = Vec::new();
+    for (i, addr) in addrs.iter().enumerate() {
+        let separator = if i + 1 < addrs.len() { "," } else { "" };
+        let addr_spec = addr.to_ascii().unwrap_or_else(|| addr.as_str().into());
+        match names.get(addr) {
+            Some(display_name) => {
+                words.extend(encode_phrase(display_name));
+                words.push(format!("<{}>{}", addr_spec, separator));
+            },
+            None => words.push(format!("{}{}", addr_spec, separator)),
         }
-        if let Some(ref body) = self.0.body {
-            writeln!(f)?;
-            writeln!(f, "{}", body)?;
+    }
+    write_header(f, name, &words)
+}
+
+fn write_message_ids(f: &mut fmt::Formatter, name: &str, ids: &[EmailMessageId]) -> fmt::Result {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    use crate::formatters::mime::{encode_angle_addr, write_header};
+    let words: Vec<String> = ids
+        .iter()
+        .map(|id| encode_angle_addr(id.as_str()))
+        .collect();
+    write_header(f, name, &words)
+}
+
+/// See: https://datatracker.ietf.org/doc/html/rfc2369#section-3
+fn write_mailing_list(f: &mut fmt::Formatter, list: &MailingList) -> fmt::Result {
+    use crate::formatters::mime::{encode_angle_addr, encode_phrase, write_header};
+    if let Some(ref id) = list.id {
+        let mut words = list.name.as_deref().map(encode_phrase).unwrap_or_default();
+        words.push(encode_angle_addr(id));
+        write_header(f, "List-Id", &words)?;
+    }
+    for (name, urls) in [
+        ("List-Post", &list.post),
+        ("List-Unsubscribe", &list.unsubscribe),
+        ("List-Archive", &list.archive),
+    ] {
+        if urls.is_empty() {
+            continue;
         }
-        Ok(())
+        let words: Vec<String> = urls
+            .iter()
+            .enumerate()
+            .map(|(i, url)| match i + 1 < urls.len() {
+                true => format!("{},", encode_angle_addr(url)),
+                false => encode_angle_addr(url),
+            })
+            .collect();
+        write_header(f, name, &words)?;
     }
+    if let Some(ref precedence) = list.precedence {
+        let precedence: String = precedence
+            .chars()
+            .filter(|c| c.is_ascii_graphic())
+            .collect();
+        write_header(f, "Precedence", &[precedence])?;
+    }
+    Ok(())
 }
 
 #[cfg(feature = "tldr")]
@@ -275,6 +658,13 @@ impl tldr::Tldr for EmailMessage {
                 if let Some(subject) = &self.subject {
                     write!(tldr, ", with the subject \"{}\"", subject)?;
                 }
+                if let Some(body) = self.body_parts().filter(|body| !body.text.is_empty()) {
+                    let text = body.text.split_whitespace().collect::<Vec<_>>().join(" ");
+                    match text.char_indices().nth(100) {
+                        Some((end, _)) => write!(tldr, ", saying \"{}…\"", &text[..end])?,
+                        None => write!(tldr, ", saying \"{}\"", text)?,
+                    }
+                }
                 Some(tldr)
             },
             _ => None,
@@ -282,9 +672,15 @@ impl tldr::Tldr for EmailMessage {
     }
 }
 
+#[cfg(feature = "dkim")]
+include!("email_message/dkim.rs");
+
 #[cfg(feature = "imap-proto")]
 include!("email_message/imap_proto.rs");
 
+#[cfg(feature = "jmap")]
+include!("email_message/jmap.rs");
+
 #[cfg(feature = "mail-parser")]
 include!("email_message/mail_parser.rs");
 
@@ -296,3 +692,619 @@ include!("email_message/mailparse.rs");
 
 #[cfg(feature = "serde")]
 include!("email_message/serde.rs");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "maildir")]
+    fn maildir(name: &str) -> maildir::Maildir {
+        let path = std::env::temp_dir().join(format!("know-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        let maildir = maildir::Maildir::from(path);
+        maildir.create_dirs().unwrap();
+        maildir
+    }
+
+    fn message() -> EmailMessage {
+        EmailMessage {
+            date: "2025-07-07T12:00:00Z".parse().unwrap(),
+            from: vec!["alice@example.org".parse().unwrap()],
+            to: vec!["bob@example.org".parse().unwrap()],
+            subject: Some("Hello".into()),
+            id: Some("1234@example.org".into()),
+            body: Some("Hi Bob!".into()),
+            ..Default::default()
+        }
+    }
+
+    #[cfg(feature = "maildir")]
+    #[test]
+    fn test_store_and_read_back() {
+        let maildir = maildir("store");
+        let mut message = message();
+        message.flags.seen = true;
+        message.flags.flagged = true;
+
+        let id = message.store_in(&maildir).unwrap();
+        assert_eq!(maildir.count_new(), 0);
+        assert_eq!(maildir.count_cur(), 1);
+
+        let mut entry = maildir.find(&id).unwrap();
+        assert!(!std::fs::read(entry.path()).unwrap().contains(&b'\r'));
+        let stored = EmailMessage::try_from(&mut entry).unwrap();
+        assert_eq!(stored.subject, message.subject);
+        assert_eq!(stored.from, message.from);
+        assert_eq!(stored.id, message.id);
+        assert_eq!(stored.flags, message.flags);
+
+        std::fs::remove_dir_all(maildir.path()).unwrap();
+    }
+
+    #[cfg(feature = "maildir")]
+    #[test]
+    fn test_sync_flags() {
+        let maildir = maildir("sync");
+        let mut message = message();
+
+        let id = message.store_in(&maildir).unwrap();
+        assert_eq!(maildir.count_new(), 1);
+
+        message.flags.seen = true;
+        message.sync_flags_in(&maildir, &id).unwrap();
+        assert_eq!(maildir.count_new(), 0);
+        assert_eq!(maildir.find(&id).unwrap().flags(), "S");
+
+        message.flags.replied = true;
+        message.sync_flags_in(&maildir, &id).unwrap();
+        assert_eq!(maildir.find(&id).unwrap().flags(), "RS");
+
+        // Keyword flags set by another client are kept by reading them:
+        maildir.add_flags(&id, "a").unwrap();
+        let stored = EmailMessageFlags::from(maildir.find(&id).unwrap().flags());
+        message.flags.keywords = stored.keywords;
+        message.flags.flagged = true;
+        message.sync_flags_in(&maildir, &id).unwrap();
+        assert_eq!(maildir.find(&id).unwrap().flags(), "FRSa");
+
+        // The last keyword flag can be cleared:
+        message.flags.keywords.clear();
+        message.sync_flags_in(&maildir, &id).unwrap();
+        assert_eq!(maildir.find(&id).unwrap().flags(), "FRS");
+
+        std::fs::remove_dir_all(maildir.path()).unwrap();
+    }
+
+    #[cfg(feature = "maildir")]
+    #[test]
+    fn test_bad_message_does_not_abort_ingestion() {
+        let maildir = maildir("bad");
+        maildir
+            .store_new(b"Subject: No date\r\n\r\nHi\r\n")
+            .unwrap();
+        message().store_in(&maildir).unwrap();
+
+        let results: Vec<_> = maildir
+            .list_new()
+            .map(|entry| EmailMessage::try_from(&mut entry.unwrap()))
+            .collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        assert!(
+            results
+                .iter()
+                .any(|result| matches!(result, Err(crate::Error::MissingHeader("Date"))))
+        );
+
+        std::fs::remove_dir_all(maildir.path()).unwrap();
+    }
+
+    #[cfg(feature = "mail-parser")]
+    #[test]
+    fn test_missing_date_with_mail_parser() {
+        let parsed = mail_parser::MessageParser::default()
+            .parse(b"Subject: No date\r\n\r\nHi\r\n".as_slice())
+            .unwrap();
+        assert!(matches!(
+            EmailMessage::try_from(&parsed),
+            Err(crate::Error::MissingHeader("Date"))
+        ));
+    }
+
+    #[cfg(feature = "mailparse")]
+    #[test]
+    fn test_invalid_date_with_mailparse() {
+        let parsed = mailparse::parse_mail(b"Date: yesterday\r\n\r\nHi\r\n").unwrap();
+        assert!(matches!(
+            EmailMessage::try_from(&parsed),
+            Err(crate::Error::InvalidHeader("Date", _))
+        ));
+    }
+
+    #[cfg(feature = "imap-proto")]
+    #[test]
+    fn test_missing_date_with_imap_proto() {
+        let envelope = imap_proto::types::Envelope {
+            date: None,
+            subject: None,
+            from: None,
+            sender: None,
+            reply_to: None,
+            to: None,
+            cc: None,
+            bcc: None,
+            in_reply_to: None,
+            message_id: None,
+        };
+        assert!(matches!(
+            EmailMessage::try_from(&envelope),
+            Err(crate::Error::MissingHeader("Date"))
+        ));
+    }
+
+    #[cfg(feature = "imap-proto")]
+    fn fetch(attrs: &str, literals: &[&str]) -> Vec<u8> {
+        let mut output = format!("* 12 FETCH ({}", attrs);
+        for (i, literal) in literals.iter().enumerate() {
+            output.push_str(&format!(
+                " BODY[{}] {{{}}}\r\n{}",
+                i + 1,
+                literal.len(),
+                literal
+            ));
+        }
+        output.push_str(")\r\n");
+        output.into_bytes()
+    }
+
+    #[cfg(feature = "imap-proto")]
+    #[test]
+    fn test_imap_fetch_with_body_structure() {
+        let response = fetch(
+            concat!(
+                r#"UID 4827 FLAGS (\Seen \Answered) INTERNALDATE "17-Jul-1996 02:44:25 -0700" "#,
+                r#"RFC822.SIZE 4286 ENVELOPE ("Wed, 17 Jul 1996 02:23:25 -0700 (PDT)" "#,
+                r#""=?utf-8?Q?Gr=C3=BC=C3=9Fe?=" (("Terry Gray" NIL "gray" "cac.washington.edu")) "#,
+                r#"NIL NIL ((NIL NIL "imap" "cac.washington.edu")) NIL NIL "#,
+                r#""<B27397-0100000@cac.washington.edu>" "<B27398-0100000@cac.washington.edu>") "#,
+                r#"BODYSTRUCTURE (("TEXT" "PLAIN" ("CHARSET" "UTF-8") NIL NIL "QUOTED-PRINTABLE" "#,
+                r#"17 1 NIL NIL NIL NIL)("APPLICATION" "PDF" ("NAME" "minutes.pdf") NIL NIL "#,
+                r#""BASE64" 8 NIL ("ATTACHMENT" ("FILENAME" "minutes.pdf")) NIL NIL) "MIXED" "#,
+                r#"("BOUNDARY" "x") NIL NIL NIL)"#,
+            ),
+            &["Gr=C3=BC=C3=9Fe\r\n", "AAEC/w==\r\n"],
+        );
+        let (_, response) = imap_proto::Response::from_bytes(&response).unwrap();
+        let fetch = crate::helpers::ImapFetch::try_from(&response).unwrap();
+        assert_eq!(fetch.sequence_number, 12);
+        assert_eq!(fetch.uid, Some(4827));
+        assert_eq!(fetch.size, Some(4286));
+        assert_eq!(
+            fetch.internal_date,
+            Some("1996-07-17T02:44:25-07:00".parse().unwrap())
+        );
+
+        let message = fetch.message;
+        assert_eq!(message.date, "1996-07-17T02:23:25-07:00".parse().unwrap());
+        assert_eq!(message.subject.as_deref(), Some("Grüße"));
+        assert_eq!(message.from, ["gray@cac.washington.edu".parse().unwrap()]);
+        assert_eq!(message.to, ["imap@cac.washington.edu".parse().unwrap()]);
+        assert_eq!(
+            message.in_reply_to,
+            [EmailMessageId::from("B27397-0100000@cac.washington.edu")]
+        );
+        assert_eq!(message.body.as_deref(), Some("Grüße"));
+        assert!(message.flags.seen && message.flags.replied);
+        assert!(!message.flags.flagged);
+        assert_eq!(
+            message.attachments,
+            [File {
+                name: Some("minutes.pdf".into()),
+                size: 4,
+                media_type: Some("application/pdf".into()),
+                data: vec![0, 1, 2, 255],
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[cfg(feature = "imap-proto")]
+    #[test]
+    fn test_imap_fetch_with_full_body() {
+        let mut expected = html_message();
+        let output = expected.mime().to_string();
+        let response = format!(
+            "* 1 FETCH (FLAGS (\\Seen $Forwarded) BODY[] {{{}}}\r\n{})\r\n",
+            output.len(),
+            output
+        );
+        let (_, response) = imap_proto::Response::from_bytes(response.as_bytes()).unwrap();
+        let imap_proto::Response::Fetch(_, attrs) = &response else {
+            unreachable!()
+        };
+        expected.flags.seen = true;
+        expected.flags.passed = true;
+        assert_eq!(EmailMessage::try_from(attrs.as_slice()).unwrap(), expected);
+    }
+
+    #[cfg(feature = "imap-proto")]
+    #[test]
+    fn test_imap_fetch_without_body_sections() {
+        let response = fetch(
+            concat!(
+                r#"ENVELOPE ("Wed, 1 Jan 2025 12:00:00 +0000" NIL NIL NIL NIL NIL NIL NIL NIL "#,
+                r#"NIL) BODYSTRUCTURE (("TEXT" "PLAIN" ("CHARSET" "UTF-8") NIL NIL "7BIT" 2 1 "#,
+                r#"NIL NIL NIL NIL)("TEXT" "HTML" ("CHARSET" "UTF-8") NIL NIL "7BIT" 9 1 NIL "#,
+                r#"NIL NIL NIL) "ALTERNATIVE" ("BOUNDARY" "x") NIL NIL NIL)"#,
+            ),
+            &[],
+        );
+        let (_, response) = imap_proto::Response::from_bytes(&response).unwrap();
+        let fetch = crate::helpers::ImapFetch::try_from(&response).unwrap();
+        assert_eq!(fetch.message.body, None);
+        assert_eq!(fetch.message.html_body, None);
+    }
+
+    #[cfg(feature = "imap-proto")]
+    #[test]
+    fn test_imap_fetch_falls_back_to_internal_date() {
+        let response = fetch(
+            concat!(
+                r#"INTERNALDATE " 1-Jan-2025 10:00:00 +0000" ENVELOPE (NIL "Hi" NIL NIL NIL NIL "#,
+                r#"NIL NIL NIL NIL)"#,
+            ),
+            &[],
+        );
+        let (_, response) = imap_proto::Response::from_bytes(&response).unwrap();
+        let fetch = crate::helpers::ImapFetch::try_from(&response).unwrap();
+        assert_eq!(fetch.message.date, "2025-01-01T10:00:00Z".parse().unwrap());
+        assert_eq!(fetch.message.subject.as_deref(), Some("Hi"));
+    }
+
+    fn multipart_message() -> EmailMessage {
+        EmailMessage {
+            sender: Some("secretary@example.org".parse().unwrap()),
+            reply_to: vec!["alice+replies@example.org".parse().unwrap()],
+            to: vec![
+                "bob@example.org".parse().unwrap(),
+                "carol@example.org".parse().unwrap(),
+                "dave@example.org".parse().unwrap(),
+                "erin@example.org".parse().unwrap(),
+            ],
+            cc: vec!["frank@example.org".parse().unwrap()],
+            names: [
+                ("alice@example.org", "Alice Smith"),
+                ("bob@example.org", "Bob \"The Builder\" Jones"),
+                ("carol@example.org", "Smith, Carol"),
+                ("dave@example.org", "Dávid Kovács"),
+            ]
+            .into_iter()
+            .map(|(addr, name)| (addr.parse().unwrap(), name.into()))
+            .collect(),
+            subject: Some("Grüße aus Köln – a rather long subject line that needs folding".into()),
+            in_reply_to: vec!["1233@example.org".into()],
+            references: vec!["1231@example.org".into(), "1233@example.org".into()],
+            mailing_list: Some(Box::new(MailingList {
+                id: Some("users.lists.example.org".into()),
+                name: Some("Exämple Users".into()),
+                post: vec!["mailto:users@lists.example.org".into()],
+                unsubscribe: vec![
+                    "mailto:users-leave@lists.example.org".into(),
+                    "https://lists.example.org/users/unsubscribe".into(),
+                ],
+                archive: vec!["https://lists.example.org/users/archive".into()],
+                precedence: Some("list".into()),
+            })),
+            body: Some(format!("Hällo Bob!\n\n{}\n-- \nAlice", "x".repeat(100))),
+            attachments: vec![File {
+                name: Some("résumé.pdf".into()),
+                size: 4,
+                media_type: Some("application/pdf".into()),
+                data: vec![0, 1, 2, 255],
+                ..Default::default()
+            }],
+            ..message()
+        }
+    }
+
+    fn html_message() -> EmailMessage {
+        EmailMessage {
+            html_body: Some("<p>Hällo <b>Bob</b>!</p>\n<img src=\"cid:logo@example.org\">".into()),
+            inline_parts: vec![File {
+                name: Some("logo.png".into()),
+                size: 3,
+                media_type: Some("image/png".into()),
+                content_id: Some("logo@example.org".into()),
+                data: vec![137, 80, 78],
+                ..Default::default()
+            }],
+            ..multipart_message()
+        }
+    }
+
+    #[test]
+    fn test_mime_is_rfc5322() {
+        let output = multipart_message().mime().to_string();
+        let (headers, _) = output.split_once("\r\n\r\n").unwrap();
+        for line in output.split("\r\n") {
+            assert!(line.len() <= 78, "line too long: {}", line);
+            assert!(line.is_ascii(), "line not ASCII: {}", line);
+        }
+        assert!(!output.replace("\r\n", "").contains('\n'));
+        assert!(headers.contains("\r\nMIME-Version: 1.0\r\n"));
+        assert!(headers.contains("\r\nSender: secretary@example.org\r\n"));
+        assert!(headers.contains("\r\nReply-To: alice+replies@example.org\r\n"));
+        assert!(headers.contains("\r\nIn-Reply-To: <1233@example.org>\r\n"));
+        assert!(headers.contains("\r\nFrom: Alice Smith <alice@example.org>\r\n"));
+        assert!(headers.contains("\r\nContent-Type: multipart/mixed;"));
+        assert_eq!(headers.matches("\r\nTo:").count(), 1);
+        assert!(headers.contains(
+            "\r\nList-Id: =?utf-8?B?RXjDpG1wbGUgVXNlcnM=?= <users.lists.example.org>\r\n"
+        ));
+        assert_eq!(output, multipart_message().mime().to_string());
+    }
+
+    #[test]
+    fn test_mime_header_injection() {
+        let mut message = multipart_message();
+        message.id = Some("1234@example.org>\r\nBcc: <eve@example.org".into());
+        message.mailing_list.as_mut().unwrap().id = Some("users\nX-Spam: no".into());
+        let output = message.mime().to_string();
+        assert!(!output.contains("\r\nBcc:"));
+        assert!(!output.contains("\nX-Spam:"));
+        assert!(output.contains("\r\nMessage-ID: <1234@example.orgBcc:eve@example.org>\r\n"));
+    }
+
+    #[test]
+    fn test_mime_structure() {
+        let output = html_message().mime().to_string();
+        let content_types: Vec<&str> = output
+            .split("\r\n")
+            .filter_map(|line| line.strip_prefix("Content-Type: "))
+            .map(|value| value.split(';').next().unwrap())
+            .collect();
+        assert_eq!(
+            content_types,
+            [
+                "multipart/mixed",
+                "multipart/alternative",
+                "text/plain",
+                "multipart/related",
+                "text/html",
+                "image/png",
+                "application/pdf",
+            ]
+        );
+        assert!(output.contains("\r\nContent-ID: <logo@example.org>\r\n"));
+        assert!(output.contains("\r\nContent-Disposition: inline; filename=\"logo.png\"\r\n"));
+    }
+
+    #[cfg(feature = "mail-parser")]
+    #[test]
+    fn test_mime_roundtrip_with_mail_parser() {
+        for expected in [message(), multipart_message(), html_message()] {
+            let output = expected.mime().to_string();
+            let parsed = mail_parser::MessageParser::default()
+                .parse(output.as_bytes())
+                .unwrap();
+            let actual = EmailMessage::try_from(&parsed).unwrap();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[cfg(feature = "mailparse")]
+    #[test]
+    fn test_mime_roundtrip_with_mailparse() {
+        for expected in [message(), multipart_message(), html_message()] {
+            let output = expected.mime().to_string();
+            let parsed = mailparse::parse_mail(output.as_bytes()).unwrap();
+            let actual = EmailMessage::try_from(&parsed).unwrap();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[cfg(feature = "jmap")]
+    #[test]
+    fn test_from_jmap() {
+        let input = serde_json::from_str(include_str!("../../test/jmap/email.json")).unwrap();
+        let message = EmailMessage::from_jmap(&input).unwrap();
+        assert_eq!(message.date, "2025-07-07T12:00:00Z".parse().unwrap());
+        assert_eq!(
+            message.received,
+            Some("2025-07-07T12:00:05Z".parse().unwrap())
+        );
+        assert_eq!(message.from, ["bob@example.org".parse().unwrap()]);
+        assert_eq!(message.to.len(), 2);
+        assert_eq!(message.display_name(&message.from[0]), Some("Bob Jones"));
+        assert_eq!(message.display_name(&message.to[1]), None);
+        assert_eq!(message.id, Some("1235@example.org".into()));
+        assert_eq!(message.references.len(), 2);
+        assert_eq!(message.mailboxes, ["Mb8b1c42f"]);
+        assert_eq!(
+            message.flags,
+            EmailMessageFlags {
+                keywords: vec!["$mdnsent".into()],
+                ..EmailMessageFlags::from("RS")
+            }
+        );
+        assert_eq!(
+            message.body.as_deref(),
+            Some("Hi Alice!\nSee the attached photo.")
+        );
+        assert!(message.html_body.unwrap().starts_with("<p>Hi Alice!</p>\n"));
+        assert!(message.inline_parts.is_empty());
+        assert_eq!(
+            message.attachments,
+            [File {
+                name: Some("photo.jpg".into()),
+                id: Some("Gf5c3c2ac0a53c6b8-3".into()),
+                size: 1842,
+                media_type: Some("image/jpeg".into()),
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[cfg(feature = "jmap")]
+    #[test]
+    fn test_from_jmap_without_date() {
+        let input = serde_json::json!({ "subject": "Hi" });
+        assert!(matches!(
+            EmailMessage::from_jmap(&input),
+            Err(crate::Error::MissingProperty("sentAt"))
+        ));
+        let input = serde_json::json!({ "sentAt": "yesterday" });
+        assert!(matches!(
+            EmailMessage::from_jmap(&input),
+            Err(crate::Error::InvalidProperty("sentAt", _))
+        ));
+    }
+
+    #[cfg(feature = "jmap")]
+    #[test]
+    fn test_from_jmap_skips_invalid_addresses() {
+        let input = serde_json::json!({
+            "sentAt": "2025-07-07T14:00:00+02:00",
+            "to": [
+                { "name": "Alice Smith", "email": "alice@example.org" },
+                { "name": "Undisclosed recipients" },
+                { "name": "Bob", "email": "bob@-bad.org" },
+            ],
+        });
+        let message = EmailMessage::from_jmap(&input).unwrap();
+        assert_eq!(message.to, ["alice@example.org".parse().unwrap()]);
+        assert_eq!(message.names.len(), 1);
+    }
+
+    #[cfg(feature = "jmap")]
+    #[test]
+    fn test_to_jmap() {
+        let message = EmailMessage {
+            received: Some("2025-07-07T12:00:05Z".parse().unwrap()),
+            mailboxes: vec!["Mb8b1c42f".into()],
+            flags: EmailMessageFlags {
+                keywords: vec!["$junk".into()],
+                ..EmailMessageFlags::from("FS")
+            },
+            ..html_message()
+        };
+        let output = message.to_jmap();
+        assert_eq!(output["receivedAt"], "2025-07-07T12:00:05Z");
+        assert_eq!(output["sentAt"], "2025-07-07T12:00:00+00:00");
+        assert_eq!(
+            output["mailboxIds"],
+            serde_json::json!({ "Mb8b1c42f": true })
+        );
+        assert_eq!(
+            output["keywords"],
+            serde_json::json!({ "$flagged": true, "$junk": true, "$seen": true })
+        );
+        assert_eq!(
+            output["from"],
+            serde_json::json!([{ "name": "Alice Smith", "email": "alice@example.org" }])
+        );
+        assert_eq!(output["bodyStructure"]["type"], "multipart/mixed");
+        assert_eq!(
+            output["bodyValues"]["1"]["value"],
+            *message.body.as_ref().unwrap()
+        );
+
+        // Files are exported by reference, without their data:
+        let expected = EmailMessage {
+            inline_parts: vec![File {
+                id: Some("G1".into()),
+                data: Vec::new(),
+                ..message.inline_parts[0].clone()
+            }],
+            attachments: vec![File {
+                id: Some("G2".into()),
+                data: Vec::new(),
+                ..message.attachments[0].clone()
+            }],
+            ..message
+        };
+        assert_eq!(
+            EmailMessage::from_jmap(&expected.to_jmap()).unwrap(),
+            expected
+        );
+    }
+
+    #[cfg(feature = "tldr")]
+    #[test]
+    fn test_tldr_summarizes_new_text() {
+        use tldr::Tldr;
+        let message = EmailMessage {
+            body: Some("Sure,\nnoon works.\n\nOn Monday, Alice wrote:\n> Lunch?\n-- \nBob".into()),
+            ..message()
+        };
+        let what = message.what(&Default::default()).unwrap().unwrap();
+        assert!(what.ends_with(", saying \"Sure, noon works.\""), "{}", what);
+    }
+
+    #[cfg(all(feature = "mail-parser", feature = "mailparse"))]
+    #[test]
+    fn test_html_only_body_is_rendered_by_all_parsers() {
+        let message = EmailMessage {
+            body: None,
+            html_body: Some("<p>Hi <a href=\"https://example.org\">Bob</a>!</p>".into()),
+            ..message()
+        };
+        let output = message.mime().to_string();
+        let expected = Some("Hi Bob[1]!\n\n[1] https://example.org".to_string());
+
+        let parsed = mailparse::parse_mail(output.as_bytes()).unwrap();
+        assert_eq!(EmailMessage::try_from(&parsed).unwrap().body, expected);
+        let parsed = mail_parser::MessageParser::default()
+            .parse(output.as_bytes())
+            .unwrap();
+        let parsed = EmailMessage::try_from(&parsed).unwrap();
+        assert_eq!(parsed.body, expected);
+
+        // The rendered text isn't invented as a text/plain alternative:
+        assert!(parsed.body_from_html);
+        assert!(!parsed.mime().to_string().contains("text/plain"));
+    }
+
+    #[test]
+    fn test_mailing_list_group() {
+        let group = multipart_message().group().unwrap();
+        assert_eq!(group.id.as_deref(), Some("list-id:users.lists.example.org"));
+        assert_eq!(group.name, "Exämple Users");
+        assert_eq!(message().group(), None);
+    }
+
+    #[cfg(feature = "dkim")]
+    #[test]
+    fn test_verify_dkim() {
+        let keys = crate::helpers::DkimKeys::parse(include_str!("../../test/dkim/keys.txt"));
+        let mut message = EmailMessage::default();
+        message.verify_dkim(include_bytes!("../../test/dkim/rfc8463.eml"), &keys);
+        assert_eq!(message.signed_by, ["football.example.com"]);
+        assert!(
+            message
+                .detailed()
+                .to_string()
+                .contains("\tSigned-By: football.example.com\n")
+        );
+
+        // The mailing list modified the message:
+        let verifications = message.verify_dkim(include_bytes!("../../test/dkim/arc.eml"), &keys);
+        assert_eq!(verifications.len(), 2);
+        assert!(message.signed_by.is_empty());
+
+        // ...but sealed it:
+        message.verify_arc(include_bytes!("../../test/dkim/arc.eml"), &keys);
+        assert_eq!(
+            message.arc_sealed_by,
+            ["lists.example.org", "mx.example.net"]
+        );
+        assert!(
+            message
+                .detailed()
+                .to_string()
+                .contains("\tARC-Sealed-By: lists.example.org, mx.example.net\n")
+        );
+        message.verify_arc(include_bytes!("../../test/dkim/rfc8463.eml"), &keys);
+        assert!(message.arc_sealed_by.is_empty());
+    }
+}
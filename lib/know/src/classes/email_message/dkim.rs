@@ -0,0 +1,40 @@
+// This is free and unencumbered software released into the public domain.
+
+impl EmailMessage {
+    /// Verifies the DKIM signatures of the raw message that this message was
+    /// parsed from, and records the domains of the valid ones in
+    /// `signed_by`.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc6376#section-6
+    pub fn verify_dkim(
+        &mut self,
+        raw: &[u8],
+        resolver: &impl crate::helpers::DkimKeyResolver,
+    ) -> Vec<crate::helpers::DkimVerification> {
+        let output = crate::helpers::verify_dkim(raw, resolver);
+        self.signed_by.clear();
+        for verification in output.iter().filter(|verification| verification.result.is_pass()) {
+            if !self.signed_by.contains(&verification.domain) {
+                self.signed_by.push(verification.domain.clone());
+            }
+        }
+        output
+    }
+    /// Verifies the ARC chain of the raw message that this message was
+    /// parsed from, and records the domains that sealed it in
+    /// `arc_sealed_by` if the chain is valid.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc8617#section-5.2
+    pub fn verify_arc(
+        &mut self,
+        raw: &[u8],
+        resolver: &impl crate::helpers::DkimKeyResolver,
+    ) -> crate::helpers::ArcVerification {
+        let output = crate::helpers::verify_arc(raw, resolver);
+        self.arc_sealed_by = match output.result {
+            Some(ref result) if result.is_pass() => output.sealers.clone(),
+            _ => Vec::new(),
+        };
+        output
+    }
+}
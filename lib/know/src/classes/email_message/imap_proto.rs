@@ -1,50 +1,291 @@
 // This is free and unencumbered software released into the public domain.
 
 impl TryFrom<&imap_proto::types::Envelope<'_>> for EmailMessage {
-    type Error = ();
+    type Error = crate::Error;
 
     fn try_from(input: &imap_proto::types::Envelope) -> Result<Self, Self::Error> {
-        let input_date = String::from_utf8_lossy(input.date.as_ref().unwrap()); // TODO: fallible?
-        let input_date = jiff::fmt::rfc2822::parse(&input_date).map_err(|_| ())?;
+        Self::from_imap_envelope(input, None)
+    }
+}
+
+/// Converts the attributes of an IMAP `FETCH` response.
+///
+/// A full `RFC822` or `BODY[]` section takes precedence over everything
+/// else. Otherwise, the headers are taken from `ENVELOPE` or a header
+/// section, falling back to `INTERNALDATE` for the date, and the parts
+/// described by `BODYSTRUCTURE` become the bodies and files of the message,
+/// with the content of any fetched `BODY[<part>]` sections.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc9051#section-7.5.2
+impl TryFrom<&[imap_proto::types::AttributeValue<'_>]> for EmailMessage {
+    type Error = crate::Error;
+
+    fn try_from(input: &[imap_proto::types::AttributeValue]) -> Result<Self, Self::Error> {
+        use imap_proto::types::{AttributeValue::*, MessageSection, SectionPath};
+
+        let internal_date = input.iter().find_map(|attr| match attr {
+            InternalDate(date) => crate::helpers::parse_imap_date(date).ok(),
+            _ => None,
+        });
+        let full = input.iter().find_map(|attr| match attr {
+            Rfc822(Some(data)) | BodySection { section: None, data: Some(data), .. } => Some(data),
+            _ => None,
+        });
+        let header = input.iter().find_map(|attr| match attr {
+            Rfc822Header(Some(data))
+            | BodySection {
+                section: Some(SectionPath::Full(MessageSection::Header)),
+                data: Some(data),
+                ..
+            } => Some(data),
+            _ => None,
+        });
+        let envelope = input.iter().find_map(|attr| match attr {
+            Envelope(envelope) => Some(envelope),
+            _ => None,
+        });
+
+        let mut message = match (full, header, envelope) {
+            (Some(data), _, _) => Self::try_from(&mailparse::parse_mail(data)?)?,
+            (None, Some(data), _) => {
+                use mailparse::MailHeaderMap;
+                let (mut headers, _) = mailparse::parse_headers(data)?;
+                let date = internal_date.as_ref().map(|date| format!("Date: {}\r\n", date.mime()));
+                if let Some(date) = &date
+                    && headers.get_first_header("Date").is_none()
+                {
+                    headers.extend(mailparse::parse_headers(date.as_bytes())?.0);
+                }
+                Self::try_from(&headers)?
+            },
+            (None, None, Some(envelope)) => {
+                Self::from_imap_envelope(envelope, internal_date.clone())?
+            },
+            (None, None, None) => Self {
+                date: internal_date
+                    .clone()
+                    .ok_or(crate::Error::MissingHeader("Date"))?,
+                ..Default::default()
+            },
+        };
+
+        if full.is_none() {
+            let structure = input.iter().find_map(|attr| match attr {
+                BodyStructure(structure) => Some(structure),
+                _ => None,
+            });
+            if let Some(structure) = structure {
+                let sections: Vec<(&[u32], &[u8])> = input
+                    .iter()
+                    .filter_map(|attr| match attr {
+                        BodySection {
+                            section: Some(SectionPath::Part(path, None)),
+                            data: Some(data),
+                            ..
+                        } => Some((path.as_slice(), data.as_ref())),
+                        _ => None,
+                    })
+                    .collect();
+                message.add_imap_body_structure(structure, &mut Vec::new(), &sections)?;
+                message.fill_body_from_html();
+            }
+        }
+
+        message.received = internal_date;
+        for attr in input {
+            if let Flags(flags) = attr {
+                message.flags = EmailMessageFlags::from_imap(flags);
+            }
+        }
+        Ok(message)
+    }
+}
+
+impl EmailMessage {
+    fn from_imap_envelope(
+        input: &imap_proto::types::Envelope,
+        fallback_date: Option<DateTime>,
+    ) -> Result<Self, crate::Error> {
+        /// Decodes any RFC 2047 encoded words in an envelope string.
+        fn decode_header(key: &str, input: &[u8]) -> String {
+            let value = String::from_utf8_lossy(input);
+            mailparse::parse_header(format!("{}: {}", key, value).as_bytes())
+                .map(|(header, _)| header.get_value())
+                .unwrap_or_else(|_| value.into_owned())
+        }
+
+        let mut names = BTreeMap::new();
+        let mut addresses = |input: &Option<Vec<imap_proto::types::Address>>| {
+            let mut output: Vec<EmailAddress> = Vec::new();
+            for address in input.iter().flatten() {
+                let Ok(email) = EmailAddress::try_from(address) else {
+                    continue;
+                };
+                if let Some(ref name) = address.name {
+                    names.insert(email.clone(), decode_header("Name", name));
+                }
+                output.push(email);
+            }
+            output
+        };
+
+        fn message_ids(input: &Option<alloc::borrow::Cow<[u8]>>) -> Vec<EmailMessageId> {
+            input
+                .iter()
+                .flat_map(|ids| {
+                    String::from_utf8_lossy(ids)
+                        .split_whitespace()
+                        .filter_map(|id| id.parse().ok())
+                        .collect::<Vec<_>>()
+                })
+                .collect()
+        }
+
+        let date = match (&input.date, fallback_date) {
+            (Some(date), _) => jiff::fmt::rfc2822::parse(&String::from_utf8_lossy(date))
+                .map_err(|error| crate::Error::InvalidHeader("Date", error.to_string()))?
+                .into(),
+            (None, Some(date)) => date,
+            (None, None) => Err(crate::Error::MissingHeader("Date"))?,
+        };
         Ok(Self {
-            date: input_date.into(),
-            from: input
-                .from
-                .as_ref()
-                .map(|xs| xs.into_iter().filter_map(|x| x.try_into().ok()).collect())
-                .unwrap_or_default(),
-            sender: input
-                .sender
-                .as_ref()
-                .map(|xs| xs.into_iter().filter_map(|x| x.try_into().ok()).next())
-                .unwrap_or_default(),
-            reply_to: input
-                .reply_to
-                .as_ref()
-                .map(|xs| xs.into_iter().filter_map(|x| x.try_into().ok()).collect())
-                .unwrap_or_default(),
-            to: input
-                .to
-                .as_ref()
-                .map(|xs| xs.into_iter().filter_map(|x| x.try_into().ok()).collect())
-                .unwrap_or_default(),
-            cc: input
-                .cc
-                .as_ref()
-                .map(|xs| xs.into_iter().filter_map(|x| x.try_into().ok()).collect())
-                .unwrap_or_default(),
-            bcc: Default::default(),
+            date,
+            received: Default::default(),
+            from: addresses(&input.from),
+            sender: addresses(&input.sender).into_iter().next(),
+            reply_to: addresses(&input.reply_to),
+            to: addresses(&input.to),
+            cc: addresses(&input.cc),
+            bcc: addresses(&input.bcc),
+            names,
             subject: input
                 .subject
                 .as_ref()
-                .map(|x| String::from_utf8_lossy(x).into_owned()),
-            id: input
-                .message_id
-                .as_ref()
-                .and_then(|header| String::from_utf8_lossy(header).parse().ok()),
-            in_reply_to: Default::default(), // TODO
-            references: Default::default(),  // TODO
+                .map(|subject| decode_header("Subject", subject)),
+            id: message_ids(&input.message_id).into_iter().next(),
+            in_reply_to: message_ids(&input.in_reply_to),
+            references: Default::default(), // not part of the envelope
+            mailing_list: Default::default(), // not part of the envelope
             body: Default::default(),
+            body_from_html: false,
+            html_body: Default::default(),
+            inline_parts: Default::default(),
+            attachments: Default::default(),
+            mailboxes: Default::default(),
+            flags: Default::default(),
+            signed_by: Default::default(), // see EmailMessage::verify_dkim
+            arc_sealed_by: Default::default(), // see EmailMessage::verify_arc
         })
     }
+
+    /// Adds the leaf parts of an IMAP `BODYSTRUCTURE` to this message,
+    /// decoding the content of those parts that were fetched.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc9051#section-7.5.2
+    fn add_imap_body_structure(
+        &mut self,
+        input: &imap_proto::types::BodyStructure,
+        path: &mut Vec<u32>,
+        sections: &[(&[u32], &[u8])],
+    ) -> Result<(), crate::Error> {
+        use imap_proto::types::{BodyStructure, ContentEncoding};
+
+        let (common, other) = match input {
+            BodyStructure::Multipart { bodies, .. } => {
+                for (i, body) in bodies.iter().enumerate() {
+                    path.push(i as u32 + 1);
+                    self.add_imap_body_structure(body, path, sections)?;
+                    path.pop();
+                }
+                return Ok(());
+            },
+            BodyStructure::Basic { common, other, .. }
+            | BodyStructure::Text { common, other, .. }
+            | BodyStructure::Message { common, other, .. } => (common, other),
+        };
+        // A non-multipart message body is numbered as part 1:
+        let section_path: &[u32] = if path.is_empty() { &[1] } else { path };
+
+        let media_type = format!("{}/{}", common.ty.ty, common.ty.subtype).to_lowercase();
+        let param = |params: &imap_proto::types::BodyParams, name: &str| {
+            params.iter().flatten().find_map(|(key, value)| {
+                key.eq_ignore_ascii_case(name).then(|| value.to_string())
+            })
+        };
+        let disposition = common
+            .disposition
+            .as_ref()
+            .map(|disposition| disposition.ty.to_lowercase());
+        let name = common
+            .disposition
+            .as_ref()
+            .and_then(|disposition| param(&disposition.params, "filename"))
+            .or_else(|| param(&common.ty.params, "name"));
+        let content_id = other
+            .id
+            .as_ref()
+            .map(|id| id.trim().trim_start_matches('<').trim_end_matches('>').to_string());
+        let is_attachment = match disposition.as_deref() {
+            Some("attachment") => true,
+            Some("inline") => false,
+            _ => content_id.is_none() && common.ty.ty.to_lowercase() != "text",
+        };
+
+        // Decode fetched content by wrapping it in a minimal MIME entity:
+        let content = match sections.iter().find(|(path, _)| *path == section_path) {
+            None => None,
+            Some((_, data)) => {
+                let encoding = match &other.transfer_encoding {
+                    ContentEncoding::SevenBit => "7bit",
+                    ContentEncoding::EightBit => "8bit",
+                    ContentEncoding::Binary => "binary",
+                    ContentEncoding::Base64 => "base64",
+                    ContentEncoding::QuotedPrintable => "quoted-printable",
+                    ContentEncoding::Other(encoding) => encoding.as_ref(),
+                };
+                let charset = param(&common.ty.params, "charset").unwrap_or("us-ascii".into());
+                let mut entity = format!(
+                    "Content-Type: {}; charset=\"{}\"\r\nContent-Transfer-Encoding: {}\r\n\r\n",
+                    media_type, charset, encoding
+                )
+                .into_bytes();
+                entity.extend_from_slice(data);
+                let part = mailparse::parse_mail(&entity)?;
+                let text = match common.ty.ty.eq_ignore_ascii_case("text") {
+                    true => crate::formatters::mime::normalize_text(&part.get_body()?),
+                    false => String::new(),
+                };
+                Some((part.get_body_raw()?, text))
+            },
+        };
+
+        match media_type.as_str() {
+            // A body whose section wasn't fetched is unknown, not empty:
+            "text/plain" if !is_attachment && self.body.is_none() => {
+                self.body = content.map(|(_, text)| text);
+            },
+            "text/html" if !is_attachment && self.html_body.is_none() => {
+                self.html_body = content.map(|(_, text)| text);
+            },
+            _ => {
+                let data = content.map(|(data, _)| data).unwrap_or_default();
+                let file = File {
+                    name,
+                    size: match data.is_empty() {
+                        true => other.octets as u64,
+                        false => data.len() as u64,
+                    },
+                    media_type: Some(media_type),
+                    content_id,
+                    data,
+                    ..Default::default()
+                };
+                match is_attachment {
+                    true => self.attachments.push(file),
+                    false => self.inline_parts.push(file),
+                }
+            },
+        }
+        Ok(())
+    }
 }
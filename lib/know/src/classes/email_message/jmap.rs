@@ -0,0 +1,346 @@
+// This is free and unencumbered software released into the public domain.
+
+/// See: https://datatracker.ietf.org/doc/html/rfc8621#section-4.1.1
+const JMAP_KEYWORDS: [&str; 5] = ["$answered", "$draft", "$flagged", "$forwarded", "$seen"];
+
+impl EmailMessage {
+    /// Converts a JMAP `Email` object, as returned by `Email/get` with the
+    /// `bodyValues` of its text parts fetched.
+    ///
+    /// Parts of other types are referenced by their `blobId`, which becomes
+    /// the file's ID. Their content must be downloaded separately.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc8621#section-4.1
+    pub fn from_jmap(input: &serde_json::Value) -> Result<Self, crate::Error> {
+        use serde_json::Value;
+
+        fn string<'a>(input: &'a Value, key: &'static str) -> Option<&'a str> {
+            input.get(key).and_then(Value::as_str)
+        }
+
+        fn date(input: &Value, key: &'static str) -> Result<Option<DateTime>, crate::Error> {
+            string(input, key)
+                .map(|date| {
+                    date.parse()
+                        .map_err(|error: jiff::Error| {
+                            crate::Error::InvalidProperty(key, error.to_string())
+                        })
+                })
+                .transpose()
+        }
+
+        fn message_ids(input: &Value, key: &'static str) -> Vec<EmailMessageId> {
+            input
+                .get(key)
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .map(EmailMessageId::from)
+                .collect()
+        }
+
+        let mut message = Self {
+            date: date(input, "sentAt")?
+                .or(date(input, "receivedAt")?)
+                .ok_or(crate::Error::MissingProperty("sentAt"))?,
+            received: date(input, "receivedAt")?,
+            subject: string(input, "subject").map(ToString::to_string),
+            id: message_ids(input, "messageId").into_iter().next(),
+            in_reply_to: message_ids(input, "inReplyTo"),
+            references: message_ids(input, "references"),
+            ..Default::default()
+        };
+
+        let mut addresses = |key: &'static str| -> Vec<EmailAddress> {
+            let mut output = Vec::new();
+            for address in input.get(key).and_then(Value::as_array).into_iter().flatten() {
+                // Skip invalid addresses rather than losing the whole message:
+                let email = string(address, "email").unwrap_or_default();
+                let Ok(email) = email.parse::<EmailAddress>() else {
+                    continue;
+                };
+                if let Some(name) = string(address, "name").filter(|name| !name.is_empty()) {
+                    message.names.insert(email.clone(), name.into());
+                }
+                output.push(email);
+            }
+            output
+        };
+        let from = addresses("from");
+        let sender = addresses("sender").into_iter().next();
+        let reply_to = addresses("replyTo");
+        let to = addresses("to");
+        let cc = addresses("cc");
+        let bcc = addresses("bcc");
+        (message.from, message.sender, message.reply_to) = (from, sender, reply_to);
+        (message.to, message.cc, message.bcc) = (to, cc, bcc);
+
+        let urls = |key: &str| -> Option<String> {
+            let urls = input.get(key)?.as_array()?.iter().filter_map(Value::as_str);
+            Some(urls.map(|url| format!("<{}>", url)).collect::<Vec<_>>().join(", "))
+        };
+        message.mailing_list = MailingList::from_headers(
+            string(input, "header:List-Id:asText"),
+            urls("header:List-Post:asURLs").as_deref(),
+            urls("header:List-Unsubscribe:asURLs").as_deref(),
+            urls("header:List-Archive:asURLs").as_deref(),
+            string(input, "header:Precedence:asText"),
+        )
+        .map(Box::new);
+
+        if let Some(keywords) = input.get("keywords").and_then(Value::as_object) {
+            let flags = &mut message.flags;
+            for (keyword, _) in keywords.iter().filter(|(_, set)| set == &&Value::Bool(true)) {
+                match keyword.to_ascii_lowercase().as_str() {
+                    "$answered" => flags.replied = true,
+                    "$draft" => flags.draft = true,
+                    "$flagged" => flags.flagged = true,
+                    "$forwarded" => flags.passed = true,
+                    "$seen" => flags.seen = true,
+                    _ => flags.keywords.push(keyword.clone()),
+                }
+            }
+            flags.keywords.sort();
+            flags.keywords.dedup();
+        }
+        if let Some(mailbox_ids) = input.get("mailboxIds").and_then(Value::as_object) {
+            message.mailboxes = mailbox_ids
+                .iter()
+                .filter(|(_, set)| set == &&Value::Bool(true))
+                .map(|(id, _)| id.clone())
+                .collect();
+        }
+
+        let body_values = input.get("bodyValues");
+        match input.get("bodyStructure") {
+            Some(structure) => message.add_jmap_body_part(structure, body_values)?,
+            None => {
+                for key in ["textBody", "htmlBody", "attachments"] {
+                    for part in input.get(key).and_then(Value::as_array).into_iter().flatten() {
+                        message.add_jmap_body_part(part, body_values)?;
+                    }
+                }
+            },
+        }
+        message.fill_body_from_html();
+        Ok(message)
+    }
+
+    /// Converts this message into a JMAP `Email` object, with the content of
+    /// its text parts in `bodyValues`.
+    ///
+    /// Files are referenced by their ID as the `blobId`, if they have one.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc8621#section-4.1
+    pub fn to_jmap(&self) -> serde_json::Value {
+        use serde_json::{Map, Value, json};
+
+        let addresses = |addresses: &[EmailAddress]| -> Value {
+            addresses
+                .iter()
+                .map(|address| {
+                    json!({
+                        "name": self.display_name(address),
+                        "email": address.as_str(),
+                    })
+                })
+                .collect()
+        };
+        let message_ids = |ids: &[EmailMessageId]| -> Value {
+            match ids.is_empty() {
+                true => Value::Null,
+                false => ids.iter().map(EmailMessageId::as_str).collect(),
+            }
+        };
+
+        let mut body_values = Map::new();
+        let body_structure = self
+            .mime_part()
+            .to_jmap(&mut body_values, &mut 0);
+
+        let mut keywords = Map::new();
+        for (set, keyword) in [
+            self.flags.replied,
+            self.flags.draft,
+            self.flags.flagged,
+            self.flags.passed,
+            self.flags.seen,
+        ]
+        .into_iter()
+        .zip(JMAP_KEYWORDS)
+        {
+            if set {
+                keywords.insert(keyword.into(), true.into());
+            }
+        }
+        for keyword in &self.flags.keywords {
+            keywords.insert(keyword.clone(), true.into());
+        }
+
+        let mut output = json!({
+            "mailboxIds": self
+                .mailboxes
+                .iter()
+                .map(|id| (id.clone(), Value::Bool(true)))
+                .collect::<Map<_, _>>(),
+            "keywords": keywords,
+            "receivedAt": self.received.as_ref().map(|date| {
+                date.as_zoned()
+                    .with_time_zone(jiff::tz::TimeZone::UTC)
+                    .strftime("%FT%TZ")
+                    .to_string()
+            }),
+            "messageId": message_ids(self.id.as_slice()),
+            "inReplyTo": message_ids(&self.in_reply_to),
+            "references": message_ids(&self.references),
+            "sender": match self.sender {
+                Some(ref sender) => addresses(core::slice::from_ref(sender)),
+                None => Value::Null,
+            },
+            "from": addresses(&self.from),
+            "to": addresses(&self.to),
+            "cc": addresses(&self.cc),
+            "bcc": addresses(&self.bcc),
+            "replyTo": addresses(&self.reply_to),
+            "subject": self.subject,
+            "sentAt": self.date.as_zoned().strftime("%FT%T%:z").to_string(),
+            "bodyStructure": body_structure,
+            "bodyValues": body_values,
+        });
+        // See: https://datatracker.ietf.org/doc/html/rfc8621#section-4.1.3
+        if let Some(ref list) = self.mailing_list {
+            let output = output.as_object_mut().unwrap(); // infallible
+            let list_id = list.id.as_ref().map(|id| match list.name {
+                Some(ref name) => format!("{} <{}>", name, id),
+                None => format!("<{}>", id),
+            });
+            output.insert("header:List-Id:asText".into(), list_id.into());
+            for (key, urls) in [
+                ("header:List-Post:asURLs", &list.post),
+                ("header:List-Unsubscribe:asURLs", &list.unsubscribe),
+                ("header:List-Archive:asURLs", &list.archive),
+            ] {
+                let urls = (!urls.is_empty()).then_some(urls);
+                output.insert(key.into(), json!(urls));
+            }
+            output.insert("header:Precedence:asText".into(), json!(list.precedence));
+        }
+        output
+    }
+
+    /// Adds a JMAP `EmailBodyPart` and its subparts to this message.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc8621#section-4.1.4
+    fn add_jmap_body_part(
+        &mut self,
+        input: &serde_json::Value,
+        body_values: Option<&serde_json::Value>,
+    ) -> Result<(), crate::Error> {
+        use serde_json::Value;
+        let string = |key: &str| input.get(key).and_then(Value::as_str);
+
+        let media_type = string("type")
+            .ok_or(crate::Error::MissingProperty("type"))?
+            .to_ascii_lowercase();
+        if let Some(parts) = input.get("subParts").and_then(Value::as_array) {
+            for part in parts {
+                self.add_jmap_body_part(part, body_values)?;
+            }
+            return Ok(());
+        }
+        let disposition = string("disposition").map(str::to_ascii_lowercase);
+        let content_id = string("cid").map(ToString::to_string);
+        let is_attachment = match disposition.as_deref() {
+            Some("attachment") => true,
+            Some(_) => false,
+            None => content_id.is_none() && !media_type.starts_with("text/"),
+        };
+        let text = string("partId")
+            .and_then(|part_id| body_values?.get(part_id)?.get("value")?.as_str())
+            .map(crate::formatters::mime::normalize_text);
+
+        match (media_type.as_str(), text) {
+            ("text/plain", Some(text)) if !is_attachment && self.body.is_none() => {
+                self.body = Some(text);
+            },
+            ("text/html", Some(text)) if !is_attachment && self.html_body.is_none() => {
+                self.html_body = Some(text);
+            },
+            (_, text) => {
+                let data = text.map(String::into_bytes).unwrap_or_default();
+                let file = File {
+                    name: string("name").map(ToString::to_string),
+                    id: string("blobId").map(ToString::to_string),
+                    size: input
+                        .get("size")
+                        .and_then(Value::as_u64)
+                        .unwrap_or(data.len() as u64),
+                    media_type: Some(media_type),
+                    content_id,
+                    data,
+                };
+                match is_attachment {
+                    true => self.attachments.push(file),
+                    false => self.inline_parts.push(file),
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+impl MimePart<'_> {
+    /// Converts the entity into a JMAP `EmailBodyPart`, numbering the leaf
+    /// parts and collecting the text parts' content into `body_values`.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc8621#section-4.1.4
+    fn to_jmap(
+        &self,
+        body_values: &mut serde_json::Map<String, serde_json::Value>,
+        count: &mut usize,
+    ) -> serde_json::Value {
+        use serde_json::json;
+        if let MimePart::Multipart(subtype, parts) = self {
+            return json!({
+                "type": format!("multipart/{}", subtype),
+                "subParts": parts
+                    .iter()
+                    .map(|part| part.to_jmap(body_values, count))
+                    .collect::<Vec<_>>(),
+            });
+        }
+        *count += 1;
+        let part_id = count.to_string();
+        match self {
+            MimePart::Text(text) | MimePart::Html(text) => {
+                body_values.insert(
+                    part_id.clone(),
+                    json!({ "value": text, "isEncodingProblem": false, "isTruncated": false }),
+                );
+                json!({
+                    "partId": part_id,
+                    "type": match self {
+                        MimePart::Html(_) => "text/html",
+                        _ => "text/plain",
+                    },
+                    "charset": "utf-8",
+                    "size": text.len(),
+                })
+            },
+            MimePart::Inline(file) | MimePart::Attachment(file) => json!({
+                "partId": part_id,
+                "blobId": file.id,
+                "type": file.media_type.as_deref().unwrap_or("application/octet-stream"),
+                "name": file.name,
+                "disposition": match self {
+                    MimePart::Inline(_) => "inline",
+                    _ => "attachment",
+                },
+                "cid": file.content_id,
+                "size": file.size,
+            }),
+            MimePart::Multipart(..) => unreachable!(),
+        }
+    }
+}
@@ -1,39 +1,120 @@
 // This is free and unencumbered software released into the public domain.
 
 impl TryFrom<&mail_parser::Message<'_>> for EmailMessage {
-    type Error = ();
+    type Error = crate::Error;
 
     fn try_from(input: &mail_parser::Message) -> Result<Self, Self::Error> {
         use crate::helpers::EmailAddressList;
-        Ok(Self {
+        use mail_parser::{HeaderValue, MessagePart, MimeHeaders, PartType};
+
+        fn message_ids(input: &HeaderValue) -> Vec<EmailMessageId> {
+            match input {
+                HeaderValue::Text(id) => vec![id.into()],
+                HeaderValue::TextList(ids) => ids.iter().map(EmailMessageId::from).collect(),
+                _ => Vec::new(),
+            }
+        }
+
+        fn is_attachment(input: &MessagePart) -> bool {
+            match input.content_disposition() {
+                Some(disposition) => disposition.is_attachment(),
+                None => input.content_id().is_none(),
+            }
+        }
+
+        fn file(input: &MessagePart) -> File {
+            File {
+                name: input.attachment_name().map(ToString::to_string),
+                size: input.len() as u64,
+                media_type: input.content_type().map(|content_type| {
+                    match content_type.subtype() {
+                        Some(subtype) => format!("{}/{}", content_type.ctype(), subtype),
+                        None => content_type.ctype().to_string(),
+                    }
+                }),
+                content_id: input.content_id().map(ToString::to_string),
+                data: input.contents().to_vec(),
+                ..Default::default()
+            }
+        }
+
+        let list_id = match input.list_id() {
+            HeaderValue::Address(mail_parser::Address::List(addrs)) => {
+                addrs.first().map(|addr| match (&addr.name, &addr.address) {
+                    (Some(name), Some(id)) => format!("{} <{}>", name, id),
+                    (name, id) => format!("<{}>", id.as_deref().or(name.as_deref()).unwrap_or("")),
+                })
+            },
+            HeaderValue::Text(text) => Some(text.to_string()),
+            _ => None,
+        };
+
+        let mut names = BTreeMap::new();
+        let mut addresses = |input: Option<&mail_parser::Address>| -> Vec<EmailAddress> {
+            input
+                .and_then(|addresses| EmailAddressList::try_from(addresses).ok())
+                .map(|addresses| {
+                    names.extend(addresses.names().clone());
+                    addresses.into_vec()
+                })
+                .unwrap_or_default()
+        };
+
+        let mut message = Self {
             date: input
                 .date()
-                .expect("message must have a Date header") // FIXME
+                .ok_or(crate::Error::MissingHeader("Date"))?
                 .try_into()
-                .unwrap(),
-            from: input
-                .from()
-                .and_then(|addresses| EmailAddressList::try_from(addresses).ok())
-                .map(EmailAddressList::into_vec)
-                .unwrap_or_default(),
-            sender: Default::default(),   // TODO
-            reply_to: Default::default(), // TODO
-            to: input
-                .to()
-                .and_then(|addresses| EmailAddressList::try_from(addresses).ok())
-                .map(EmailAddressList::into_vec)
-                .unwrap_or_default(),
-            cc: input
-                .cc()
-                .and_then(|addresses| EmailAddressList::try_from(addresses).ok())
-                .map(EmailAddressList::into_vec)
-                .unwrap_or_default(),
-            bcc: Default::default(),
+                .map_err(|error: jiff::Error| {
+                    crate::Error::InvalidHeader("Date", error.to_string())
+                })?,
+            received: Default::default(),
+            from: addresses(input.from()),
+            sender: addresses(input.sender()).into_iter().next(),
+            reply_to: addresses(input.reply_to()),
+            to: addresses(input.to()),
+            cc: addresses(input.cc()),
+            bcc: addresses(input.bcc()),
+            names,
             subject: input.subject().map(ToString::to_string),
             id: input.message_id().map(EmailMessageId::from),
-            in_reply_to: Default::default(), // TODO
-            references: Default::default(),  // TODO
-            body: input.body_text(0).map(|s| s.into_owned()),
-        })
+            in_reply_to: message_ids(input.in_reply_to()),
+            references: message_ids(input.references()),
+            mailing_list: MailingList::from_headers(
+                list_id.as_deref(),
+                input.header_raw("List-Post"),
+                input.header_raw("List-Unsubscribe"),
+                input.header_raw("List-Archive"),
+                input.header_raw("Precedence"),
+            )
+            .map(Box::new),
+            body: input
+                .text_part(0)
+                .filter(|part| matches!(part.body, PartType::Text(_)))
+                .and_then(MessagePart::text_contents)
+                .map(crate::formatters::mime::normalize_text),
+            body_from_html: false,
+            html_body: input
+                .html_part(0)
+                .filter(|part| part.is_text_html())
+                .and_then(MessagePart::text_contents)
+                .map(crate::formatters::mime::normalize_text),
+            inline_parts: input
+                .attachments()
+                .filter(|part| !is_attachment(part))
+                .map(file)
+                .collect(),
+            attachments: input
+                .attachments()
+                .filter(|part| is_attachment(part))
+                .map(file)
+                .collect(),
+            mailboxes: Default::default(),
+            flags: Default::default(),
+            signed_by: Default::default(), // see EmailMessage::verify_dkim
+            arc_sealed_by: Default::default(), // see EmailMessage::verify_arc
+        };
+        message.fill_body_from_html();
+        Ok(message)
     }
 }
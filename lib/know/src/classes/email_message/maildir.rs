@@ -1,9 +1,51 @@
 // This is free and unencumbered software released into the public domain.
 
 impl TryFrom<&mut maildir::MailEntry> for EmailMessage {
-    type Error = maildir::MailEntryError;
+    type Error = crate::Error;
 
     fn try_from(input: &mut maildir::MailEntry) -> Result<Self, Self::Error> {
-        Ok((&input.headers()?).try_into()?)
+        let mut message: Self = (&input.headers()?).try_into()?;
+        message.flags = input.flags().into();
+        Ok(message)
+    }
+}
+
+impl EmailMessage {
+    /// Delivers this message into the given Maildir, returning the unique
+    /// ID of the stored message.
+    ///
+    /// The message is written to `tmp/` and then moved into `new/`, or into
+    /// `cur/` with the message flags in its info suffix if any are set.
+    /// Maildir messages have Unix line endings, so CRLF is stored as LF.
+    pub fn store_in(&self, maildir: &maildir::Maildir) -> Result<String, crate::Error> {
+        let data = self.mime().to_string().replace("\r\n", "\n");
+        Ok(if self.flags.is_empty() {
+            maildir.store_new(data.as_bytes())?
+        } else {
+            maildir.store_cur_with_flags(data.as_bytes(), &self.flags.to_string())?
+        })
+    }
+
+    /// Replaces the flags of the Maildir message with the given ID with the
+    /// flags of this message, moving the message from `new/` to `cur/` if
+    /// needed.
+    ///
+    /// This message's flags are authoritative, including its keywords: to
+    /// keep keyword flags set by other clients, read them from the stored
+    /// message first.
+    pub fn sync_flags_in(&self, maildir: &maildir::Maildir, id: &str) -> Result<(), crate::Error> {
+        let Some(entry) = maildir.find(id) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "mail entry not found",
+            )
+            .into());
+        };
+        let flags = self.flags.to_string();
+        match entry.path().parent().and_then(|dir| dir.file_name()) {
+            Some(dir) if dir == "new" => maildir.move_new_to_cur_with_flags(id, &flags)?,
+            _ => maildir.set_flags(id, &flags)?,
+        }
+        Ok(())
     }
 }
@@ -1,56 +1,149 @@
 // This is free and unencumbered software released into the public domain.
 
 impl TryFrom<&mailparse::ParsedMail<'_>> for EmailMessage {
-    type Error = mailparse::MailParseError;
+    type Error = crate::Error;
 
     fn try_from(input: &mailparse::ParsedMail) -> Result<Self, Self::Error> {
-        (&input.headers).try_into()
+        /// Sorts the leaf parts of a MIME tree into bodies and files.
+        fn walk(
+            message: &mut EmailMessage,
+            input: &mailparse::ParsedMail,
+            is_subpart: bool,
+        ) -> Result<(), crate::Error> {
+            use mailparse::DispositionType;
+            if input.ctype.mimetype.starts_with("multipart/") {
+                for part in &input.subparts {
+                    walk(message, part, true)?;
+                }
+                return Ok(());
+            }
+            let disposition = input.get_content_disposition();
+            let content_id = input
+                .headers
+                .get_first_value("Content-ID")
+                .map(|id| id.trim().trim_start_matches('<').trim_end_matches('>').into());
+            let is_attachment = match disposition.disposition {
+                DispositionType::Attachment => true,
+                DispositionType::Inline => false,
+                _ => content_id.is_none() && !input.ctype.mimetype.starts_with("text/"),
+            };
+            match input.ctype.mimetype.as_str() {
+                "text/plain" if !is_attachment && message.body.is_none() => {
+                    message.body = Some(text_body(&input.get_body()?, is_subpart));
+                },
+                "text/html" if !is_attachment && message.html_body.is_none() => {
+                    message.html_body = Some(text_body(&input.get_body()?, is_subpart));
+                },
+                media_type => {
+                    let data = input.get_body_raw()?;
+                    let file = File {
+                        name: disposition
+                            .params
+                            .get("filename")
+                            .or_else(|| input.ctype.params.get("name"))
+                            .cloned(),
+                        size: data.len() as u64,
+                        media_type: Some(media_type.into()),
+                        content_id,
+                        data,
+                        ..Default::default()
+                    };
+                    match is_attachment {
+                        true => message.attachments.push(file),
+                        false => message.inline_parts.push(file),
+                    }
+                },
+            }
+            Ok(())
+        }
+
+        fn text_body(text: &str, is_subpart: bool) -> String {
+            // mailparse includes the line break preceding a multipart
+            // boundary in the part, though it belongs to the boundary:
+            let text = match is_subpart {
+                true => text
+                    .strip_suffix('\n')
+                    .map(|text| text.strip_suffix('\r').unwrap_or(text))
+                    .unwrap_or(text),
+                false => text,
+            };
+            crate::formatters::mime::normalize_text(text)
+        }
+
+        use mailparse::MailHeaderMap;
+        let mut message: Self = (&input.headers).try_into()?;
+        walk(&mut message, input, false)?;
+        message.fill_body_from_html();
+        Ok(message)
     }
 }
 
 impl TryFrom<&Vec<mailparse::MailHeader<'_>>> for EmailMessage {
-    type Error = mailparse::MailParseError;
+    type Error = crate::Error;
 
     fn try_from(input: &Vec<mailparse::MailHeader<'_>>) -> Result<Self, Self::Error> {
-        use mailparse::{MailHeaderMap, MailParseError};
-        Ok(Self {
-            date: match input.get_first_header("Date") {
-                None => Err(MailParseError::Generic("missing Date header"))?,
-                Some(header) => header
-                    .try_into()
-                    .map_err(|_| MailParseError::Generic("invalid Date header"))?,
-            },
-            from: input
-                .get_all_headers("From")
-                .into_iter()
-                .filter_map(|header| header.try_into().ok())
-                .collect(),
-            sender: input
-                .get_first_value("Sender")
-                .and_then(|header| header.parse().ok()),
-            reply_to: input
-                .get_all_headers("Reply-To")
-                .into_iter()
-                .filter_map(|header| header.try_into().ok())
-                .collect(),
-            to: input
-                .get_all_headers("To")
+        use crate::helpers::EmailAddressList;
+        use mailparse::MailHeaderMap;
+
+        let mut names = BTreeMap::new();
+        let mut addresses = |key: &str| -> Vec<EmailAddress> {
+            input
+                .get_all_headers(key)
                 .into_iter()
-                .filter_map(|header| header.try_into().ok())
-                .collect(),
-            cc: input
-                .get_all_headers("Cc")
+                .filter_map(|header| EmailAddressList::try_from(header).ok())
+                .flat_map(|addresses| {
+                    names.extend(addresses.names().clone());
+                    addresses.into_vec()
+                })
+                .collect()
+        };
+
+        fn message_ids(input: &[mailparse::MailHeader], key: &str) -> Vec<EmailMessageId> {
+            input
+                .get_all_values(key)
                 .into_iter()
-                .filter_map(|header| header.try_into().ok())
-                .collect(),
-            bcc: Default::default(),
+                .filter_map(|value| mailparse::msgidparse(&value).ok())
+                .flat_map(|ids| ids.to_vec())
+                .map(EmailMessageId::from)
+                .collect()
+        }
+
+        Ok(Self {
+            date: input
+                .get_first_header("Date")
+                .ok_or(crate::Error::MissingHeader("Date"))?
+                .try_into()?,
+            received: Default::default(),
+            from: addresses("From"),
+            sender: addresses("Sender").into_iter().next(),
+            reply_to: addresses("Reply-To"),
+            to: addresses("To"),
+            cc: addresses("Cc"),
+            bcc: addresses("Bcc"),
+            names,
             subject: input.get_first_value("Subject"),
             id: input
                 .get_first_value("Message-ID")
                 .and_then(|header| header.parse().ok()),
-            in_reply_to: Default::default(), // TODO
-            references: Default::default(),  // TODO
+            in_reply_to: message_ids(input, "In-Reply-To"),
+            references: message_ids(input, "References"),
+            mailing_list: MailingList::from_headers(
+                input.get_first_value("List-Id").as_deref(),
+                input.get_first_value("List-Post").as_deref(),
+                input.get_first_value("List-Unsubscribe").as_deref(),
+                input.get_first_value("List-Archive").as_deref(),
+                input.get_first_value("Precedence").as_deref(),
+            )
+            .map(Box::new),
             body: Default::default(),
+            body_from_html: false,
+            html_body: Default::default(),
+            inline_parts: Default::default(),
+            attachments: Default::default(),
+            mailboxes: Default::default(),
+            flags: Default::default(),
+            signed_by: Default::default(), // see EmailMessage::verify_dkim
+            arc_sealed_by: Default::default(), // see EmailMessage::verify_arc
         })
     }
 }
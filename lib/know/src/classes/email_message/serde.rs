@@ -24,6 +24,7 @@ impl crate::traits::ToJsonLd for EmailMessage {
                 None => "_:message".into(),
             },
             "date": self.date.to_jsonld()?,
+            "dateReceived": self.received.as_ref().and_then(|x| x.to_jsonld().ok()),
             "from": self.from.iter().filter_map(|x| x.to_jsonld().ok()).collect::<Vec<_>>(),
             "sender": self.sender.as_ref().and_then(|x| x.to_jsonld().ok()),
             "replyTo": self.reply_to.iter().filter_map(|x| x.to_jsonld().ok()).collect::<Vec<_>>(),
@@ -35,6 +36,9 @@ impl crate::traits::ToJsonLd for EmailMessage {
             "inReplyTo": self.in_reply_to.iter().filter_map(|x| x.to_jsonld().ok()).collect::<Vec<_>>(),
             "references": self.references.iter().filter_map(|x| x.to_jsonld().ok()).collect::<Vec<_>>(),
             "body": self.body,
+            "htmlBody": self.html_body,
+            "inlineParts": self.inline_parts.iter().filter_map(|x| x.to_jsonld().ok()).collect::<Vec<_>>(),
+            "attachments": self.attachments.iter().filter_map(|x| x.to_jsonld().ok()).collect::<Vec<_>>(),
         }))
     }
 }
@@ -25,6 +25,14 @@ pub struct File {
 
     pub size: u64,
 
+    /// See: https://www.iana.org/assignments/media-types/media-types.xhtml
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub media_type: Option<String>,
+
+    /// See: https://datatracker.ietf.org/doc/html/rfc2392
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub content_id: Option<String>,
+
     #[cfg_attr(
         feature = "serde",
         serde(
@@ -61,7 +69,10 @@ impl crate::traits::ToJsonLd for File {
                 Some(ref id) => id.clone(),
                 None => "_:file".into(), // TODO: genid
             },
+            "name": self.name,
             "size": self.size,
+            "mediaType": self.media_type,
+            "contentId": self.content_id,
             "data": serialize_data(&self.data, serde_json::value::Serializer)?,
         }))
     }
@@ -6,7 +6,6 @@ use crate::{
     datatypes::DateTime,
     formatters::{DisplayConcise, DisplayDetailed, DisplayInline, DisplayJsonLd, DisplayOneliner},
     prelude::*,
-    traits::ToJsonLd,
 };
 use alloc::fmt;
 
@@ -133,8 +132,10 @@ impl fmt::Display for DisplayDetailed<'_, FileMetadata> {
     }
 }
 
+#[cfg(feature = "serde")]
 impl fmt::Display for DisplayJsonLd<'_, FileMetadata> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use crate::traits::ToJsonLd;
         write!(f, "{}", self.0.to_jsonld().unwrap())
     }
 }
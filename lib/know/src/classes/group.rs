@@ -1,23 +1,40 @@
 // This is free and unencumbered software released into the public domain.
 
-use super::ThingLike;
+use super::{PersonRef, ThingLike};
 use crate::prelude::*;
 
+#[cfg(feature = "serde")]
+use serde_with::serde_as;
+
 pub trait GroupLike: ThingLike {}
 
 #[derive(Debug, Clone, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", cfg_eval::cfg_eval, serde_as)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[cfg_attr(
     feature = "serde",
     serde(default, tag = "@type", rename_all = "camelCase")
 )]
 pub struct Group {
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "@id", skip_serializing_if = "Option::is_none")
+    )]
+    pub id: Option<String>,
+
     pub name: Name,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, alias = "member", skip_serializing_if = "Vec::is_empty"),
+        serde_as(as = "serde_with::OneOrMany<_>")
+    )]
+    pub members: Vec<PersonRef>,
 }
 
 impl ThingLike for Group {
     fn id(&self) -> Option<&str> {
-        None
+        self.id.as_deref()
     }
 
     fn name(&self) -> Option<&Name> {
@@ -1,8 +1,12 @@
 // This is free and unencumbered software released into the public domain.
 
-use super::ThingLike;
+use super::{Group, ThingLike};
 use crate::{
-    datatypes::{DateTime, InstantMessageHandle, InstantMessageId, InstantMessageRecipient},
+    datatypes::{
+        DateTime, InstantMessageAttachment, InstantMessageHandle, InstantMessageId,
+        InstantMessagePlatform, InstantMessageReaction, InstantMessageRecipient,
+        InstantMessageRevision,
+    },
     formatters::{DisplayConcise, DisplayDetailed, DisplayInline, DisplayJsonLd, DisplayOneliner},
     prelude::Name,
     traits,
@@ -16,13 +20,109 @@ pub struct InstantMessage {
     pub date: DateTime,
     pub from: InstantMessageHandle,
     pub to: InstantMessageRecipient,
-    pub platform: Option<String>,
+    pub platform: Option<InstantMessagePlatform>,
     pub references: Option<InstantMessageId>,
     pub content: String,
-    // TODO: group context?
+
+    /// The original sender of a forwarded message.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub forwarded_from: Option<InstantMessageHandle>,
+
+    /// When the content was last edited.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub edited: Option<DateTime>,
+
+    /// The earlier versions of the content, oldest first.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub revisions: Vec<InstantMessageRevision>,
+
+    /// Whether the message was deleted, in which case the content is
+    /// usually empty.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "core::ops::Not::not")
+    )]
+    pub deleted: bool,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub reactions: Vec<InstantMessageReaction>,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub attachments: Vec<InstantMessageAttachment>,
 }
 
 impl InstantMessage {
+    pub fn new(
+        date: DateTime,
+        from: InstantMessageHandle,
+        to: InstantMessageRecipient,
+        content: impl Into<String>,
+    ) -> Self {
+        Self {
+            id: None,
+            date,
+            from,
+            to,
+            platform: None,
+            references: None,
+            content: content.into(),
+            forwarded_from: None,
+            edited: None,
+            revisions: Vec::new(),
+            deleted: false,
+            reactions: Vec::new(),
+            attachments: Vec::new(),
+        }
+    }
+
+    /// Returns the group chat or channel the message was sent to, if any.
+    pub fn group(&self) -> Option<Group> {
+        self.to.group()
+    }
+
+    /// Replaces the content with an edited version, keeping the current
+    /// content as a revision.
+    pub fn edit(&mut self, date: DateTime, content: impl Into<String>) {
+        let previous = InstantMessageRevision {
+            date: self
+                .edited
+                .replace(date)
+                .unwrap_or_else(|| self.date.clone()),
+            content: core::mem::replace(&mut self.content, content.into()),
+        };
+        self.revisions.push(previous);
+    }
+
+    /// Returns the reactions grouped by emoji, in order of first use.
+    pub fn reaction_counts(&self) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> = Vec::new();
+        for reaction in &self.reactions {
+            match counts
+                .iter_mut()
+                .find(|(emoji, _)| *emoji == reaction.emoji)
+            {
+                Some((_, count)) => *count += 1,
+                None => counts.push((&reaction.emoji, 1)),
+            }
+        }
+        counts
+    }
+
     pub fn inline(&self) -> DisplayInline<'_, InstantMessage> {
         DisplayInline(self)
     }
@@ -71,12 +171,40 @@ impl fmt::Display for DisplayDetailed<'_, InstantMessage> {
         writeln!(f, "\tDate: {}", self.0.date.inline())?;
         writeln!(f, "\tFrom: {}", self.0.from.inline())?;
         writeln!(f, "\tTo: {}", self.0.to.inline())?;
+        if let Some(group) = self.0.to.as_group() {
+            for member in &group.members {
+                writeln!(f, "\tMember: {}", member.inline())?;
+            }
+        }
         if let Some(ref platform) = self.0.platform {
             writeln!(f, "\tPlatform: {}", platform)?;
         }
         if let Some(ref references) = self.0.references {
             writeln!(f, "\tReferences: {}", references.inline())?;
         }
+        if let Some(ref forwarded_from) = self.0.forwarded_from {
+            writeln!(f, "\tForwarded-From: {}", forwarded_from.inline())?;
+        }
+        if let Some(ref edited) = self.0.edited {
+            writeln!(f, "\tEdited: {}", edited.inline())?;
+        }
+        for revision in &self.0.revisions {
+            writeln!(
+                f,
+                "\tRevision: {} {}",
+                revision.date.inline(),
+                revision.content
+            )?;
+        }
+        if self.0.deleted {
+            writeln!(f, "\tDeleted: yes")?;
+        }
+        for reaction in &self.0.reactions {
+            writeln!(f, "\tReaction: {}", reaction.inline())?;
+        }
+        for attachment in &self.0.attachments {
+            writeln!(f, "\tAttachment: {}", attachment.inline())?;
+        }
         Ok(())
     }
 }
@@ -107,9 +235,30 @@ impl traits::ToJsonLd for InstantMessage {
                 None => "_:message".into(),
             },
             "from": self.from.to_jsonld()?,
-            "to": self.to,
-            "platform": self.platform,
+            "to": self.to.to_jsonld()?,
+            "platform": self.platform.as_ref().map(InstantMessagePlatform::as_str),
             "content": self.content,
+            "forwardedFrom": match self.forwarded_from {
+                Some(ref handle) => Some(handle.to_jsonld()?),
+                None => None,
+            },
+            "dateModified": self.edited.as_ref().map(|date| date.to_string()),
+            "revisions": self
+                .revisions
+                .iter()
+                .map(|revision| revision.to_jsonld())
+                .collect::<serde_json::Result<Vec<_>>>()?,
+            "deleted": self.deleted,
+            "reactions": self
+                .reactions
+                .iter()
+                .map(|reaction| reaction.to_jsonld())
+                .collect::<serde_json::Result<Vec<_>>>()?,
+            "attachments": self
+                .attachments
+                .iter()
+                .map(|attachment| attachment.to_jsonld())
+                .collect::<serde_json::Result<Vec<_>>>()?,
         }))
     }
 }
@@ -123,3 +272,97 @@ impl ThingLike for InstantMessage {
         None
     }
 }
+
+#[cfg(feature = "matrix")]
+include!("instant_message/matrix.rs");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classes::{AudioFrame, Image};
+
+    #[test]
+    fn test_edits_and_reactions() {
+        let mut message = InstantMessage::new(
+            1_700_000_000.into(),
+            "alice".parse().unwrap(),
+            InstantMessageRecipient::Direct("bob".parse().unwrap()),
+            "See you at 7",
+        );
+        message.edit(1_700_000_060.into(), "See you at 8");
+        message.edit(1_700_000_120.into(), "See you at 9");
+        assert_eq!(message.content, "See you at 9");
+        assert_eq!(message.edited, Some(1_700_000_120.into()));
+        assert_eq!(
+            message.revisions,
+            [
+                InstantMessageRevision {
+                    date: 1_700_000_000.into(),
+                    content: "See you at 7".into(),
+                },
+                InstantMessageRevision {
+                    date: 1_700_000_060.into(),
+                    content: "See you at 8".into(),
+                },
+            ]
+        );
+
+        for (emoji, from) in [("👍", "bob"), ("❤️", "carol"), ("👍", "dave")] {
+            let reaction = InstantMessageReaction::new(emoji, from.parse().unwrap());
+            message.reactions.push(reaction);
+        }
+        assert_eq!(message.reaction_counts(), [("👍", 2), ("❤️", 1)]);
+
+        message
+            .attachments
+            .push(InstantMessageAttachment::Image(Image {
+                source: Some("IMG-20231114-WA0001.jpg".into()),
+                ..Default::default()
+            }));
+        message
+            .attachments
+            .push(InstantMessageAttachment::VoiceNote(AudioFrame::default()));
+        let detailed = message.detailed().to_string();
+        assert!(detailed.contains("\tReaction: ❤️ carol\n"));
+        assert!(detailed.contains("\tAttachment: 🖼️ IMG-20231114-WA0001.jpg\n"));
+        assert!(detailed.contains("\tAttachment: 🎤\n"));
+    }
+
+    #[cfg(feature = "matrix")]
+    #[test]
+    fn test_matrix() {
+        let events: Vec<serde_json::Value> =
+            serde_json::from_str(include_str!("../../test/matrix/timeline.json")).unwrap();
+        let reply = InstantMessage::from_matrix(&events[2]).unwrap();
+        assert_eq!(
+            reply.from,
+            InstantMessageHandle::Matrix("@bob:matrix.org".into())
+        );
+        assert_eq!(reply.to.as_str(), "!ops:example.org");
+        assert_eq!(reply.date, 1_704_103_260.into());
+        assert_eq!(reply.references.as_ref().unwrap().as_str(), "$hello");
+        assert_eq!(reply.content, "Hi Alice");
+        assert_eq!(
+            reply.to_matrix(),
+            [serde_json::json!({
+                "msgtype": "m.text",
+                "body": "Hi Alice",
+                "m.relates_to": {"m.in_reply_to": {"event_id": "$hello"}},
+            })]
+        );
+
+        let image = InstantMessage::from_matrix(&events[4]).unwrap();
+        assert_eq!(image.content, "");
+        let content = &image.to_matrix()[0];
+        assert_eq!(content["msgtype"], "m.image");
+        assert_eq!(content["url"], "mxc://matrix.org/AbCdEfGh");
+        assert_eq!(content["info"]["w"], 1024);
+
+        let redacted = InstantMessage::from_matrix(&events[8]).unwrap();
+        assert!(redacted.deleted);
+        assert!(redacted.to_matrix().is_empty());
+        assert!(
+            InstantMessage::from_matrix(&serde_json::json!({"type": "m.room.message"})).is_err()
+        );
+    }
+}
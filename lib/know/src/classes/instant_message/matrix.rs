@@ -0,0 +1,227 @@
+// This is free and unencumbered software released into the public domain.
+
+impl InstantMessage {
+    /// Converts a Matrix `m.room.message` or `m.sticker` room event, in the
+    /// client-server API format.
+    ///
+    /// An edit is converted with its new content as a message of its own,
+    /// and a redacted event as a deleted message. Use
+    /// `Conversation::from_matrix` to apply edits, redactions and reactions
+    /// to the messages they relate to.
+    ///
+    /// See: https://spec.matrix.org/latest/client-server-api/#mroommessage
+    pub fn from_matrix(input: &serde_json::Value) -> Result<Self, crate::Error> {
+        use serde_json::Value;
+
+        let string = |key: &'static str| -> Result<&str, crate::Error> {
+            input
+                .get(key)
+                .and_then(Value::as_str)
+                .ok_or(crate::Error::MissingProperty(key))
+        };
+
+        let event_type = string("type")?;
+        let room_id = string("room_id")?;
+        let content = input.get("content").unwrap_or(&Value::Null);
+        let relates_to = content.get("m.relates_to").unwrap_or(&Value::Null);
+        let is_edit = relates_to.get("rel_type").and_then(Value::as_str) == Some("m.replace");
+        let new_content = match content.get("m.new_content") {
+            Some(new_content) if is_edit => new_content,
+            _ => content,
+        };
+
+        let mut message = Self::new(
+            matrix_timestamp(input)?,
+            InstantMessageHandle::Matrix(string("sender")?.into()),
+            InstantMessageRecipient::Group(crate::datatypes::InstantMessageGroup {
+                platform: Some(InstantMessagePlatform::Matrix),
+                ..crate::datatypes::InstantMessageGroup::new(room_id)
+            }),
+            "",
+        );
+        message.id = Some(string("event_id")?.into());
+        message.platform = Some(InstantMessagePlatform::Matrix);
+        if !is_edit {
+            message.references = relates_to
+                .pointer("/m.in_reply_to/event_id")
+                .and_then(Value::as_str)
+                .map(InstantMessageId::from);
+        }
+        if input.pointer("/unsigned/redacted_because").is_some() {
+            message.deleted = true;
+            return Ok(message);
+        }
+
+        let body = new_content
+            .get("body")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let url = new_content
+            .get("url")
+            .and_then(Value::as_str)
+            .map(String::from);
+        let info = new_content.get("info").unwrap_or(&Value::Null);
+        let dimension = |key: &str| {
+            info.get(key)
+                .and_then(Value::as_u64)
+                .map(|value| value as usize)
+        };
+        let image = || crate::classes::Image {
+            width: dimension("w"),
+            height: dimension("h"),
+            source: url.clone(),
+            ..Default::default()
+        };
+        let filename = new_content.get("filename").and_then(Value::as_str);
+        // A body that differs from the file name is a caption:
+        let caption = match filename {
+            Some(filename) if filename != body => body,
+            _ => "",
+        };
+        let msgtype = new_content.get("msgtype").and_then(Value::as_str);
+        match (event_type, msgtype) {
+            ("m.sticker", _) => {
+                message
+                    .attachments
+                    .push(InstantMessageAttachment::Sticker(image()));
+            },
+            (_, Some("m.image")) => {
+                message
+                    .attachments
+                    .push(InstantMessageAttachment::Image(image()));
+                message.content = caption.into();
+            },
+            (_, Some("m.audio")) if new_content.get("org.matrix.msc3245.voice").is_some() => {
+                let audio = crate::classes::AudioFrame {
+                    id: url,
+                    ..Default::default()
+                };
+                message
+                    .attachments
+                    .push(InstantMessageAttachment::VoiceNote(audio));
+            },
+            (_, Some("m.audio" | "m.file" | "m.video")) => {
+                let file = crate::classes::File {
+                    id: url,
+                    name: Some(filename.unwrap_or(body).into()),
+                    size: info.get("size").and_then(Value::as_u64).unwrap_or_default(),
+                    media_type: info
+                        .get("mimetype")
+                        .and_then(Value::as_str)
+                        .map(String::from),
+                    ..Default::default()
+                };
+                message
+                    .attachments
+                    .push(InstantMessageAttachment::File(file));
+                message.content = caption.into();
+            },
+            _ => {
+                message.content = match message.references {
+                    Some(_) => strip_matrix_reply_fallback(body).into(),
+                    None => body.into(),
+                };
+            },
+        }
+        Ok(message)
+    }
+
+    /// Converts the message to the contents of Matrix `m.room.message`
+    /// events, one for each attachment with an `mxc://` URL and one for the
+    /// text, for sending with `PUT /rooms/{roomId}/send/m.room.message`.
+    ///
+    /// See: https://spec.matrix.org/latest/client-server-api/#put_matrixclientv3roomsroomidsendeventtypetxnid
+    pub fn to_matrix(&self) -> Vec<serde_json::Value> {
+        use serde_json::{Value, json};
+
+        let mut output = Vec::new();
+        if self.deleted {
+            return output;
+        }
+        let mxc = |url: Option<&String>| url.filter(|url| url.starts_with("mxc://")).cloned();
+        for attachment in &self.attachments {
+            let content = match attachment {
+                InstantMessageAttachment::Image(image)
+                | InstantMessageAttachment::Sticker(image) => {
+                    let Some(url) = mxc(image.source.as_ref()) else {
+                        continue;
+                    };
+                    json!({
+                        "msgtype": "m.image",
+                        "body": "image",
+                        "url": url,
+                        "info": {"w": image.width, "h": image.height},
+                    })
+                },
+                InstantMessageAttachment::VoiceNote(audio) => {
+                    let Some(url) = mxc(audio.id.as_ref()) else {
+                        continue;
+                    };
+                    json!({
+                        "msgtype": "m.audio",
+                        "body": "Voice message",
+                        "url": url,
+                        "org.matrix.msc3245.voice": {},
+                    })
+                },
+                InstantMessageAttachment::File(file) => {
+                    let Some(url) = mxc(file.id.as_ref()) else {
+                        continue;
+                    };
+                    let name = file.name.as_deref().unwrap_or("file");
+                    json!({
+                        "msgtype": "m.file",
+                        "body": name,
+                        "filename": name,
+                        "url": url,
+                        "info": {"mimetype": file.media_type, "size": file.size},
+                    })
+                },
+            };
+            output.push(content);
+        }
+        if !self.content.is_empty() {
+            output.push(json!({
+                "msgtype": "m.text",
+                "body": self.content,
+            }));
+        }
+        if let Some(ref references) = self.references
+            && let Some(Value::Object(content)) = output.first_mut()
+        {
+            content.insert(
+                "m.relates_to".into(),
+                json!({"m.in_reply_to": {"event_id": references.as_str()}}),
+            );
+        }
+        output
+    }
+}
+
+/// Parses the `origin_server_ts` of a Matrix event, in milliseconds since
+/// the Unix epoch.
+pub(super) fn matrix_timestamp(input: &serde_json::Value) -> Result<DateTime, crate::Error> {
+    let millis = input
+        .get("origin_server_ts")
+        .ok_or(crate::Error::MissingProperty("origin_server_ts"))?;
+    millis
+        .as_i64()
+        .and_then(|millis| jiff::Timestamp::from_millisecond(millis).ok())
+        .map(DateTime::from)
+        .ok_or_else(|| crate::Error::InvalidProperty("origin_server_ts", millis.to_string()))
+}
+
+/// Removes the quote of the replied-to message that clients prepend to
+/// the body of a reply.
+///
+/// See: https://spec.matrix.org/v1.12/client-server-api/#fallbacks-for-rich-replies
+fn strip_matrix_reply_fallback(body: &str) -> &str {
+    if !body.starts_with("> ") {
+        return body;
+    }
+    let mut rest = body;
+    while let Some(line) = rest.strip_prefix("> ") {
+        rest = line.split_once('\n').map(|(_, rest)| rest).unwrap_or_default();
+    }
+    rest.strip_prefix('\n').unwrap_or(rest)
+}
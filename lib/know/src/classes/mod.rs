@@ -1,6 +1,7 @@
 // This is free and unencumbered software released into the public domain.
 
 mod audio_frame;
+pub(crate) mod conversation;
 mod email_message;
 mod event;
 mod file;
@@ -18,6 +19,7 @@ mod software_release;
 mod thing;
 
 pub use audio_frame::*;
+pub use conversation::*;
 pub use email_message::*;
 pub use event::*;
 pub use file::*;
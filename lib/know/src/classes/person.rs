@@ -177,7 +177,7 @@ impl ThingLike for Person {
     }
 
     fn name(&self) -> Option<&Name> {
-        Some(&self.name.as_ref())
+        Some(self.name.as_ref())
     }
 }
 
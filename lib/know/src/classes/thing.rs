@@ -12,6 +12,7 @@ pub trait ThingLike {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Thing {
     AudioFrame(AudioFrame),
+    Conversation(Conversation),
     EmailMessage(EmailMessage),
     Event(Event),
     File(File),
@@ -32,6 +33,7 @@ impl Debug for Thing {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             Thing::AudioFrame(a) => a.fmt(f),
+            Thing::Conversation(x) => x.fmt(f),
             Thing::EmailMessage(x) => x.fmt(f),
             Thing::Event(x) => x.fmt(f),
             Thing::File(x) => x.fmt(f),
@@ -56,6 +58,7 @@ impl ThingLike for Thing {
     fn id(&self) -> Option<&str> {
         match self {
             Thing::AudioFrame(a) => a.id(),
+            Thing::Conversation(c) => c.id(),
             Thing::EmailMessage(_e) => todo!(), // TODO
             Thing::Event(e) => e.id(),
             Thing::File(f) => f.id(),
@@ -76,6 +79,7 @@ impl ThingLike for Thing {
     fn name(&self) -> Option<&Name> {
         match self {
             Thing::AudioFrame(a) => a.name(),
+            Thing::Conversation(c) => c.name(),
             Thing::EmailMessage(_e) => todo!(), // TODO
             Thing::Event(e) => e.name(),
             Thing::File(f) => f.name(),
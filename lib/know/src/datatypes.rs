@@ -15,18 +15,39 @@ pub use duration::*;
 mod email_address;
 pub use email_address::*;
 
+mod email_message_flags;
+pub use email_message_flags::*;
+
 mod email_message_id;
 pub use email_message_id::*;
 
+mod instant_message_attachment;
+pub use instant_message_attachment::*;
+
+mod instant_message_group;
+pub use instant_message_group::*;
+
 mod instant_message_handle;
 pub use instant_message_handle::*;
 
 mod instant_message_id;
 pub use instant_message_id::*;
 
+mod instant_message_platform;
+pub use instant_message_platform::*;
+
+mod instant_message_reaction;
+pub use instant_message_reaction::*;
+
 mod instant_message_recipient;
 pub use instant_message_recipient::*;
 
+mod instant_message_revision;
+pub use instant_message_revision::*;
+
+mod mailing_list;
+pub use mailing_list::*;
+
 mod person_name;
 pub use person_name::*;
 
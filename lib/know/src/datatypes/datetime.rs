@@ -65,7 +65,7 @@ impl fmt::Display for DisplayMime<'_, DateTime> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use jiff::fmt::{StdFmtWrite, rfc2822::DateTimePrinter};
         static P: DateTimePrinter = DateTimePrinter::new();
-        P.print_zoned(&self.0.as_zoned(), StdFmtWrite(f))
+        P.print_zoned(self.0.as_zoned(), StdFmtWrite(f))
             .map_err(|_| fmt::Error)
     }
 }
@@ -74,8 +74,7 @@ impl FromStr for DateTime {
     type Err = jiff::Error;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        if input.ends_with('Z') {
-            let without_z = &input[..input.len() - 1];
+        if let Some(without_z) = input.strip_suffix('Z') {
             jiff::civil::DateTime::strptime("%FT%T.%f", without_z)
                 .or_else(|_| jiff::civil::DateTime::strptime("%FT%T", without_z))
                 .and_then(|dt| dt.to_zoned(jiff::tz::TimeZone::UTC))
@@ -128,33 +127,35 @@ impl TryFrom<&mail_parser::DateTime> for DateTime {
 
 #[cfg(feature = "mail-parser")]
 impl TryFrom<&mail_parser::HeaderValue<'_>> for DateTime {
-    type Error = jiff::Error;
+    type Error = crate::Error;
 
     fn try_from(input: &mail_parser::HeaderValue) -> Result<Self, Self::Error> {
+        use crate::Error::InvalidHeader;
         match input {
-            mail_parser::HeaderValue::DateTime(value) => value.try_into(),
-            _ => Err(jiff::Error::from_args(format_args!("not a date header"))), // unreachable?
+            mail_parser::HeaderValue::DateTime(value) => value
+                .try_into()
+                .map_err(|error: jiff::Error| InvalidHeader("Date", error.to_string())),
+            _ => Err(InvalidHeader("Date", "not a date".into())),
         }
     }
 }
 
 #[cfg(feature = "mailparse")]
 impl TryFrom<&mailparse::MailHeader<'_>> for DateTime {
-    // `mailparse::MailParseError` if UTF-8 decoding fails
-    // `jiff::Error` if RFC-2822 date parsing fails
-    // See: https://github.com/BurntSushi/jiff/issues/262
-    type Error = Box<dyn core::error::Error>;
+    type Error = crate::Error;
 
     fn try_from(input: &mailparse::MailHeader) -> Result<Self, Self::Error> {
+        // See: https://github.com/BurntSushi/jiff/issues/262
         let input_str = input.get_value_utf8()?;
-        let input_date = jiff::fmt::rfc2822::parse(&input_str)?;
+        let input_date = jiff::fmt::rfc2822::parse(&input_str)
+            .map_err(|error| crate::Error::InvalidHeader("Date", error.to_string()))?;
         Ok(Self(input_date))
     }
 }
 
-impl Into<jiff::Zoned> for DateTime {
-    fn into(self) -> jiff::Zoned {
-        self.0
+impl From<DateTime> for jiff::Zoned {
+    fn from(input: DateTime) -> Self {
+        input.0
     }
 }
 
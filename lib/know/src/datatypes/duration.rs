@@ -70,13 +70,13 @@ impl From<jiff::Span> for Duration {
 
 impl From<&jiff::Span> for Duration {
     fn from(input: &jiff::Span) -> Self {
-        Self(input.clone())
+        Self(*input)
     }
 }
 
-impl Into<jiff::Span> for Duration {
-    fn into(self) -> jiff::Span {
-        self.0
+impl From<Duration> for jiff::Span {
+    fn from(input: Duration) -> Self {
+        input.0
     }
 }
 
@@ -3,16 +3,219 @@
 use crate::{formatters::DisplayInline, traits};
 use alloc::{fmt, str::FromStr};
 
+/// Punycode, for the `xn--` ASCII form of internationalized domains.
+mod punycode;
+
+/// An email address, with its domain normalized to lowercase Unicode and its
+/// local part as given, since local parts are case-sensitive.
+///
 /// See: https://datatracker.ietf.org/doc/html/rfc5322#section-3.6
+/// See: https://datatracker.ietf.org/doc/html/rfc5321#section-4.1.2
+/// See: https://datatracker.ietf.org/doc/html/rfc6531#section-3.3
 #[derive(Debug, Clone, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EmailAddress(String);
 
+/// How strictly to validate an email address.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum EmailAddressMode {
+    /// Also accepts the display forms `Alice <alice@example.org>` and
+    /// `mailto:alice@example.org`, and the misplaced dots and underscores
+    /// that real-world mail systems produce, e.g., `alice.@example.org`.
+    #[default]
+    Lenient,
+
+    /// Accepts only a mailbox as specified for SMTP, with UTF-8 allowed in
+    /// the local part and domain.
+    Strict,
+}
+
+/// The reason an email address is invalid.
+#[derive(Clone, Debug, Eq, Hash, PartialEq, thiserror::Error)]
+#[non_exhaustive]
+pub enum EmailAddressError {
+    #[error("empty email address")]
+    Empty,
+
+    #[error("missing @ between local part and domain")]
+    MissingAt,
+
+    #[error("empty local part")]
+    EmptyLocalPart,
+
+    #[error("empty domain")]
+    EmptyDomain,
+
+    #[error("local part is longer than 64 octets")]
+    LocalPartTooLong,
+
+    #[error("domain is longer than 255 octets")]
+    DomainTooLong,
+
+    #[error("email address is longer than 254 octets")]
+    TooLong,
+
+    #[error("invalid character {0:?} in local part")]
+    InvalidLocalPartCharacter(char),
+
+    #[error("leading, trailing, or consecutive dot in local part")]
+    InvalidDot,
+
+    #[error("unterminated or invalid quoted local part")]
+    InvalidQuotedString,
+
+    #[error("invalid character {0:?} in domain")]
+    InvalidDomainCharacter(char),
+
+    #[error("invalid domain label {0:?}")]
+    InvalidLabel(String),
+
+    #[error("domain label {0:?} is longer than 63 octets")]
+    LabelTooLong(String),
+
+    #[error("invalid Punycode in domain label {0:?}")]
+    InvalidPunycode(String),
+
+    #[error("invalid address literal {0:?}")]
+    InvalidAddressLiteral(String),
+}
+
 impl EmailAddress {
+    /// Parses and normalizes an email address.
+    pub fn parse(input: &str, mode: EmailAddressMode) -> Result<Self, EmailAddressError> {
+        use EmailAddressError::*;
+        let lenient = mode == EmailAddressMode::Lenient;
+
+        let mut address = input.trim();
+        if lenient {
+            // Check if the input contains angle brackets:
+            if let Some(start) = address.rfind('<')
+                && let Some(end) = address.rfind('>')
+                && start < end
+            {
+                address = address[start + 1..end].trim();
+            }
+            if let Some(scheme) = address.get(..7)
+                && scheme.eq_ignore_ascii_case("mailto:")
+            {
+                address = address[7..].split('?').next().unwrap_or_default();
+            }
+        }
+        if address.is_empty() {
+            return Err(Empty);
+        }
+
+        // Domains can't contain an @, but quoted local parts can:
+        let (local_part, domain) = address.rsplit_once('@').ok_or(MissingAt)?;
+        let local_part = parse_local_part(local_part, lenient)?;
+        let domain = parse_domain(domain, lenient)?;
+
+        let output = Self(format!("{}@{}", local_part, domain));
+        if !lenient {
+            if local_part.len() > 64 {
+                return Err(LocalPartTooLong);
+            }
+            let ascii_domain = output.ascii_domain();
+            if ascii_domain.len() > 255 {
+                return Err(DomainTooLong);
+            }
+            // See: https://www.rfc-editor.org/errata/eid1690
+            if local_part.len() + 1 + ascii_domain.len() > 254 {
+                return Err(TooLong);
+            }
+        }
+        Ok(output)
+    }
+
+    /// Parses an email address in [`EmailAddressMode::Strict`] mode.
+    pub fn parse_strict(input: &str) -> Result<Self, EmailAddressError> {
+        Self::parse(input, EmailAddressMode::Strict)
+    }
+
     pub fn as_str(&self) -> &str {
         &self.0
     }
 
+    /// The part before the `@`, e.g., `alice+news` for
+    /// `alice+news@example.org`.
+    pub fn local_part(&self) -> &str {
+        self.0
+            .rsplit_once('@')
+            .map_or(&self.0, |(local_part, _)| local_part)
+    }
+
+    /// The part after the `@`, in Unicode, e.g., `bücher.example`.
+    pub fn domain(&self) -> &str {
+        self.0.rsplit_once('@').map_or("", |(_, domain)| domain)
+    }
+
+    /// The domain in ASCII, with internationalized labels encoded as
+    /// Punycode, e.g., `xn--bcher-kva.example`.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc5890#section-2.3.2.1
+    pub fn ascii_domain(&self) -> String {
+        let domain = self.domain();
+        if domain.is_ascii() {
+            return domain.into();
+        }
+        domain
+            .split('.')
+            .map(|label| match label.is_ascii() {
+                true => label.into(),
+                false => match punycode::encode(label) {
+                    Some(label) => format!("xn--{}", label),
+                    None => label.into(), // unreachable for parsed labels
+                },
+            })
+            .collect::<Vec<String>>()
+            .join(".")
+    }
+
+    /// Returns the address with its domain in ASCII, for servers and
+    /// headers that don't support UTF-8, or `None` if the local part isn't
+    /// ASCII.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc6531#section-3.2
+    pub fn to_ascii(&self) -> Option<String> {
+        let local_part = self.local_part();
+        local_part
+            .is_ascii()
+            .then(|| format!("{}@{}", local_part, self.ascii_domain()))
+    }
+
+    /// The tag of a subaddress, e.g., `news` for `alice+news@example.org`.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc5233
+    pub fn subaddress(&self) -> Option<&str> {
+        let local_part = self.local_part();
+        if local_part.starts_with('"') {
+            return None;
+        }
+        local_part.split_once('+').map(|(_, tag)| tag)
+    }
+
+    /// Returns the address without its subaddress, e.g.,
+    /// `alice@example.org` for `alice+news@example.org`.
+    pub fn without_subaddress(&self) -> Self {
+        match self.subaddress() {
+            Some(tag) => {
+                let user = &self.local_part()[..self.local_part().len() - tag.len() - 1];
+                Self(format!("{}@{}", user, self.domain()))
+            },
+            None => self.clone(),
+        }
+    }
+
+    /// Returns the address with its local part in lowercase, for comparing
+    /// addresses of the many providers that ignore its case.
+    pub fn to_lowercase(&self) -> Self {
+        Self(format!(
+            "{}@{}",
+            self.local_part().to_lowercase(),
+            self.domain()
+        ))
+    }
+
     pub fn inline(&self) -> DisplayInline<'_, EmailAddress> {
         DisplayInline(self)
     }
@@ -31,36 +234,135 @@ impl fmt::Display for DisplayInline<'_, EmailAddress> {
 }
 
 impl FromStr for EmailAddress {
-    type Err = ();
+    type Err = EmailAddressError;
 
+    /// Parses an email address in [`EmailAddressMode::Lenient`] mode.
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        let trimmed = input.trim();
-
-        // Check if the input contains angle brackets:
-        if let Some(start) = trimmed.rfind('<') {
-            if let Some(end) = trimmed.rfind('>') {
-                if start < end {
-                    let email = trimmed[start + 1..end].trim();
-                    if !email.is_empty() {
-                        return Ok(EmailAddress(email.to_lowercase()));
-                    }
-                }
-            }
-        }
+        Self::parse(input, EmailAddressMode::Lenient)
+    }
+}
 
-        // If no angle brackets or parsing failed, treat the whole input as email:
-        if !trimmed.is_empty() {
-            Ok(EmailAddress(trimmed.to_lowercase()))
-        } else {
-            Err(())
+impl From<EmailAddress> for String {
+    fn from(input: EmailAddress) -> Self {
+        input.0
+    }
+}
+
+/// Characters allowed in atoms besides letters, digits, and UTF-8.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc5322#section-3.2.3
+const ATEXT: &str = "!#$%&'*+-/=?^_`{|}~";
+
+fn is_atext(c: char) -> bool {
+    c.is_ascii_alphanumeric() || ATEXT.contains(c) || (!c.is_ascii() && !c.is_control())
+}
+
+/// Validates a local part, returning it unquoted if the quotes are
+/// unnecessary.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc5321#section-4.1.2
+fn parse_local_part(input: &str, lenient: bool) -> Result<String, EmailAddressError> {
+    use EmailAddressError::*;
+    if input.is_empty() {
+        return Err(EmptyLocalPart);
+    }
+    if let Some(quoted) = input.strip_prefix('"') {
+        let quoted = quoted.strip_suffix('"').ok_or(InvalidQuotedString)?;
+        let mut content = String::new();
+        let mut chars = quoted.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(c @ ' '..='~') => content.push(c),
+                    _ => return Err(InvalidQuotedString),
+                },
+                '"' => return Err(InvalidQuotedString),
+                ' '..='~' => content.push(c),
+                c if !c.is_ascii() && !c.is_control() => content.push(c),
+                c => return Err(InvalidLocalPartCharacter(c)),
+            }
         }
+        return match parse_local_part(&content, false) {
+            Ok(unquoted) => Ok(unquoted),
+            Err(_) => Ok(input.into()),
+        };
+    }
+    if let Some(c) = input.chars().find(|c| *c != '.' && !is_atext(*c)) {
+        return Err(InvalidLocalPartCharacter(c));
     }
+    if !lenient && input.split('.').any(str::is_empty) {
+        return Err(InvalidDot);
+    }
+    Ok(input.into())
 }
 
-impl Into<String> for EmailAddress {
-    fn into(self) -> String {
-        self.0
+/// Validates and normalizes a domain, or an address literal such as
+/// `[192.0.2.1]`.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc5321#section-4.1.2
+/// See: https://datatracker.ietf.org/doc/html/rfc5890#section-2.3.2
+fn parse_domain(input: &str, lenient: bool) -> Result<String, EmailAddressError> {
+    use EmailAddressError::*;
+    use unicode_normalization::UnicodeNormalization;
+    if input.is_empty() {
+        return Err(EmptyDomain);
     }
+    if let Some(literal) = input.strip_prefix('[') {
+        let invalid = || InvalidAddressLiteral(input.into());
+        let literal = literal.strip_suffix(']').ok_or_else(invalid)?;
+        let valid = match literal.get(..5) {
+            Some(tag) if tag.eq_ignore_ascii_case("IPv6:") => {
+                literal[5..].parse::<core::net::Ipv6Addr>().is_ok()
+            },
+            _ => literal.parse::<core::net::Ipv4Addr>().is_ok(),
+        };
+        return valid.then(|| input.into()).ok_or_else(invalid);
+    }
+
+    // Map the ideographic full stops to dots, as IDNA does:
+    let domain: String = input
+        .chars()
+        .map(|c| match c {
+            '\u{3002}' | '\u{FF0E}' | '\u{FF61}' => '.',
+            c => c,
+        })
+        .nfc()
+        .collect::<String>()
+        .to_lowercase();
+    let domain = match lenient {
+        true => domain.strip_suffix('.').unwrap_or(&domain),
+        false => &domain,
+    };
+
+    let mut labels = Vec::new();
+    for label in domain.split('.') {
+        let label = match label.strip_prefix("xn--") {
+            Some(encoded) => punycode::decode(encoded)
+                .filter(|decoded| !decoded.is_ascii())
+                .filter(|decoded| punycode::encode(decoded).as_deref() == Some(encoded))
+                .ok_or_else(|| InvalidPunycode(label.into()))?,
+            None => label.into(),
+        };
+        if let Some(c) = label.chars().find(|c| match c {
+            'a'..='z' | '0'..='9' | '-' => false,
+            '_' => !lenient,
+            c => c.is_ascii() || c.is_control() || c.is_whitespace(),
+        }) {
+            return Err(InvalidDomainCharacter(c));
+        }
+        if label.is_empty() || label.starts_with('-') || label.ends_with('-') {
+            return Err(InvalidLabel(label));
+        }
+        let ascii_len = match label.is_ascii() {
+            true => label.len(),
+            false => punycode::encode(&label).map_or(usize::MAX, |label| label.len() + 4),
+        };
+        if !lenient && ascii_len > 63 {
+            return Err(LabelTooLong(label));
+        }
+        labels.push(label);
+    }
+    Ok(labels.join("."))
 }
 
 #[cfg(feature = "imap-proto")]
@@ -74,3 +376,137 @@ include!("email_address/mailparse.rs");
 
 #[cfg(feature = "serde")]
 include!("email_address/serde.rs");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use EmailAddressError::*;
+
+    #[test]
+    fn test_parse_lenient() {
+        let address: EmailAddress = "Alice <Alice.Smith+News@Example.ORG>".parse().unwrap();
+        assert_eq!(address.as_str(), "Alice.Smith+News@example.org");
+        assert_eq!(address.local_part(), "Alice.Smith+News");
+        assert_eq!(address.domain(), "example.org");
+        assert_eq!(address.subaddress(), Some("News"));
+        assert_eq!(
+            address.without_subaddress().as_str(),
+            "Alice.Smith@example.org"
+        );
+        assert_eq!(
+            address.to_lowercase().as_str(),
+            "alice.smith+news@example.org"
+        );
+
+        assert_eq!(
+            "mailto:bob@example.org?subject=Hi".parse::<EmailAddress>(),
+            "bob@example.org".parse()
+        );
+        assert!("bob.@example.org.".parse::<EmailAddress>().is_ok());
+        assert_eq!("".parse::<EmailAddress>(), Err(Empty));
+        assert_eq!("Bob".parse::<EmailAddress>(), Err(MissingAt));
+        assert_eq!(
+            "bob smith@example.org".parse::<EmailAddress>(),
+            Err(InvalidLocalPartCharacter(' '))
+        );
+        assert_eq!(
+            "bob@exa mple.org".parse::<EmailAddress>(),
+            Err(InvalidDomainCharacter(' '))
+        );
+    }
+
+    #[test]
+    fn test_parse_strict() {
+        for valid in [
+            "simple@example.org",
+            "very.common+tag@sub.example.co.uk",
+            "\"john..doe\"@example.org",
+            "admin@mailserver1",
+            "user@[192.0.2.1]",
+            "user@[IPv6:2001:db8::1]",
+            "用户@例子.广告",
+            "δοκιμή@παράδειγμα.δοκιμή",
+        ] {
+            assert_eq!(
+                EmailAddress::parse_strict(valid)
+                    .map(String::from)
+                    .as_deref(),
+                Ok(valid)
+            );
+        }
+
+        // Unnecessary quotes are removed:
+        assert_eq!(
+            EmailAddress::parse_strict("\"alice\"@example.org")
+                .unwrap()
+                .as_str(),
+            "alice@example.org"
+        );
+
+        for (invalid, error) in [
+            ("Alice <alice@example.org>", InvalidLocalPartCharacter(' ')),
+            ("alice@example.org.", InvalidLabel("".into())),
+            ("alice..smith@example.org", InvalidDot),
+            (".alice@example.org", InvalidDot),
+            ("a\"b@example.org", InvalidLocalPartCharacter('"')),
+            ("\"alice@example.org", InvalidQuotedString),
+            ("alice@-example.org", InvalidLabel("-example".into())),
+            ("alice@exa_mple.org", InvalidDomainCharacter('_')),
+            (
+                "alice@[300.0.0.1]",
+                InvalidAddressLiteral("[300.0.0.1]".into()),
+            ),
+            ("alice@xn--ab-.org", InvalidPunycode("xn--ab-".into())),
+            (&format!("{}@example.org", "a".repeat(65)), LocalPartTooLong),
+            (
+                &format!("a@{}.org", "b".repeat(64)),
+                LabelTooLong("b".repeat(64)),
+            ),
+            (
+                &format!(
+                    "{}@{}.org",
+                    "a".repeat(64),
+                    vec!["b".repeat(60); 4].join(".")
+                ),
+                TooLong,
+            ),
+        ] {
+            assert_eq!(
+                EmailAddress::parse_strict(invalid),
+                Err(error),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_idn() {
+        let address = EmailAddress::parse_strict("info@xn--bcher-kva.example").unwrap();
+        assert_eq!(address.domain(), "bücher.example");
+        assert_eq!(address.ascii_domain(), "xn--bcher-kva.example");
+        assert_eq!(
+            address.to_ascii().as_deref(),
+            Some("info@xn--bcher-kva.example")
+        );
+        assert_eq!(
+            EmailAddress::parse_strict("用户@例子.广告")
+                .unwrap()
+                .to_ascii(),
+            None
+        );
+
+        // Domains are compared in NFC, regardless of case and encoding:
+        for variant in [
+            "info@BÜCHER.example",
+            "info@bu\u{308}cher.example",
+            "info@bücher\u{3002}example",
+        ] {
+            assert_eq!(variant.parse(), Ok(address.clone()), "{}", variant);
+        }
+        assert_eq!(
+            EmailAddress::parse_strict("info@XN--BCHER-KVA.example"),
+            Ok(address)
+        );
+    }
+}
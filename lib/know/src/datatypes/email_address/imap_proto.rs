@@ -1,16 +1,25 @@
 // This is free and unencumbered software released into the public domain.
 
 impl TryFrom<&imap_proto::Address<'_>> for EmailAddress {
-    type Error = ();
+    type Error = crate::Error;
 
     fn try_from(input: &imap_proto::Address) -> Result<Self, Self::Error> {
         match (&input.mailbox, &input.host) {
             (Some(mailbox), Some(host)) => {
-                let mailbox = String::from_utf8_lossy(&mailbox);
-                let host = String::from_utf8_lossy(&host);
-                Ok(Self(format!("{}@{}", mailbox, host).to_lowercase()))
+                let mailbox = String::from_utf8_lossy(mailbox);
+                let host = String::from_utf8_lossy(host);
+                let address = format!("{}@{}", mailbox, host);
+                address
+                    .parse()
+                    .map_err(|_| crate::Error::InvalidEmailAddress(address))
             },
-            _ => Err(()),
+            _ => Err(crate::Error::InvalidEmailAddress(
+                input
+                    .name
+                    .as_ref()
+                    .map(|name| String::from_utf8_lossy(name).into_owned())
+                    .unwrap_or_default(),
+            )),
         }
     }
 }
@@ -1,15 +1,14 @@
 // This is free and unencumbered software released into the public domain.
 
 impl TryFrom<&mail_parser::Addr<'_>> for EmailAddress {
-    type Error = ();
+    type Error = crate::Error;
 
     fn try_from(input: &mail_parser::Addr) -> Result<Self, Self::Error> {
-        Ok(Self(
-            input
-                .address
-                .as_ref()
-                .map(|s| s.to_lowercase())
-                .ok_or_else(|| ())?,
-        ))
+        let address = input.address.as_deref().ok_or_else(|| {
+            crate::Error::InvalidEmailAddress(input.name.as_deref().unwrap_or_default().to_string())
+        })?;
+        address
+            .parse()
+            .map_err(|_| crate::Error::InvalidEmailAddress(address.to_string()))
     }
 }
@@ -1,23 +1,28 @@
 // This is free and unencumbered software released into the public domain.
 
 impl TryFrom<&mailparse::MailAddr> for EmailAddress {
-    type Error = mailparse::MailParseError;
+    type Error = crate::Error;
 
     fn try_from(input: &mailparse::MailAddr) -> Result<Self, Self::Error> {
         match input {
-            mailparse::MailAddr::Single(single) => Ok(Self(single.addr.to_lowercase())),
-            mailparse::MailAddr::Group(_group) => todo!(),
+            mailparse::MailAddr::Single(single) => single
+                .addr
+                .parse()
+                .map_err(|_| crate::Error::InvalidEmailAddress(single.addr.clone())),
+            mailparse::MailAddr::Group(group) => {
+                Err(crate::Error::InvalidEmailAddress(group.group_name.clone()))
+            },
         }
     }
 }
 
 impl TryFrom<&mailparse::MailHeader<'_>> for EmailAddress {
-    type Error = mailparse::MailParseError;
+    type Error = crate::Error;
 
     fn try_from(input: &mailparse::MailHeader) -> Result<Self, Self::Error> {
-        use mailparse::MailParseError;
-        (input.get_value_utf8()?)
+        let value = input.get_value_utf8()?;
+        value
             .parse()
-            .map_err(|_| MailParseError::Generic("invalid email address in header"))
+            .map_err(|_| crate::Error::InvalidEmailAddress(value))
     }
 }
@@ -0,0 +1,234 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::formatters::DisplayInline;
+use alloc::{fmt, str::FromStr};
+
+/// The read state of an email message, as tracked by mail clients.
+///
+/// See: https://cr.yp.to/proto/maildir.html
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "camelCase"))]
+pub struct EmailMessageFlags {
+    /// The message has been resent, forwarded, or bounced (`P`).
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub passed: bool,
+
+    /// The message has been replied to (`R`).
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub replied: bool,
+
+    /// The message has been seen (`S`).
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub seen: bool,
+
+    /// The message has been marked for deletion (`T`).
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub trashed: bool,
+
+    /// The message is a draft (`D`).
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub draft: bool,
+
+    /// The message has been flagged for urgent or special attention (`F`).
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_false"))]
+    pub flagged: bool,
+
+    /// Other keywords, in ASCII order, e.g., JMAP's `$junk`, or the
+    /// lowercase keyword flags of Dovecot, which map to its
+    /// `dovecot-keywords` file. Only single-character keywords are Maildir
+    /// flags.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub keywords: Vec<String>,
+}
+
+impl EmailMessageFlags {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Returns the flag names in Maildir order.
+    pub fn names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        if self.draft {
+            names.push("Draft");
+        }
+        if self.flagged {
+            names.push("Flagged");
+        }
+        if self.passed {
+            names.push("Passed");
+        }
+        if self.replied {
+            names.push("Replied");
+        }
+        if self.seen {
+            names.push("Seen");
+        }
+        if self.trashed {
+            names.push("Trashed");
+        }
+        names
+    }
+
+    /// Parses IMAP flags, e.g., `\Seen`, ignoring unknown keywords.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc9051#section-2.3.2
+    pub fn from_imap<S: AsRef<str>>(flags: impl IntoIterator<Item = S>) -> Self {
+        let mut output = Self::default();
+        for flag in flags {
+            match flag.as_ref().to_ascii_lowercase().as_str() {
+                "\\answered" => output.replied = true,
+                "\\deleted" => output.trashed = true,
+                "\\draft" => output.draft = true,
+                "\\flagged" => output.flagged = true,
+                "\\seen" => output.seen = true,
+                "$forwarded" => output.passed = true,
+                _ => {},
+            }
+        }
+        output
+    }
+
+    /// Returns the flags as IMAP flags, e.g., `\Seen`.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc9051#section-2.3.2
+    pub fn imap_names(&self) -> Vec<&'static str> {
+        let mut names = Vec::new();
+        for (set, flag) in [
+            (self.replied, "\\Answered"),
+            (self.trashed, "\\Deleted"),
+            (self.draft, "\\Draft"),
+            (self.flagged, "\\Flagged"),
+            (self.seen, "\\Seen"),
+            (self.passed, "$Forwarded"),
+        ] {
+            if set {
+                names.push(flag);
+            }
+        }
+        names
+    }
+
+    pub fn inline(&self) -> DisplayInline<'_, EmailMessageFlags> {
+        DisplayInline(self)
+    }
+}
+
+/// Formats the flags as a Maildir info string, e.g., `FRS`.
+impl fmt::Display for EmailMessageFlags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut flags: Vec<char> = [
+            (self.draft, 'D'),
+            (self.flagged, 'F'),
+            (self.passed, 'P'),
+            (self.replied, 'R'),
+            (self.seen, 'S'),
+            (self.trashed, 'T'),
+        ]
+        .into_iter()
+        .filter_map(|(set, flag)| set.then_some(flag))
+        .chain(self.keywords.iter().filter_map(|keyword| {
+            let mut chars = keyword.chars();
+            chars.next().filter(|_| chars.next().is_none())
+        }))
+        .collect();
+        // Maildir requires the flags to be in ASCII order:
+        flags.sort_unstable();
+        flags.into_iter().try_for_each(|flag| write!(f, "{}", flag))
+    }
+}
+
+impl fmt::Display for DisplayInline<'_, EmailMessageFlags> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "🏷️ {}", self.0.names().join(", "))
+    }
+}
+
+/// Parses a Maildir info string, e.g., `2,FRS` or `FRS`.
+///
+/// Unknown flags, such as lowercase keyword flags, are kept as keywords.
+impl FromStr for EmailMessageFlags {
+    type Err = core::convert::Infallible;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let input = input.strip_prefix("2,").unwrap_or(input);
+        let mut flags = Self::default();
+        let mut keywords = Vec::new();
+        for flag in input.chars() {
+            match flag {
+                'D' => flags.draft = true,
+                'F' => flags.flagged = true,
+                'P' => flags.passed = true,
+                'R' => flags.replied = true,
+                'S' => flags.seen = true,
+                'T' => flags.trashed = true,
+                flag if flag.is_ascii_alphanumeric() => keywords.push(flag.to_string()),
+                _ => {},
+            }
+        }
+        keywords.sort_unstable();
+        keywords.dedup();
+        flags.keywords = keywords;
+        Ok(flags)
+    }
+}
+
+impl From<&str> for EmailMessageFlags {
+    fn from(input: &str) -> Self {
+        input.parse().unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "serde")]
+fn is_false(input: &bool) -> bool {
+    !*input
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maildir_roundtrip() {
+        let flags: EmailMessageFlags = "2,SRF".into();
+        assert!(flags.seen && flags.replied && flags.flagged);
+        assert!(!flags.draft && !flags.passed && !flags.trashed);
+        assert_eq!(flags.to_string(), "FRS");
+    }
+
+    #[test]
+    fn test_keyword_flags_are_kept() {
+        let flags: EmailMessageFlags = "Sba".into();
+        assert_eq!(
+            flags,
+            EmailMessageFlags {
+                seen: true,
+                keywords: vec!["a".into(), "b".into()],
+                ..Default::default()
+            }
+        );
+        assert_eq!(flags.to_string(), "Sab");
+
+        let flags = EmailMessageFlags {
+            keywords: vec!["$junk".into(), "c".into()],
+            ..flags
+        };
+        assert_eq!(flags.to_string(), "Sc");
+    }
+
+    #[test]
+    fn test_imap_roundtrip() {
+        let flags = EmailMessageFlags::from_imap(["\\Seen", "\\ANSWERED", "$Forwarded", "$Junk"]);
+        assert!(flags.seen && flags.replied && flags.passed);
+        assert!(!flags.draft && !flags.flagged && !flags.trashed);
+        assert_eq!(flags.imap_names(), ["\\Answered", "\\Seen", "$Forwarded"]);
+    }
+
+    #[test]
+    fn test_empty() {
+        assert!(EmailMessageFlags::default().is_empty());
+        assert!(EmailMessageFlags::from("").is_empty());
+        assert_eq!(EmailMessageFlags::default().to_string(), "");
+    }
+}
@@ -92,9 +92,9 @@ impl From<&Cow<'_, [u8]>> for EmailMessageId {
     }
 }
 
-impl Into<String> for EmailMessageId {
-    fn into(self) -> String {
-        self.0
+impl From<EmailMessageId> for String {
+    fn from(input: EmailMessageId) -> Self {
+        input.0
     }
 }
 
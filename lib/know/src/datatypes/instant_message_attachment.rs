@@ -0,0 +1,70 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{
+    classes::{AudioFrame, File, Image, ThingLike},
+    formatters::DisplayInline,
+    traits,
+};
+use alloc::fmt;
+
+/// Media sent along with, or instead of, the text of an instant message.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub enum InstantMessageAttachment {
+    /// A document or any other file, including media that wasn't decoded.
+    File(File),
+    /// A photo or other picture.
+    Image(Image),
+    /// A sticker, which is an image picked from a sticker pack.
+    Sticker(Image),
+    /// A voice note recorded in the chat.
+    VoiceNote(AudioFrame),
+}
+
+impl InstantMessageAttachment {
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            InstantMessageAttachment::File(file) => file.id(),
+            InstantMessageAttachment::Image(image) | InstantMessageAttachment::Sticker(image) => {
+                image.id()
+            },
+            InstantMessageAttachment::VoiceNote(audio) => audio.id(),
+        }
+    }
+
+    pub fn inline(&self) -> DisplayInline<'_, InstantMessageAttachment> {
+        DisplayInline(self)
+    }
+}
+
+impl fmt::Display for DisplayInline<'_, InstantMessageAttachment> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (emoji, label) = match self.0 {
+            InstantMessageAttachment::File(file) => ("📎", file.name.as_ref()),
+            InstantMessageAttachment::Image(image) => ("🖼️", image.source.as_ref()),
+            InstantMessageAttachment::Sticker(image) => ("🏷️", image.source.as_ref()),
+            InstantMessageAttachment::VoiceNote(_) => ("🎤", None),
+        };
+        match label.map(String::as_str).or(self.0.id()) {
+            Some(label) => write!(f, "{} {}", emoji, label),
+            None => write!(f, "{}", emoji),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl traits::ToJsonLd for InstantMessageAttachment {
+    fn to_jsonld(&self) -> serde_json::Result<serde_json::Value> {
+        match self {
+            InstantMessageAttachment::File(file) => file.to_jsonld(),
+            InstantMessageAttachment::Image(image) => image.to_jsonld(),
+            InstantMessageAttachment::Sticker(image) => {
+                let mut json = image.to_jsonld()?;
+                json["additionalType"] = "Sticker".into();
+                Ok(json)
+            },
+            InstantMessageAttachment::VoiceNote(audio) => audio.to_jsonld(),
+        }
+    }
+}
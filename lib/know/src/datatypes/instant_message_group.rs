@@ -0,0 +1,124 @@
+// This is free and unencumbered software released into the public domain.
+
+use super::{InstantMessageHandle, InstantMessagePlatform};
+use crate::{
+    classes::{Group, Person, PersonRef},
+    formatters::DisplayInline,
+    traits,
+};
+use alloc::{fmt, rc::Rc};
+
+/// A group chat or broadcast channel on an instant messaging platform.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "camelCase"))]
+pub struct InstantMessageGroup {
+    /// The platform's stable identifier for the chat, which survives
+    /// renames, e.g., `120363025246125486@g.us` or `C024BE91L`, or empty if
+    /// unknown.
+    pub id: String,
+
+    /// The platform the chat is on, which qualifies its identifier.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub platform: Option<InstantMessagePlatform>,
+
+    /// The current display name of the chat, if any.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub name: Option<String>,
+
+    /// The handles of the known members of the chat.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub members: Vec<InstantMessageHandle>,
+}
+
+impl InstantMessageGroup {
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// Returns the display name, falling back to the identifier.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.id)
+    }
+
+    /// Returns the chat's `im-group:` URI, qualified with its platform if
+    /// known, e.g., `im-group:slack:C024BE91L`.
+    pub fn to_uri(&self) -> String {
+        match self.platform {
+            Some(ref platform) => format!("im-group:{}:{}", platform, self.id),
+            None => format!("im-group:{}", self.id),
+        }
+    }
+
+    /// Returns the chat as a group, whose members are stubs identified by
+    /// their handles' URIs. The group is identified by the chat's URI,
+    /// unless the chat's identifier is unknown.
+    pub fn group(&self) -> Group {
+        Group {
+            id: (!self.id.is_empty()).then(|| self.to_uri()),
+            name: self.display_name().into(),
+            members: self
+                .members
+                .iter()
+                .map(|handle| {
+                    PersonRef(Rc::new(Person {
+                        id: Some(handle.to_uri()),
+                        phones: match handle {
+                            InstantMessageHandle::Phone(phone) => vec![phone.clone()],
+                            _ => Vec::new(),
+                        },
+                        accounts: match (handle, handle.platform()) {
+                            (InstantMessageHandle::Phone(_), _) => Vec::new(),
+                            (_, Some(platform)) => vec![format!("{}:{}", platform, handle)],
+                            (_, None) => vec![handle.as_str().into()],
+                        },
+                        ..Default::default()
+                    }))
+                })
+                .collect(),
+        }
+    }
+
+    pub fn inline(&self) -> DisplayInline<'_, InstantMessageGroup> {
+        DisplayInline(self)
+    }
+}
+
+impl fmt::Display for InstantMessageGroup {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl fmt::Display for DisplayInline<'_, InstantMessageGroup> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "👥 {}", self.0.display_name())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl traits::ToJsonLd for InstantMessageGroup {
+    fn to_jsonld(&self) -> serde_json::Result<serde_json::Value> {
+        use serde_json::json;
+        let mut members = Vec::new();
+        for member in &self.members {
+            members.push(member.to_jsonld()?);
+        }
+        let mut output = json!({
+            "@type": "Group",
+            "name": self.display_name(),
+            "member": members,
+        });
+        if !self.id.is_empty() {
+            output["@id"] = self.to_uri().into();
+        }
+        Ok(output)
+    }
+}
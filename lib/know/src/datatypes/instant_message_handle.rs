@@ -3,23 +3,112 @@
 use crate::{formatters::DisplayInline, traits};
 use alloc::{fmt, str::FromStr};
 
-use super::PhoneNumber;
+use super::{InstantMessagePlatform, PhoneNumber};
 
+/// The address of a user on an instant messaging platform.
+///
+/// Handles parse from and serialize to the platform's URI scheme, if it
+/// has one, e.g., `xmpp:alice@example.org`, and otherwise to an `im:` URI
+/// qualified by the platform, e.g., `im:slack:alice`.
 #[derive(Debug, Clone, Eq, Hash, PartialEq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InstantMessageHandle {
+    /// A phone number, on SMS or any platform that identifies users by it,
+    /// e.g., `sms:+15551234567`.
     Phone(PhoneNumber),
+
+    /// A username on an unknown platform, e.g., `im:alice`.
     Username(String),
+
+    /// A Discord username, e.g., `im:discord:alice`.
+    Discord(String),
+
+    /// A fully qualified Matrix user ID, e.g., `@alice:example.org`, whose
+    /// URI is `matrix:u/alice:example.org`.
+    ///
+    /// See: https://spec.matrix.org/latest/appendices/#user-identifiers
+    Matrix(String),
+
+    /// A Signal username, e.g., `im:signal:alice.01`.
+    Signal(String),
+
+    /// A Slack username, e.g., `im:slack:alice`, or a member ID, e.g.,
+    /// `im:slack:U024BE7LH`, which survives renames.
+    Slack(String),
+
+    /// A Telegram username without the `@`, e.g., `alice`, whose URI is
+    /// `tg://resolve?domain=alice`, or a numeric user ID, whose URI is
+    /// `tg://user?id=123456789`.
+    Telegram(String),
+
+    /// A bare Jabber ID, e.g., `alice@example.org`, whose URI is
+    /// `xmpp:alice@example.org`.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc5122
+    Xmpp(String),
 }
 
 impl InstantMessageHandle {
     pub fn as_str(&self) -> &str {
         match self {
             InstantMessageHandle::Phone(phone) => phone.as_str(),
-            InstantMessageHandle::Username(user) => user.as_str(),
+            InstantMessageHandle::Username(user)
+            | InstantMessageHandle::Discord(user)
+            | InstantMessageHandle::Matrix(user)
+            | InstantMessageHandle::Signal(user)
+            | InstantMessageHandle::Slack(user)
+            | InstantMessageHandle::Telegram(user)
+            | InstantMessageHandle::Xmpp(user) => user.as_str(),
         }
     }
 
+    /// Returns the platform of the handle, which phone numbers and
+    /// unqualified usernames lack.
+    pub fn platform(&self) -> Option<InstantMessagePlatform> {
+        match self {
+            InstantMessageHandle::Phone(_) | InstantMessageHandle::Username(_) => None,
+            InstantMessageHandle::Discord(_) => Some(InstantMessagePlatform::Discord),
+            InstantMessageHandle::Matrix(_) => Some(InstantMessagePlatform::Matrix),
+            InstantMessageHandle::Signal(_) => Some(InstantMessagePlatform::Signal),
+            InstantMessageHandle::Slack(_) => Some(InstantMessagePlatform::Slack),
+            InstantMessageHandle::Telegram(_) => Some(InstantMessagePlatform::Telegram),
+            InstantMessageHandle::Xmpp(_) => Some(InstantMessagePlatform::Xmpp),
+        }
+    }
+
+    /// Returns the handle as a URI, e.g., `tg://resolve?domain=alice`.
+    pub fn to_uri(&self) -> String {
+        match self {
+            InstantMessageHandle::Phone(phone) => format!("sms:{}", phone),
+            InstantMessageHandle::Username(user) => format!("im:{}", user),
+            InstantMessageHandle::Matrix(user) => {
+                format!("matrix:u/{}", user.strip_prefix('@').unwrap_or(user))
+            },
+            InstantMessageHandle::Telegram(user) if user.bytes().all(|b| b.is_ascii_digit()) => {
+                format!("tg://user?id={}", user)
+            },
+            InstantMessageHandle::Telegram(user) => format!("tg://resolve?domain={}", user),
+            InstantMessageHandle::Xmpp(jid) => format!("xmpp:{}", jid),
+            InstantMessageHandle::Discord(user) => format!("im:discord:{}", user),
+            InstantMessageHandle::Signal(user) => format!("im:signal:{}", user),
+            InstantMessageHandle::Slack(user) => format!("im:slack:{}", user),
+        }
+    }
+
+    /// Returns whether the input is a Matrix user ID, i.e., `@` followed by
+    /// a localpart and a server name separated by a colon.
+    pub fn is_matrix_id(input: &str) -> bool {
+        let Some((localpart, server_name)) = input
+            .strip_prefix('@')
+            .and_then(|input| input.split_once(':'))
+        else {
+            return false;
+        };
+        !localpart.is_empty()
+            && !server_name.is_empty()
+            && !input.chars().any(|c| c.is_whitespace() || c.is_control())
+    }
+
     pub fn inline(&self) -> DisplayInline<'_, InstantMessageHandle> {
         DisplayInline(self)
     }
@@ -35,7 +124,10 @@ impl fmt::Display for DisplayInline<'_, InstantMessageHandle> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.0 {
             InstantMessageHandle::Phone(phone) => write!(f, "{}", phone.inline()),
-            InstantMessageHandle::Username(user) => write!(f, "📇 {user}"),
+            InstantMessageHandle::Telegram(user) if !user.bytes().all(|b| b.is_ascii_digit()) => {
+                write!(f, "📇 @{user}")
+            },
+            handle => write!(f, "📇 {handle}"),
         }
     }
 }
@@ -43,25 +135,186 @@ impl fmt::Display for DisplayInline<'_, InstantMessageHandle> {
 #[cfg(feature = "serde")]
 impl traits::ToJsonLd for InstantMessageHandle {
     fn to_jsonld(&self) -> serde_json::Result<serde_json::Value> {
-        Ok(format!("im:{self}").into())
+        Ok(self.to_uri().into())
     }
 }
 
 impl FromStr for InstantMessageHandle {
     type Err = ();
 
+    /// Parses a handle's URI, a phone number's `tel:` URI, a Matrix user
+    /// ID, or else an unqualified username.
     fn from_str(input: &str) -> Result<Self, Self::Err> {
+        fn nonempty(input: &str) -> Result<&str, ()> {
+            Some(input).filter(|input| !input.is_empty()).ok_or(())
+        }
+
+        // The query of `sms:`, `xmpp:` and `matrix:` URIs, e.g., `?body=`,
+        // is an action rather than part of the handle:
+        fn without_query(input: &str) -> &str {
+            input.split('?').next().unwrap_or_default()
+        }
+
         let trimmed = input.trim();
 
-        if let Some(tel) = trimmed.strip_prefix("tel:") {
-            return Ok(InstantMessageHandle::Phone(tel.into()));
+        if let Some(phone) = trimmed
+            .strip_prefix("sms:")
+            .or_else(|| trimmed.strip_prefix("tel:"))
+        {
+            let phone = nonempty(without_query(phone))?;
+            return Ok(InstantMessageHandle::Phone(phone.into()));
+        }
+
+        if let Some(jid) = trimmed.strip_prefix("xmpp:") {
+            let jid = nonempty(without_query(jid.trim_start_matches('/')))?;
+            return Ok(InstantMessageHandle::Xmpp(jid.into()));
+        }
+
+        if let Some(user) = trimmed.strip_prefix("matrix:u/") {
+            let user = format!("@{}", without_query(user));
+            return match Self::is_matrix_id(&user) {
+                true => Ok(InstantMessageHandle::Matrix(user)),
+                false => Err(()),
+            };
+        }
+
+        if let Some(query) = trimmed.strip_prefix("tg://") {
+            let user = query
+                .strip_prefix("resolve?domain=")
+                .or_else(|| query.strip_prefix("user?id="))
+                .ok_or(())?;
+            let user = nonempty(user.split('&').next().unwrap_or_default())?;
+            return Ok(InstantMessageHandle::Telegram(user.into()));
+        }
+
+        if let Some(user) = trimmed.strip_prefix("im:") {
+            let qualified =
+                user.split_once(':')
+                    .and_then(|(platform, user)| match platform.parse().ok()? {
+                        InstantMessagePlatform::Discord => {
+                            Some(InstantMessageHandle::Discord(user.into()))
+                        },
+                        InstantMessagePlatform::Signal => {
+                            Some(InstantMessageHandle::Signal(user.into()))
+                        },
+                        InstantMessagePlatform::Slack => {
+                            Some(InstantMessageHandle::Slack(user.into()))
+                        },
+                        _ => None,
+                    });
+            if let Some(handle) = qualified {
+                return match handle.as_str().is_empty() {
+                    true => Err(()),
+                    false => Ok(handle),
+                };
+            }
+            return Ok(InstantMessageHandle::Username(nonempty(user)?.into()));
+        }
+
+        if Self::is_matrix_id(trimmed) {
+            return Ok(InstantMessageHandle::Matrix(trimmed.to_string()));
         }
 
         // Otherwise treat the whole input as username:
-        if !trimmed.is_empty() {
-            Ok(InstantMessageHandle::Username(trimmed.to_string()))
-        } else {
-            Err(())
+        Ok(InstantMessageHandle::Username(nonempty(trimmed)?.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "tel:+15551234567".parse(),
+            Ok(InstantMessageHandle::Phone("+15551234567".into()))
+        );
+        assert_eq!(
+            " @alice:example.org ".parse(),
+            Ok(InstantMessageHandle::Matrix("@alice:example.org".into()))
+        );
+        assert_eq!(
+            "@alice".parse(),
+            Ok(InstantMessageHandle::Username("@alice".into()))
+        );
+        assert_eq!("".parse::<InstantMessageHandle>(), Err(()));
+        assert_eq!("tg://".parse::<InstantMessageHandle>(), Err(()));
+        assert_eq!("im:".parse::<InstantMessageHandle>(), Err(()));
+    }
+
+    #[test]
+    fn test_uri() {
+        for (uri, handle) in [
+            (
+                "sms:+15551234567",
+                InstantMessageHandle::Phone("+15551234567".into()),
+            ),
+            ("im:alice", InstantMessageHandle::Username("alice".into())),
+            (
+                "im:discord:alice",
+                InstantMessageHandle::Discord("alice".into()),
+            ),
+            (
+                "matrix:u/alice:example.org",
+                InstantMessageHandle::Matrix("@alice:example.org".into()),
+            ),
+            (
+                "im:signal:alice.01",
+                InstantMessageHandle::Signal("alice.01".into()),
+            ),
+            (
+                "im:slack:alice",
+                InstantMessageHandle::Slack("alice".into()),
+            ),
+            (
+                "tg://resolve?domain=alice",
+                InstantMessageHandle::Telegram("alice".into()),
+            ),
+            (
+                "tg://user?id=123456789",
+                InstantMessageHandle::Telegram("123456789".into()),
+            ),
+            (
+                "xmpp:alice@example.org",
+                InstantMessageHandle::Xmpp("alice@example.org".into()),
+            ),
+        ] {
+            assert_eq!(handle.to_uri(), uri);
+            assert_eq!(uri.parse(), Ok(handle));
         }
+
+        assert_eq!(
+            "sms:+15551234567?body=hello".parse(),
+            Ok(InstantMessageHandle::Phone("+15551234567".into()))
+        );
+        assert_eq!(
+            "xmpp:alice@example.org?message".parse(),
+            Ok(InstantMessageHandle::Xmpp("alice@example.org".into()))
+        );
+        assert_eq!(
+            "matrix:u/alice:example.org?action=chat".parse(),
+            Ok(InstantMessageHandle::Matrix("@alice:example.org".into()))
+        );
+        assert_eq!(
+            "im:myspace:tom".parse(),
+            Ok(InstantMessageHandle::Username("myspace:tom".into()))
+        );
+        assert_eq!(
+            InstantMessageHandle::Slack("alice".into()).platform(),
+            Some(InstantMessagePlatform::Slack)
+        );
+        assert_eq!(
+            InstantMessageHandle::Telegram("alice".into())
+                .inline()
+                .to_string(),
+            "📇 @alice"
+        );
+        assert_eq!(
+            InstantMessageHandle::Telegram("123456789".into())
+                .inline()
+                .to_string(),
+            "📇 123456789"
+        );
     }
 }
@@ -80,9 +80,9 @@ impl From<&Cow<'_, [u8]>> for InstantMessageId {
     }
 }
 
-impl Into<String> for InstantMessageId {
-    fn into(self) -> String {
-        self.0
+impl From<InstantMessageId> for String {
+    fn from(input: InstantMessageId) -> Self {
+        input.0
     }
 }
 
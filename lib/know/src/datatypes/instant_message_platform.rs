@@ -0,0 +1,116 @@
+// This is free and unencumbered software released into the public domain.
+
+use alloc::{fmt, str::FromStr};
+
+/// An instant messaging platform, or SMS/MMS text messaging.
+#[derive(Debug, Clone, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub enum InstantMessagePlatform {
+    Discord,
+    Irc,
+    Matrix,
+    Signal,
+    Slack,
+    Sms,
+    Telegram,
+    WhatsApp,
+    Xmpp,
+    Other(String),
+}
+
+impl InstantMessagePlatform {
+    pub fn as_str(&self) -> &str {
+        match self {
+            InstantMessagePlatform::Discord => "discord",
+            InstantMessagePlatform::Irc => "irc",
+            InstantMessagePlatform::Matrix => "matrix",
+            InstantMessagePlatform::Signal => "signal",
+            InstantMessagePlatform::Slack => "slack",
+            InstantMessagePlatform::Sms => "sms",
+            InstantMessagePlatform::Telegram => "telegram",
+            InstantMessagePlatform::WhatsApp => "whatsapp",
+            InstantMessagePlatform::Xmpp => "xmpp",
+            InstantMessagePlatform::Other(platform) => platform.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for InstantMessagePlatform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for InstantMessagePlatform {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.trim().to_lowercase().as_str() {
+            "" => Err(()),
+            "discord" => Ok(InstantMessagePlatform::Discord),
+            "irc" => Ok(InstantMessagePlatform::Irc),
+            "matrix" => Ok(InstantMessagePlatform::Matrix),
+            "signal" => Ok(InstantMessagePlatform::Signal),
+            "slack" => Ok(InstantMessagePlatform::Slack),
+            "sms" | "mms" => Ok(InstantMessagePlatform::Sms),
+            "telegram" | "tg" => Ok(InstantMessagePlatform::Telegram),
+            "whatsapp" => Ok(InstantMessagePlatform::WhatsApp),
+            "xmpp" | "jabber" => Ok(InstantMessagePlatform::Xmpp),
+            _ => Ok(InstantMessagePlatform::Other(input.trim().to_string())),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for InstantMessagePlatform {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for InstantMessagePlatform {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let input = String::deserialize(deserializer)?;
+        input
+            .parse()
+            .map_err(|_| serde::de::Error::custom("empty platform"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("Telegram".parse(), Ok(InstantMessagePlatform::Telegram));
+        assert_eq!("jabber".parse(), Ok(InstantMessagePlatform::Xmpp));
+        assert_eq!(
+            "Mastodon".parse(),
+            Ok(InstantMessagePlatform::Other("Mastodon".into()))
+        );
+        assert_eq!("".parse::<InstantMessagePlatform>(), Err(()));
+        assert_eq!(InstantMessagePlatform::WhatsApp.to_string(), "whatsapp");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let platform = InstantMessagePlatform::Other("mastodon".into());
+        assert_eq!(serde_json::to_string(&platform).unwrap(), r#""mastodon""#);
+        assert_eq!(
+            serde_json::from_str::<InstantMessagePlatform>(r#""mastodon""#).unwrap(),
+            platform
+        );
+        assert_eq!(
+            serde_json::from_str::<InstantMessagePlatform>(r#""whatsapp""#).unwrap(),
+            InstantMessagePlatform::WhatsApp
+        );
+    }
+}
@@ -0,0 +1,56 @@
+// This is free and unencumbered software released into the public domain.
+
+use super::{DateTime, InstantMessageHandle};
+use crate::{formatters::DisplayInline, traits};
+use alloc::fmt;
+
+/// An emoji reaction to an instant message.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct InstantMessageReaction {
+    /// The reaction, e.g., `👍`, or a custom emoji's name, e.g., `:shipit:`.
+    pub emoji: String,
+
+    pub from: InstantMessageHandle,
+
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub date: Option<DateTime>,
+}
+
+impl InstantMessageReaction {
+    pub fn new(emoji: impl Into<String>, from: InstantMessageHandle) -> Self {
+        Self {
+            emoji: emoji.into(),
+            from,
+            date: None,
+        }
+    }
+
+    pub fn inline(&self) -> DisplayInline<'_, InstantMessageReaction> {
+        DisplayInline(self)
+    }
+}
+
+impl fmt::Display for DisplayInline<'_, InstantMessageReaction> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.0.emoji, self.0.from)
+    }
+}
+
+/// See: https://schema.org/ReactAction
+#[cfg(feature = "serde")]
+impl traits::ToJsonLd for InstantMessageReaction {
+    fn to_jsonld(&self) -> serde_json::Result<serde_json::Value> {
+        use serde_json::json;
+        Ok(json!({
+            "@type": "ReactAction",
+            "agent": self.from.to_jsonld()?,
+            "name": self.emoji,
+            "startTime": self.date.as_ref().map(|date| date.to_string()),
+        }))
+    }
+}
@@ -1,7 +1,7 @@
 // This is free and unencumbered software released into the public domain.
 
-use super::InstantMessageHandle;
-use crate::formatters::DisplayInline;
+use super::{InstantMessageGroup, InstantMessageHandle};
+use crate::{classes::Group, formatters::DisplayInline, traits};
 use alloc::fmt;
 
 #[derive(Debug, Clone, Eq, Hash, PartialEq, PartialOrd, Ord)]
@@ -16,17 +16,35 @@ pub enum InstantMessageRecipientType {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum InstantMessageRecipient {
     Direct(InstantMessageHandle),
-    // Group( some kind of group id ),
-    // Channel( channel id ),
+    Group(InstantMessageGroup),
+    Channel(InstantMessageGroup),
 }
 
 impl InstantMessageRecipient {
     pub fn as_str(&self) -> &str {
         match self {
             InstantMessageRecipient::Direct(handle) => handle.as_str(),
+            InstantMessageRecipient::Group(group) | InstantMessageRecipient::Channel(group) => {
+                group.as_str()
+            },
         }
     }
 
+    /// Returns the group chat or channel, if the message wasn't direct.
+    pub fn as_group(&self) -> Option<&InstantMessageGroup> {
+        match self {
+            InstantMessageRecipient::Direct(_) => None,
+            InstantMessageRecipient::Group(group) | InstantMessageRecipient::Channel(group) => {
+                Some(group)
+            },
+        }
+    }
+
+    /// Returns the group chat or channel as a group.
+    pub fn group(&self) -> Option<Group> {
+        self.as_group().map(InstantMessageGroup::group)
+    }
+
     pub fn inline(&self) -> DisplayInline<'_, InstantMessageRecipient> {
         DisplayInline(self)
     }
@@ -34,6 +52,8 @@ impl InstantMessageRecipient {
     pub fn r#type(&self) -> InstantMessageRecipientType {
         match self {
             InstantMessageRecipient::Direct(_) => InstantMessageRecipientType::Direct,
+            InstantMessageRecipient::Group(_) => InstantMessageRecipientType::Group,
+            InstantMessageRecipient::Channel(_) => InstantMessageRecipientType::Channel,
         }
     }
 }
@@ -48,6 +68,83 @@ impl fmt::Display for DisplayInline<'_, InstantMessageRecipient> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.0 {
             InstantMessageRecipient::Direct(direct) => write!(f, "{}", direct.inline()),
+            InstantMessageRecipient::Group(group) => write!(f, "{}", group.inline()),
+            InstantMessageRecipient::Channel(channel) => {
+                write!(f, "📢 {}", channel.display_name())
+            },
         }
     }
 }
+
+#[cfg(feature = "serde")]
+impl traits::ToJsonLd for InstantMessageRecipient {
+    fn to_jsonld(&self) -> serde_json::Result<serde_json::Value> {
+        match self {
+            InstantMessageRecipient::Direct(handle) => handle.to_jsonld(),
+            InstantMessageRecipient::Group(group) | InstantMessageRecipient::Channel(group) => {
+                group.to_jsonld()
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{classes::ThingLike, datatypes::InstantMessagePlatform};
+
+    #[test]
+    fn test_group() {
+        let group = InstantMessageGroup {
+            id: "120363025246125486@g.us".into(),
+            platform: Some(InstantMessagePlatform::WhatsApp),
+            name: Some("Family".into()),
+            members: vec![
+                "tel:+15551234567".parse().unwrap(),
+                "alice".parse().unwrap(),
+            ],
+        };
+        let to = InstantMessageRecipient::Group(group.clone());
+        assert_eq!(to.r#type(), InstantMessageRecipientType::Group);
+        assert_eq!(to.as_str(), "120363025246125486@g.us");
+        assert_eq!(to.inline().to_string(), "👥 Family");
+
+        let group = to.group().unwrap();
+        assert_eq!(
+            group.id(),
+            Some("im-group:whatsapp:120363025246125486@g.us")
+        );
+        assert_eq!(group.name, "Family");
+        assert_eq!(group.members.len(), 2);
+        assert_eq!(group.members[0].id(), Some("sms:+15551234567"));
+        assert_eq!(group.members[1].0.accounts, ["alice"]);
+
+        let to = InstantMessageRecipient::Channel(InstantMessageGroup::new("C024BE91L"));
+        assert_eq!(to.inline().to_string(), "📢 C024BE91L");
+        assert_eq!(
+            InstantMessageRecipient::Direct("bob".parse().unwrap()).group(),
+            None
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_to_jsonld() {
+        use crate::traits::ToJsonLd;
+        let to = InstantMessageRecipient::Group(InstantMessageGroup {
+            id: "family".into(),
+            platform: None,
+            name: None,
+            members: vec!["alice".parse().unwrap()],
+        });
+        assert_eq!(
+            to.to_jsonld().unwrap(),
+            serde_json::json!({
+                "@type": "Group",
+                "@id": "im-group:family",
+                "name": "family",
+                "member": ["im:alice"],
+            })
+        );
+    }
+}
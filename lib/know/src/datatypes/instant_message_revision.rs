@@ -0,0 +1,26 @@
+// This is free and unencumbered software released into the public domain.
+
+use super::DateTime;
+use crate::traits;
+
+/// An earlier version of the text of an edited instant message.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "camelCase"))]
+pub struct InstantMessageRevision {
+    /// When this version was written.
+    pub date: DateTime,
+
+    pub content: String,
+}
+
+#[cfg(feature = "serde")]
+impl traits::ToJsonLd for InstantMessageRevision {
+    fn to_jsonld(&self) -> serde_json::Result<serde_json::Value> {
+        use serde_json::json;
+        Ok(json!({
+            "dateModified": self.date.to_string(),
+            "text": self.content,
+        }))
+    }
+}
@@ -0,0 +1,162 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::formatters::DisplayInline;
+use alloc::fmt;
+
+/// The mailing list that distributed a message, from its `List-*` and
+/// `Precedence` headers.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc2919
+/// See: https://datatracker.ietf.org/doc/html/rfc2369
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, rename_all = "camelCase"))]
+pub struct MailingList {
+    /// The `List-Id` identifier, e.g., `users.lists.example.org`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub id: Option<String>,
+
+    /// The `List-Id` description, e.g., `Example Users`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub name: Option<String>,
+
+    /// The `List-Post` URLs, e.g., `mailto:users@lists.example.org`, which
+    /// are absent for announcement lists that don't allow posting.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub post: Vec<String>,
+
+    /// The `List-Unsubscribe` URLs.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub unsubscribe: Vec<String>,
+
+    /// The `List-Archive` URLs.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub archive: Vec<String>,
+
+    /// The `Precedence`, e.g., `list` or `bulk`.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub precedence: Option<String>,
+}
+
+impl MailingList {
+    /// Parses the values of the mailing list headers of a message, returning
+    /// `None` if none of them are present.
+    pub fn from_headers(
+        list_id: Option<&str>,
+        post: Option<&str>,
+        unsubscribe: Option<&str>,
+        archive: Option<&str>,
+        precedence: Option<&str>,
+    ) -> Option<Self> {
+        let (name, id) = list_id.map(parse_list_id).unwrap_or_default();
+        let output = Self {
+            id,
+            name,
+            post: post.map(parse_urls).unwrap_or_default(),
+            unsubscribe: unsubscribe.map(parse_urls).unwrap_or_default(),
+            archive: archive.map(parse_urls).unwrap_or_default(),
+            precedence: precedence
+                .map(|precedence| precedence.trim().to_ascii_lowercase())
+                .filter(|precedence| !precedence.is_empty()),
+        };
+        (output != Self::default()).then_some(output)
+    }
+
+    /// Whether the message is bulk mail, e.g., a newsletter, rather than
+    /// personal mail.
+    pub fn is_bulk(&self) -> bool {
+        self.id.is_some() || matches!(self.precedence.as_deref(), Some("bulk" | "list" | "junk"))
+    }
+
+    pub fn inline(&self) -> DisplayInline<'_, MailingList> {
+        DisplayInline(self)
+    }
+}
+
+impl fmt::Display for DisplayInline<'_, MailingList> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.0.name, &self.0.id) {
+            (Some(name), Some(id)) => write!(f, "📣 {} <{}>", name, id),
+            (Some(name), None) => write!(f, "📣 {}", name),
+            (None, Some(id)) => write!(f, "📣 {}", id),
+            (None, None) => write!(f, "📣 ({})", self.0.precedence.as_deref().unwrap_or("list")),
+        }
+    }
+}
+
+/// Parses a `List-Id` of the form `Description <list-id>`, where the
+/// description is optional and may be quoted.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc2919#section-3
+fn parse_list_id(input: &str) -> (Option<String>, Option<String>) {
+    let input = input.trim();
+    let (name, id) = match (input.rfind('<'), input.rfind('>')) {
+        (Some(start), Some(end)) if start < end => (&input[..start], &input[start + 1..end]),
+        _ => ("", input),
+    };
+    let name = name.trim().trim_matches('"').trim();
+    let id = id.trim();
+    (
+        (!name.is_empty()).then(|| name.into()),
+        (!id.is_empty()).then(|| id.to_ascii_lowercase()),
+    )
+}
+
+/// Parses a comma-separated list of `<url>`s, ignoring comments and the
+/// `NO` of a `List-Post` that doesn't allow posting.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc2369#section-2
+fn parse_urls(input: &str) -> Vec<String> {
+    let mut output = Vec::new();
+    let mut rest = input;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let url: String = rest[start + 1..start + end]
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        if !url.is_empty() {
+            output.push(url);
+        }
+        rest = &rest[start + end + 1..];
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_headers() {
+        let list = MailingList::from_headers(
+            Some("\"Example Users\" <Users.Lists.Example.org>"),
+            Some("NO (posting not allowed)"),
+            Some("<mailto:leave@lists.example.org?subject=unsubscribe>,\r\n <https://lists.example.org/unsubscribe>"),
+            Some("<https://lists.example.org/archive/\r\n users>"),
+            Some(" Bulk"),
+        )
+        .unwrap();
+        assert_eq!(list.id.as_deref(), Some("users.lists.example.org"));
+        assert_eq!(list.name.as_deref(), Some("Example Users"));
+        assert!(list.post.is_empty());
+        assert_eq!(list.unsubscribe.len(), 2);
+        assert_eq!(list.archive, ["https://lists.example.org/archive/users"]);
+        assert_eq!(list.precedence.as_deref(), Some("bulk"));
+        assert!(list.is_bulk());
+
+        assert_eq!(
+            MailingList::from_headers(Some("<announce.example.org>"), None, None, None, None)
+                .unwrap()
+                .inline()
+                .to_string(),
+            "📣 announce.example.org"
+        );
+        assert_eq!(
+            MailingList::from_headers(None, None, None, None, None),
+            None
+        );
+    }
+}
@@ -72,9 +72,9 @@ impl From<&Cow<'_, [u8]>> for PersonName {
     }
 }
 
-impl Into<String> for PersonName {
-    fn into(self) -> String {
-        self.0
+impl From<PersonName> for String {
+    fn from(input: PersonName) -> Self {
+        input.0
     }
 }
 
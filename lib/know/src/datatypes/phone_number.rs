@@ -72,9 +72,9 @@ impl From<&Cow<'_, [u8]>> for PhoneNumber {
     }
 }
 
-impl Into<String> for PhoneNumber {
-    fn into(self) -> String {
-        self.0
+impl From<PhoneNumber> for String {
+    fn from(input: PhoneNumber) -> Self {
+        input.0
     }
 }
 
@@ -1,3 +1,64 @@
 // This is free and unencumbered software released into the public domain.
 
-//use thiserror::Error;
+use alloc::string::String;
+use thiserror::Error;
+
+/// An error converting to or from one of the supported integrations.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// A required message header is absent, e.g., `Date`.
+    #[error("missing {0} header")]
+    MissingHeader(&'static str),
+
+    /// A message header is present but can't be parsed.
+    #[error("invalid {0} header: {1}")]
+    InvalidHeader(&'static str, String),
+
+    /// A required object property is absent, e.g., a JMAP `sentAt`.
+    #[error("missing {0} property")]
+    MissingProperty(&'static str),
+
+    /// An object property is present but can't be parsed.
+    #[error("invalid {0} property: {1}")]
+    InvalidProperty(&'static str, String),
+
+    #[error("invalid email address: {0}")]
+    InvalidEmailAddress(String),
+
+    /// A server sent a response that doesn't fit the protocol state.
+    #[error("unexpected response: {0}")]
+    UnexpectedResponse(String),
+
+    #[cfg(feature = "std")]
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    #[cfg(feature = "mailparse")]
+    #[error(transparent)]
+    MailParse(#[from] mailparse::MailParseError),
+
+    #[cfg(feature = "maildir")]
+    #[error(transparent)]
+    Maildir(#[from] maildir::MaildirError),
+
+    #[cfg(feature = "sms-backup")]
+    #[error(transparent)]
+    Xml(#[from] roxmltree::Error),
+
+    #[cfg(feature = "whatsapp")]
+    #[error(transparent)]
+    Zip(#[from] zip::result::ZipError),
+}
+
+#[cfg(feature = "maildir")]
+impl From<maildir::MailEntryError> for Error {
+    fn from(input: maildir::MailEntryError) -> Self {
+        use maildir::MailEntryError::*;
+        match input {
+            IOError(error) => Self::Io(error),
+            ParseError(error) => Self::MailParse(error),
+            DateError(error) => Self::InvalidHeader("Date", error.into()),
+        }
+    }
+}
@@ -5,6 +5,8 @@ use std::{
     io::{self, Write},
 };
 
+pub(crate) mod mime;
+
 #[derive(Clone, Debug, Default)]
 pub struct DisplayConfig {
     pub color: bool,
@@ -47,7 +49,7 @@ impl<'a, 'b> Write for WriteToFormatter<'a, 'b> {
 
         self.0
             .write_str(s)
-            .map_err(|_| io::Error::new(io::ErrorKind::Other, "format error"))?;
+            .map_err(|_| io::Error::other("format error"))?;
 
         Ok(buf.len())
     }
@@ -0,0 +1,290 @@
+// This is free and unencumbered software released into the public domain.
+
+//! Helpers for generating RFC 5322 and MIME messages.
+//!
+//! See: https://datatracker.ietf.org/doc/html/rfc5322
+//! See: https://datatracker.ietf.org/doc/html/rfc2045
+//! See: https://datatracker.ietf.org/doc/html/rfc2047
+
+use alloc::fmt::{self, Write};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+
+/// The recommended maximum line length, excluding the CRLF.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc5322#section-2.1.1
+pub(crate) const MAX_LINE_LENGTH: usize = 78;
+
+/// The maximum length of a base64 or quoted-printable encoded line.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc2045#section-6.7
+const MAX_ENCODED_LINE_LENGTH: usize = 76;
+
+/// The maximum number of UTF-8 bytes in a single encoded word, chosen so
+/// that an encoded word fits on a line together with the header name.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc2047#section-2
+const MAX_ENCODED_WORD_BYTES: usize = 36;
+
+pub(crate) const CRLF: &str = "\r\n";
+
+/// Writes a header field, folding it at the whitespace between `words` to
+/// keep lines within 78 characters where possible.
+pub(crate) fn write_header<W, S>(w: &mut W, name: &str, words: &[S]) -> fmt::Result
+where
+    W: Write + ?Sized,
+    S: AsRef<str>,
+{
+    write!(w, "{}:", name)?;
+    let mut line_length = name.len() + 1;
+    for word in words {
+        let word = word.as_ref();
+        if line_length + 1 + word.len() > MAX_LINE_LENGTH && line_length > name.len() + 1 {
+            w.write_str(CRLF)?;
+            line_length = 0;
+        }
+        write!(w, " {}", word)?;
+        line_length += 1 + word.len();
+    }
+    w.write_str(CRLF)
+}
+
+/// Splits unstructured header text into words, using RFC 2047 encoded words
+/// whenever the text can't be represented verbatim.
+pub(crate) fn encode_unstructured(text: &str) -> Vec<String> {
+    let is_verbatim = text.chars().all(|c| c == ' ' || c.is_ascii_graphic())
+        && !text.contains("=?")
+        && !text.starts_with(' ')
+        && !text.ends_with(' ')
+        && !text.contains("  ")
+        && text
+            .split(' ')
+            .all(|word| word.len() < MAX_LINE_LENGTH - 10);
+    if is_verbatim {
+        return text.split(' ').map(ToString::to_string).collect();
+    }
+    encode_words(text)
+}
+
+/// Splits a display name into words, using a quoted string if the name
+/// isn't a plain sequence of atoms, and RFC 2047 encoded words if it isn't
+/// printable ASCII.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc5322#section-3.2.5
+pub(crate) fn encode_phrase(text: &str) -> Vec<String> {
+    let is_atext = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c);
+    if text
+        .split(' ')
+        .all(|word| !word.is_empty() && word.chars().all(is_atext))
+        && !text.contains("=?")
+    {
+        return text.split(' ').map(ToString::to_string).collect();
+    }
+    if text.chars().all(|c| c == ' ' || c.is_ascii_graphic()) && !text.contains("=?") {
+        let quoted = text.replace('\\', "\\\\").replace('"', "\\\"");
+        return vec![format!("\"{}\"", quoted)];
+    }
+    encode_words(text)
+}
+
+/// Encodes text as a sequence of RFC 2047 "B" encoded words, never splitting
+/// a character across words.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc2047#section-4.1
+pub(crate) fn encode_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let mut end = start;
+        for (offset, c) in text[start..].char_indices() {
+            if offset + c.len_utf8() > MAX_ENCODED_WORD_BYTES {
+                break;
+            }
+            end = start + offset + c.len_utf8();
+        }
+        words.push(format!(
+            "=?utf-8?B?{}?=",
+            STANDARD.encode(&text.as_bytes()[start..end])
+        ));
+        start = end;
+    }
+    words
+}
+
+/// Formats a MIME parameter, using RFC 2231 extended notation for values
+/// that can't be represented as a quoted string.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc2231#section-4
+pub(crate) fn encode_parameter(name: &str, value: &str) -> String {
+    let is_quotable = value
+        .chars()
+        .all(|c| (c == ' ' || c.is_ascii_graphic()) && c != '"' && c != '\\');
+    if is_quotable {
+        return format!("{}=\"{}\"", name, value);
+    }
+    let mut output = format!("{}*=utf-8''", name);
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                output.push(byte as char)
+            },
+            _ => write!(output, "%{:02X}", byte).unwrap(), // infallible
+        }
+    }
+    output
+}
+
+/// Wraps a message ID, list ID or URL in angle brackets, dropping the
+/// control characters, whitespace and angle brackets in it, which could
+/// otherwise end the header early and inject another one.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc5322#section-3.6.4
+pub(crate) fn encode_angle_addr(text: &str) -> String {
+    let text = text
+        .chars()
+        .filter(|c| !c.is_control() && !c.is_whitespace() && !matches!(c, '<' | '>'));
+    core::iter::once('<').chain(text).chain(['>']).collect()
+}
+
+/// Returns whether the text can be sent as-is with `7bit` encoding.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc2045#section-2.7
+pub(crate) fn is_7bit(text: &str) -> bool {
+    text.lines().all(|line| {
+        line.len() <= 998
+            && line
+                .bytes()
+                .all(|b| b == b'\t' || (b' '..=b'~').contains(&b))
+    })
+}
+
+/// Writes text with CRLF line endings, terminating the last line.
+pub(crate) fn write_7bit<W: Write + ?Sized>(w: &mut W, text: &str) -> fmt::Result {
+    for line in text.split('\n') {
+        write!(w, "{}{}", line.strip_suffix('\r').unwrap_or(line), CRLF)?;
+    }
+    Ok(())
+}
+
+/// Writes text in the quoted-printable encoding, terminating the last line.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc2045#section-6.7
+pub(crate) fn write_quoted_printable<W: Write + ?Sized>(w: &mut W, text: &str) -> fmt::Result {
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line).as_bytes();
+        let mut line_length = 0;
+        for (i, &byte) in line.iter().enumerate() {
+            let is_last = i + 1 == line.len();
+            let is_literal = match byte {
+                b' ' | b'\t' => !is_last,
+                b'=' => false,
+                b'!'..=b'~' => true,
+                _ => false,
+            };
+            let width = if is_literal { 1 } else { 3 };
+            // Leave room for the soft line break, unless this is the end of
+            // the line:
+            let limit = if is_last {
+                MAX_ENCODED_LINE_LENGTH
+            } else {
+                MAX_ENCODED_LINE_LENGTH - 1
+            };
+            if line_length + width > limit {
+                write!(w, "={}", CRLF)?;
+                line_length = 0;
+            }
+            if is_literal {
+                w.write_char(byte as char)?;
+            } else {
+                write!(w, "={:02X}", byte)?;
+            }
+            line_length += width;
+        }
+        w.write_str(CRLF)?;
+    }
+    Ok(())
+}
+
+/// Writes binary data in the base64 encoding, wrapped at 76 characters.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc2045#section-6.8
+pub(crate) fn write_base64<W: Write + ?Sized>(w: &mut W, data: &[u8]) -> fmt::Result {
+    let encoded = STANDARD.encode(data);
+    for chunk in encoded.as_bytes().chunks(MAX_ENCODED_LINE_LENGTH) {
+        // The base64 alphabet is ASCII:
+        write!(w, "{}{}", core::str::from_utf8(chunk).unwrap(), CRLF)?;
+    }
+    Ok(())
+}
+
+/// Converts a decoded text part back into text with LF line endings,
+/// dropping the line break that terminates the part.
+#[cfg(any(feature = "jmap", feature = "mail-parser", feature = "mailparse"))]
+pub(crate) fn normalize_text(text: &str) -> String {
+    let text = text.replace(CRLF, "\n");
+    match text.strip_suffix('\n') {
+        Some(text) => text.to_string(),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_header_folds_long_lines() {
+        let words = encode_unstructured("lorem ipsum ".repeat(20).trim_end());
+        let mut output = String::new();
+        write_header(&mut output, "Subject", &words).unwrap();
+        assert!(output.ends_with(CRLF));
+        for line in output.split(CRLF) {
+            assert!(line.len() <= MAX_LINE_LENGTH);
+        }
+        assert_eq!(
+            output.replace("\r\n ", " "),
+            format!("Subject: {}\r\n", "lorem ipsum ".repeat(20).trim_end())
+        );
+    }
+
+    #[test]
+    fn test_encode_unstructured() {
+        assert_eq!(encode_unstructured("Hello, world"), ["Hello,", "world"]);
+        assert_eq!(encode_unstructured("Grüße"), ["=?utf-8?B?R3LDvMOfZQ==?="]);
+        assert_eq!(encode_unstructured("a  b"), ["=?utf-8?B?YSAgYg==?="]);
+        for word in encode_unstructured(&"Grüße ".repeat(30)) {
+            assert!(word.len() <= 75);
+        }
+    }
+
+    #[test]
+    fn test_encode_phrase() {
+        assert_eq!(encode_phrase("Alice Smith"), ["Alice", "Smith"]);
+        assert_eq!(encode_phrase("Smith, Alice"), ["\"Smith, Alice\""]);
+        assert_eq!(encode_phrase("Jürgen"), ["=?utf-8?B?SsO8cmdlbg==?="]);
+    }
+
+    #[test]
+    fn test_encode_parameter() {
+        assert_eq!(
+            encode_parameter("filename", "a b.txt"),
+            "filename=\"a b.txt\""
+        );
+        assert_eq!(
+            encode_parameter("filename", "résumé.pdf"),
+            "filename*=utf-8''r%C3%A9sum%C3%A9.pdf"
+        );
+    }
+
+    #[test]
+    fn test_write_quoted_printable() {
+        let mut output = String::new();
+        write_quoted_printable(&mut output, "Grüße = hi \n").unwrap();
+        assert_eq!(output, "Gr=C3=BC=C3=9Fe =3D hi=20\r\n\r\n");
+
+        let mut output = String::new();
+        write_quoted_printable(&mut output, &"é".repeat(40)).unwrap();
+        for line in output.split(CRLF) {
+            assert!(line.len() <= MAX_ENCODED_LINE_LENGTH);
+        }
+    }
+}
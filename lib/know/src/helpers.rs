@@ -1,4 +1,41 @@
 // This is free and unencumbered software released into the public domain.
 
+mod contact_proposal;
+pub use contact_proposal::*;
+
+mod correspondent_graph;
+pub use correspondent_graph::*;
+
+#[cfg(feature = "dkim")]
+mod dkim;
+#[cfg(feature = "dkim")]
+pub use dkim::*;
+
 mod email_address_list;
 pub use email_address_list::*;
+
+mod email_body;
+pub use email_body::*;
+
+mod html_to_text;
+pub use html_to_text::*;
+
+#[cfg(feature = "imap-proto")]
+mod imap_fetch;
+#[cfg(feature = "imap-proto")]
+pub use imap_fetch::*;
+
+#[cfg(feature = "irc")]
+pub use crate::classes::conversation::irc_log::*;
+
+#[cfg(feature = "slack")]
+pub use crate::classes::conversation::slack::*;
+
+#[cfg(feature = "sms-backup")]
+pub use crate::classes::conversation::sms_backup::*;
+
+#[cfg(feature = "telegram")]
+pub use crate::classes::conversation::telegram::*;
+
+#[cfg(feature = "whatsapp")]
+pub use crate::classes::conversation::whatsapp::*;
@@ -0,0 +1,356 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{
+    classes::{EmailMessage, Person},
+    datatypes::{EmailAddress, PersonName, PhoneNumber},
+    helpers::EmailBody,
+};
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// A value proposed by an extractor, with a confidence between 0 and 1.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Scored<T> {
+    pub value: T,
+    pub confidence: f32,
+}
+
+/// A proposed address book record for the sender of a message, based on the
+/// sender's mailbox and the contact details in the message's signature.
+///
+/// Review the proposal, or use [`ContactProposal::to_person`] to keep just
+/// the details with a sufficient confidence.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ContactProposal {
+    pub name: Option<Scored<PersonName>>,
+    pub emails: Vec<Scored<EmailAddress>>,
+    pub phones: Vec<Scored<PhoneNumber>>,
+    pub links: Vec<Scored<String>>,
+    pub accounts: Vec<Scored<String>>,
+}
+
+/// The platforms whose handles are recognized in signatures, by the labels
+/// or hosts that identify them.
+const PLATFORMS: [(&str, &[&str]); 6] = [
+    ("github", &["github", "github.com"]),
+    ("linkedin", &["linkedin", "linkedin.com"]),
+    ("mastodon", &["mastodon", "fediverse"]),
+    ("telegram", &["telegram", "t.me"]),
+    ("twitter", &["twitter", "x", "twitter.com", "x.com"]),
+    ("bluesky", &["bluesky", "bsky", "bsky.app"]),
+];
+
+impl ContactProposal {
+    /// Extracts a proposal from a message.
+    ///
+    /// The sender's mailbox is certain. Details found below a `-- `
+    /// signature delimiter are more likely to be the sender's than those
+    /// found in the last paragraph of an undelimited message, and labeled
+    /// details, e.g., `Mobile: +1 555 0100`, more likely than unlabeled ones.
+    pub fn from_message(message: &EmailMessage) -> Self {
+        let mut output = Self::default();
+        let sender = message.sender.as_ref().or(message.from.first());
+        if let Some(sender) = sender {
+            output.add_email(sender.clone(), 1.0);
+            if let Some(name) = message.display_name(sender) {
+                output.name = Some(Scored {
+                    value: name.into(),
+                    confidence: 0.9,
+                });
+            }
+        }
+
+        let Some(body) = message.body_parts() else {
+            return output;
+        };
+        let (signature, weight) = match body.signature {
+            Some(signature) => (signature, 1.0),
+            None => match last_paragraph(&body) {
+                Some(paragraph) => (paragraph, 0.6),
+                None => return output,
+            },
+        };
+        let sender_domain = sender.and_then(|sender| sender.as_str().rsplit_once('@'));
+        let sender_domain = sender_domain.map(|(_, domain)| domain);
+        for line in signature.lines() {
+            output.add_line(line, weight, sender_domain);
+        }
+        output
+    }
+
+    /// Returns a person with the details that have at least the given
+    /// confidence.
+    pub fn to_person(&self, min_confidence: f32) -> Person {
+        fn values<T: Clone>(input: &[Scored<T>], min_confidence: f32) -> Vec<T> {
+            input
+                .iter()
+                .filter(|scored| scored.confidence >= min_confidence)
+                .map(|scored| scored.value.clone())
+                .collect()
+        }
+        Person {
+            name: self
+                .name
+                .as_ref()
+                .filter(|name| name.confidence >= min_confidence)
+                .map(|name| name.value.clone())
+                .unwrap_or_default(),
+            emails: values(&self.emails, min_confidence),
+            phones: values(&self.phones, min_confidence),
+            links: values(&self.links, min_confidence),
+            accounts: values(&self.accounts, min_confidence),
+            ..Default::default()
+        }
+    }
+
+    fn add_line(&mut self, line: &str, weight: f32, sender_domain: Option<&str>) {
+        let (label, value) = match line.split_once(':') {
+            Some((label, value)) if label.len() <= 12 && !label.contains(char::is_whitespace) => (
+                Some(label.trim_end_matches('.').to_ascii_lowercase()),
+                value,
+            ),
+            _ => (None, line),
+        };
+        // Labels such as `http` belong to the value:
+        let (label, value) = match label.as_deref() {
+            Some("http" | "https") => (None, line),
+            _ => (label, value),
+        };
+        let platform = label.as_deref().and_then(platform);
+
+        let has_address = value.contains("://") || value.contains("www.") || value.contains('@');
+        if let Some(phone) = find_phone(value).filter(|_| !has_address) {
+            let confidence = match label.as_deref() {
+                Some("fax" | "f") => 0.2,
+                Some(
+                    "tel" | "t" | "phone" | "p" | "mobile" | "mob" | "m" | "cell" | "c" | "office"
+                    | "direct" | "telefon" | "handy",
+                ) => 0.9,
+                _ => 0.7,
+            };
+            self.add_phone(phone.into(), confidence * weight);
+        }
+
+        for word in value.split_whitespace() {
+            let word = word.trim_matches(|c: char| "<>()[],;\"'".contains(c));
+            let lowercase = word.to_ascii_lowercase();
+            if lowercase.starts_with("http://")
+                || lowercase.starts_with("https://")
+                || lowercase.starts_with("www.")
+            {
+                let link = match lowercase.starts_with("www.") {
+                    true => format!("https://{}", word),
+                    false => word.to_string(),
+                };
+                let host = link
+                    .split('/')
+                    .nth(2)
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                let host = host.strip_prefix("www.").unwrap_or(&host);
+                let confidence = match sender_domain {
+                    Some(domain) if host == domain || host.ends_with(&format!(".{}", domain)) => {
+                        0.9
+                    },
+                    _ => 0.8,
+                };
+                if let Some(account) = profile_account(&link) {
+                    self.add_account(account, confidence * weight);
+                }
+                self.add_link(link, confidence * weight);
+            } else if let Some(handle) = word.strip_prefix('@') {
+                if handle.is_empty() {
+                    continue;
+                }
+                let (account, confidence) = match (platform, handle.contains('@')) {
+                    (Some(platform), _) => (format!("{}:{}", platform, handle), 0.8),
+                    (None, true) => (format!("mastodon:{}", handle), 0.7),
+                    (None, false) => (format!("@{}", handle), 0.5),
+                };
+                self.add_account(account, confidence * weight);
+            } else if word.contains('@') {
+                if let Ok(email) = word.trim_start_matches("mailto:").parse::<EmailAddress>() {
+                    self.add_email(email, 0.8 * weight);
+                }
+            } else if let (Some(platform), true) = (platform, value.split_whitespace().count() == 1)
+            {
+                self.add_account(format!("{}:{}", platform, word), 0.7 * weight);
+            }
+        }
+    }
+
+    fn add_email(&mut self, value: EmailAddress, confidence: f32) {
+        add(&mut self.emails, value, confidence);
+    }
+
+    fn add_phone(&mut self, value: PhoneNumber, confidence: f32) {
+        add(&mut self.phones, value, confidence);
+    }
+
+    fn add_link(&mut self, value: String, confidence: f32) {
+        add(&mut self.links, value, confidence);
+    }
+
+    fn add_account(&mut self, value: String, confidence: f32) {
+        add(&mut self.accounts, value, confidence);
+    }
+}
+
+impl From<&EmailMessage> for ContactProposal {
+    fn from(input: &EmailMessage) -> Self {
+        Self::from_message(input)
+    }
+}
+
+/// Adds a value, or raises the confidence of a value already proposed.
+fn add<T: PartialEq>(output: &mut Vec<Scored<T>>, value: T, confidence: f32) {
+    match output.iter_mut().find(|scored| scored.value == value) {
+        Some(scored) => scored.confidence = scored.confidence.max(confidence),
+        None => output.push(Scored { value, confidence }),
+    }
+}
+
+/// Returns the last paragraph of the new text, if it is short enough to be
+/// an undelimited signature.
+fn last_paragraph(body: &EmailBody) -> Option<String> {
+    let paragraph = body.text.rsplit("\n\n").next()?;
+    (paragraph.lines().count() <= 6).then(|| paragraph.to_string())
+}
+
+fn platform(label: &str) -> Option<&'static str> {
+    PLATFORMS
+        .iter()
+        .find(|(_, labels)| labels.contains(&label))
+        .map(|(platform, _)| *platform)
+}
+
+/// Returns the account of a profile URL, e.g., `github:alice` for
+/// `https://github.com/alice`.
+fn profile_account(link: &str) -> Option<String> {
+    let mut segments = link.split('/').skip(2);
+    let host = segments.next()?.to_ascii_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host);
+    let platform = platform(host)?;
+    let user = match (platform, segments.next()?) {
+        ("linkedin", "in") => segments.next()?,
+        ("bluesky", "profile") => segments.next()?,
+        (_, user) => user,
+    };
+    let user = user.split(['?', '#']).next()?;
+    (!user.is_empty()).then(|| format!("{}:{}", platform, user))
+}
+
+/// Finds a phone number, i.e., a run of 7 to 15 digits with the usual
+/// separators, optionally preceded by `+`, that isn't a date.
+///
+/// See: https://www.itu.int/rec/T-REC-E.164
+fn find_phone(input: &str) -> Option<String> {
+    let start = input.find(|c: char| c == '+' || c == '(' || c.is_ascii_digit())?;
+    let candidate: String = input[start..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || " +-.()/".contains(*c))
+        .collect();
+    let candidate = candidate.trim_end_matches(|c: char| !c.is_ascii_digit());
+    // Skip dates, e.g., `2024-01-15` or `15.01.2024 10:30`:
+    let word = candidate.split(' ').next().unwrap_or_default();
+    if is_date(word) {
+        return input.get(start + word.len()..).and_then(find_phone);
+    }
+    let digits = candidate.chars().filter(char::is_ascii_digit).count();
+    if (7..=15).contains(&digits) {
+        return Some(candidate.trim().to_string());
+    }
+    // Try again after the rejected run, e.g., after a postal code:
+    let end = start + candidate.len().max(1);
+    input.get(end..).and_then(find_phone)
+}
+
+/// Returns whether the text is a numeric date with a 4-digit year, e.g.,
+/// `2024-01-15`, `15.01.2024` or `1/15/2024`.
+fn is_date(input: &str) -> bool {
+    let Some(separator) = input.chars().find(|c| "-./".contains(*c)) else {
+        return false;
+    };
+    let lengths: Vec<usize> = input
+        .split(separator)
+        .map(|part| match part.chars().all(|c| c.is_ascii_digit()) {
+            true => part.len(),
+            false => 0,
+        })
+        .collect();
+    matches!(lengths[..], [4, 1..=2, 1..=2] | [1..=2, 1..=2, 4])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(body: &str) -> EmailMessage {
+        let from: EmailAddress = "alice@example.org".parse().unwrap();
+        EmailMessage {
+            from: vec![from.clone()],
+            names: [(from, "Alice Smith".into())].into(),
+            body: Some(body.into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_delimited_signature() {
+        let proposal = ContactProposal::from_message(&message(
+            "See you at 10:30.\n\n-- \nAlice Smith, CTO\nExample Corp, 12345 Springfield\nMobile: +1 (555) 010-0199\nFax: +1 555 010 0100\nhttps://www.example.org/team\nGitHub: @alice\nhttps://linkedin.com/in/asmith\nalice.smith@example.com\n\n> Meeting at 10?\n> +49 30 1234567\n",
+        ));
+        let person = proposal.to_person(0.75);
+        assert_eq!(person.name.as_str(), "Alice Smith");
+        assert_eq!(
+            person.emails,
+            [
+                "alice@example.org".parse().unwrap(),
+                "alice.smith@example.com".parse().unwrap()
+            ]
+        );
+        assert_eq!(person.phones, [PhoneNumber::from("+1 (555) 010-0199")]);
+        assert_eq!(
+            person.links,
+            [
+                "https://www.example.org/team",
+                "https://linkedin.com/in/asmith"
+            ]
+        );
+        assert_eq!(person.accounts, ["github:alice", "linkedin:asmith"]);
+
+        // The fax number is proposed, but with a low confidence:
+        assert_eq!(proposal.phones.len(), 2);
+        assert!(proposal.phones[1].confidence < 0.5);
+        assert_eq!(proposal.links[0].confidence, 0.9);
+    }
+
+    #[test]
+    fn test_undelimited_signature() {
+        let proposal = ContactProposal::from_message(&message(
+            "Thanks, see below.\n\nAlice\n+1 555 010 0199\n@alice@mastodon.social",
+        ));
+        assert_eq!(proposal.phones.len(), 1);
+        assert!(proposal.phones[0].confidence < 0.5);
+        assert_eq!(proposal.accounts[0].value, "mastodon:alice@mastodon.social");
+        let person = proposal.to_person(0.9);
+        assert_eq!(person.emails.len(), 1);
+        assert!(person.phones.is_empty());
+    }
+
+    #[test]
+    fn test_find_phone() {
+        assert_eq!(
+            find_phone("Call 12345 or 030 123 4567."),
+            Some("030 123 4567".into())
+        );
+        assert_eq!(find_phone("Room 101, floor 3"), None);
+        assert_eq!(find_phone("Due 2024-01-15, or 15.01.2024 10:30"), None);
+        assert_eq!(
+            find_phone("On 1/15/2024, call +1 555 010 0199"),
+            Some("+1 555 010 0199".into())
+        );
+    }
+}
@@ -0,0 +1,418 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{
+    classes::{EmailMessage, Person, PersonRef},
+    datatypes::{DateTime, Duration, EmailAddress, EmailMessageId},
+};
+use alloc::{collections::BTreeMap, rc::Rc, string::String, vec::Vec};
+
+/// Domains of public mail providers, whose users aren't colleagues.
+const FREEMAIL_DOMAINS: [&str; 12] = [
+    "aol.com",
+    "gmail.com",
+    "gmx.de",
+    "gmx.net",
+    "googlemail.com",
+    "hotmail.com",
+    "icloud.com",
+    "me.com",
+    "outlook.com",
+    "proton.me",
+    "protonmail.com",
+    "yahoo.com",
+];
+
+/// Aggregate statistics on the correspondents of an email archive, and the
+/// relationships between them inferred from the traffic.
+///
+/// Add messages in any order with [`CorrespondentGraph::add`], or collect
+/// them from an iterator.
+#[derive(Clone, Debug, Default)]
+pub struct CorrespondentGraph {
+    /// The statistics of each address that sent or received a message,
+    /// with its local part in lowercase.
+    pub correspondents: BTreeMap<EmailAddress, Correspondent>,
+
+    /// The number of messages sent from the first address to the second,
+    /// including as `Cc` and `Bcc`.
+    pub traffic: BTreeMap<(EmailAddress, EmailAddress), usize>,
+
+    /// The number of messages in which the first address copied the second
+    /// as `Cc`.
+    pub ccs: BTreeMap<(EmailAddress, EmailAddress), usize>,
+
+    /// The number of messages in which two addresses were both visible
+    /// participants, i.e., sender or `To` or `Cc` recipients, keyed by the
+    /// ordered pair of addresses.
+    pub co_occurrences: BTreeMap<(EmailAddress, EmailAddress), usize>,
+
+    /// The sender and date of each message, for the replies to come.
+    messages: BTreeMap<EmailMessageId, (EmailAddress, DateTime)>,
+
+    /// The replies whose message wasn't added yet.
+    pending_replies: BTreeMap<EmailMessageId, Vec<(EmailAddress, DateTime)>>,
+}
+
+/// The statistics of an address in a [`CorrespondentGraph`].
+#[derive(Clone, Debug, Default)]
+pub struct Correspondent {
+    /// The display name last seen with the address.
+    pub name: Option<String>,
+
+    /// The number of messages sent from the address.
+    pub sent: usize,
+
+    /// The number of messages received by the address.
+    pub received: usize,
+
+    /// The date of the earliest message sent or received.
+    pub first: Option<DateTime>,
+
+    /// The date of the latest message sent or received.
+    pub last: Option<DateTime>,
+
+    /// The number of messages sent or received, by year and month.
+    pub monthly: BTreeMap<(i16, i8), usize>,
+
+    /// The time taken to reply to other correspondents, from shortest to
+    /// longest.
+    pub response_times: Vec<Duration>,
+
+    /// The `response_times` in seconds, to keep them sorted.
+    response_seconds: Vec<i64>,
+}
+
+impl Correspondent {
+    /// The number of messages sent or received.
+    pub fn total(&self) -> usize {
+        self.sent + self.received
+    }
+
+    /// The median time taken to reply to other correspondents.
+    pub fn median_response_time(&self) -> Option<&Duration> {
+        self.response_times
+            .get(self.response_times.len().checked_sub(1)? / 2)
+    }
+
+    fn count(&mut self, date: &DateTime) {
+        let zoned = date.as_zoned();
+        *self
+            .monthly
+            .entry((zoned.year(), zoned.month()))
+            .or_default() += 1;
+        if self.first.as_ref().is_none_or(|first| date < first) {
+            self.first = Some(date.clone());
+        }
+        if self.last.as_ref().is_none_or(|last| date > last) {
+            self.last = Some(date.clone());
+        }
+    }
+
+    fn add_response_time(&mut self, message_date: &DateTime, reply_date: &DateTime) {
+        let seconds = reply_date.as_zoned().timestamp().as_second()
+            - message_date.as_zoned().timestamp().as_second();
+        if seconds < 0 {
+            return; // clock skew
+        }
+        let Ok(duration) = reply_date.since(message_date) else {
+            return;
+        };
+        let index = self
+            .response_seconds
+            .partition_point(|other| *other <= seconds);
+        self.response_seconds.insert(index, seconds);
+        self.response_times.insert(index, duration);
+    }
+}
+
+impl CorrespondentGraph {
+    /// Adds a message's sender, recipients, and reply latency.
+    ///
+    /// Addresses are keyed with their local part in lowercase, so that
+    /// `Alice@example.org` and `alice@example.org` are one correspondent.
+    pub fn add(&mut self, message: &EmailMessage) {
+        let lowercase = |addresses: &[EmailAddress]| -> Vec<EmailAddress> {
+            addresses.iter().map(EmailAddress::to_lowercase).collect()
+        };
+        let (from, to, cc, bcc) = (
+            lowercase(&message.from),
+            lowercase(&message.to),
+            lowercase(&message.cc),
+            lowercase(&message.bcc),
+        );
+        let mut names = BTreeMap::new();
+        for address in message.from.iter().chain(&message.to).chain(&message.cc) {
+            if let Some(name) = message.display_name(address) {
+                names.insert(address.to_lowercase(), name);
+            }
+        }
+
+        let date = &message.date;
+        let sender = message.sender.as_ref().map(EmailAddress::to_lowercase);
+        let sender = sender.as_ref().or(from.first());
+        let visible = dedup(from.iter().chain(&to).chain(&cc));
+        let recipients = dedup(to.iter().chain(&cc).chain(&bcc));
+
+        let addresses = sender
+            .into_iter()
+            .chain(visible.iter().copied())
+            .chain(recipients.iter().copied());
+        for address in dedup(addresses) {
+            let correspondent = self.correspondents.entry(address.clone()).or_default();
+            if let Some(name) = names.get(address) {
+                correspondent.name = Some((*name).into());
+            }
+            if from.contains(address) || sender == Some(address) {
+                correspondent.sent += 1;
+            }
+            if recipients.contains(&address) {
+                correspondent.received += 1;
+            }
+            correspondent.count(date);
+        }
+
+        if let Some(sender) = sender {
+            for recipient in recipients.iter().filter(|recipient| **recipient != sender) {
+                *self
+                    .traffic
+                    .entry((sender.clone(), (*recipient).clone()))
+                    .or_default() += 1;
+            }
+            for cc in dedup(cc.iter()).into_iter().filter(|cc| *cc != sender) {
+                *self.ccs.entry((sender.clone(), cc.clone())).or_default() += 1;
+            }
+        }
+        for (i, a) in visible.iter().enumerate() {
+            for b in &visible[i + 1..] {
+                let pair = match a < b {
+                    true => ((*a).clone(), (*b).clone()),
+                    false => ((*b).clone(), (*a).clone()),
+                };
+                *self.co_occurrences.entry(pair).or_default() += 1;
+            }
+        }
+
+        let Some(sender) = sender else {
+            return;
+        };
+        if let Some(parent) = message.in_reply_to.first() {
+            match self.messages.get(parent) {
+                Some((parent_sender, parent_date)) if parent_sender != sender => {
+                    let correspondent = self.correspondents.get_mut(sender).unwrap(); // added above
+                    correspondent.add_response_time(parent_date, date);
+                },
+                Some(_) => {},
+                None => {
+                    let replies = self.pending_replies.entry(parent.clone()).or_default();
+                    replies.push((sender.clone(), date.clone()));
+                },
+            }
+        }
+        if let Some(ref id) = message.id {
+            for (replier, reply_date) in self.pending_replies.remove(id).unwrap_or_default() {
+                if &replier != sender
+                    && let Some(correspondent) = self.correspondents.get_mut(&replier)
+                {
+                    correspondent.add_response_time(date, &reply_date);
+                }
+            }
+            self.messages
+                .insert(id.clone(), (sender.clone(), date.clone()));
+        }
+    }
+
+    /// Returns the correspondents by decreasing number of messages.
+    pub fn top(&self) -> Vec<(&EmailAddress, &Correspondent)> {
+        let mut output: Vec<_> = self.correspondents.iter().collect();
+        output.sort_by_key(|(_, correspondent)| core::cmp::Reverse(correspondent.total()));
+        output
+    }
+
+    /// Returns the number of messages exchanged between two addresses, in
+    /// either direction.
+    pub fn exchanged(&self, a: &EmailAddress, b: &EmailAddress) -> usize {
+        let count = |from: &EmailAddress, to: &EmailAddress| {
+            self.traffic
+                .get(&(from.to_lowercase(), to.to_lowercase()))
+                .copied()
+                .unwrap_or_default()
+        };
+        count(a, b) + count(b, a)
+    }
+
+    /// Returns a person for each correspondent with at least `min_messages`
+    /// messages, by decreasing number of messages.
+    ///
+    /// A person `knows` the correspondents they exchanged at least
+    /// `min_messages` messages with, and those sharing their domain are also
+    /// `colleagues`, unless the domain is a public mail provider.
+    pub fn to_persons(&self, min_messages: usize) -> Vec<Person> {
+        let person = |address: &EmailAddress, correspondent: &Correspondent| Person {
+            id: Some(format!("mailto:{}", address)),
+            name: correspondent.name.as_deref().unwrap_or_default().into(),
+            emails: vec![address.clone()],
+            ..Default::default()
+        };
+        let correspondents: Vec<_> = self
+            .top()
+            .into_iter()
+            .filter(|(_, correspondent)| correspondent.total() >= min_messages)
+            .collect();
+        let refs: BTreeMap<&EmailAddress, PersonRef> = correspondents
+            .iter()
+            .map(|(address, correspondent)| {
+                (*address, PersonRef(Rc::new(person(address, correspondent))))
+            })
+            .collect();
+
+        correspondents
+            .iter()
+            .map(|(address, correspondent)| {
+                let mut output = person(address, correspondent);
+                for (other, other_ref) in &refs {
+                    if other == address || self.exchanged(address, other) < min_messages.max(1) {
+                        continue;
+                    }
+                    output.knows.push(other_ref.clone());
+                    if let Some(domain) = organization_domain(address)
+                        && organization_domain(other) == Some(domain)
+                    {
+                        output.colleagues.push(other_ref.clone());
+                    }
+                }
+                output
+            })
+            .collect()
+    }
+}
+
+impl<'a> Extend<&'a EmailMessage> for CorrespondentGraph {
+    fn extend<I: IntoIterator<Item = &'a EmailMessage>>(&mut self, messages: I) {
+        for message in messages {
+            self.add(message);
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a EmailMessage> for CorrespondentGraph {
+    fn from_iter<I: IntoIterator<Item = &'a EmailMessage>>(messages: I) -> Self {
+        let mut output = Self::default();
+        output.extend(messages);
+        output
+    }
+}
+
+fn dedup<'a>(addresses: impl Iterator<Item = &'a EmailAddress>) -> Vec<&'a EmailAddress> {
+    let mut output: Vec<&EmailAddress> = Vec::new();
+    for address in addresses {
+        if !output.contains(&address) {
+            output.push(address);
+        }
+    }
+    output
+}
+
+/// Returns the domain of an address, unless it's a public mail provider.
+fn organization_domain(address: &EmailAddress) -> Option<&str> {
+    let (_, domain) = address.as_str().rsplit_once('@')?;
+    (!FREEMAIL_DOMAINS.contains(&domain)).then_some(domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(id: &str, date: &str, from: &str, to: &[&str], cc: &[&str]) -> EmailMessage {
+        EmailMessage {
+            id: Some(id.into()),
+            date: date.parse().unwrap(),
+            from: vec![from.parse().unwrap()],
+            to: to.iter().map(|to| to.parse().unwrap()).collect(),
+            cc: cc.iter().map(|cc| cc.parse().unwrap()).collect(),
+            ..Default::default()
+        }
+    }
+
+    fn reply(mut message: EmailMessage, parent: &str) -> EmailMessage {
+        message.in_reply_to = vec![parent.into()];
+        message
+    }
+
+    #[test]
+    fn test_correspondent_graph() {
+        let alice: EmailAddress = "alice@example.org".parse().unwrap();
+        let bob: EmailAddress = "bob@example.org".parse().unwrap();
+        let carol: EmailAddress = "carol@gmail.com".parse().unwrap();
+        let messages = [
+            // The reply is added before the message it replies to:
+            reply(
+                message(
+                    "2@x",
+                    "2025-07-01T12:30:00Z",
+                    "bob@example.org",
+                    &["alice@example.org"],
+                    &[],
+                ),
+                "1@x",
+            ),
+            message(
+                "1@x",
+                "2025-07-01T12:00:00Z",
+                "alice@example.org",
+                &["bob@example.org"],
+                &["carol@gmail.com"],
+            ),
+            // The same correspondent, whatever the case of the local part:
+            reply(
+                message(
+                    "3@x",
+                    "2025-08-01T09:00:00+02:00",
+                    "Alice@example.org",
+                    &["bob@example.org"],
+                    &[],
+                ),
+                "2@x",
+            ),
+        ];
+        let graph: CorrespondentGraph = messages.iter().collect();
+
+        let top = graph.top();
+        assert_eq!(top.len(), 3);
+        assert_eq!(top[0].1.total(), 3);
+        assert_eq!(graph.correspondents[&alice].sent, 2);
+        assert_eq!(graph.correspondents[&alice].received, 1);
+        assert_eq!(
+            graph.correspondents[&alice].monthly,
+            [((2025, 7), 2), ((2025, 8), 1)].into()
+        );
+        assert_eq!(graph.correspondents[&carol].received, 1);
+
+        let bob_stats = &graph.correspondents[&bob];
+        assert_eq!(bob_stats.response_times.len(), 1);
+        assert_eq!(bob_stats.median_response_time().unwrap().to_string(), "30m");
+        assert_eq!(graph.correspondents[&alice].response_times.len(), 1);
+
+        assert_eq!(graph.ccs[&(alice.clone(), carol.clone())], 1);
+        assert_eq!(graph.co_occurrences[&(bob.clone(), carol.clone())], 1);
+        assert_eq!(graph.exchanged(&alice, &bob), 3);
+
+        let persons = graph.to_persons(1);
+        assert_eq!(persons.len(), 3);
+        let alice = persons
+            .iter()
+            .find(|person| person.id.as_deref() == Some("mailto:alice@example.org"))
+            .unwrap();
+        assert_eq!(alice.knows.len(), 2);
+        assert_eq!(alice.colleagues.len(), 1);
+        assert_eq!(alice.colleagues[0].0.emails, [bob]);
+
+        // Carol only received a single message from Alice:
+        assert_eq!(graph.to_persons(2).len(), 2);
+        assert!(
+            graph
+                .to_persons(2)
+                .iter()
+                .all(|person| person.knows.len() == 1)
+        );
+    }
+}
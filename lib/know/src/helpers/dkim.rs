@@ -0,0 +1,852 @@
+// This is free and unencumbered software released into the public domain.
+
+use alloc::{
+    borrow::Cow,
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use base64::{Engine, engine::general_purpose::STANDARD as BASE64};
+use sha2::{Digest, Sha256};
+
+/// A source of DKIM public keys, e.g., DNS or a local file.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc6376#section-3.6.2
+pub trait DkimKeyResolver {
+    /// Returns the TXT record of a key name, e.g.,
+    /// `brisbane._domainkey.example.com`, or `None` if there is no such key.
+    fn resolve(&self, name: &str) -> Result<Option<String>, crate::Error>;
+}
+
+impl<F> DkimKeyResolver for F
+where
+    F: Fn(&str) -> Result<Option<String>, crate::Error>,
+{
+    fn resolve(&self, name: &str) -> Result<Option<String>, crate::Error> {
+        self(name)
+    }
+}
+
+/// DKIM public keys supplied locally, for tests and air-gapped systems.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DkimKeys(BTreeMap<String, String>);
+
+impl DkimKeys {
+    /// Parses keys, one per line, as a key name followed by its TXT record,
+    /// e.g., `brisbane._domainkey.example.com v=DKIM1; k=ed25519; p=...`.
+    ///
+    /// Zone file lines, with an `IN TXT` and the record in quoted strings,
+    /// are accepted too. Blank lines and `#` or `;` comments are ignored.
+    pub fn parse(input: &str) -> Self {
+        let mut output = Self::default();
+        for line in input.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+            let Some((name, mut record)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            for keyword in ["IN", "TXT"] {
+                let rest = record.trim_start();
+                if let Some(rest) = rest.strip_prefix(keyword)
+                    && rest.starts_with(char::is_whitespace)
+                {
+                    record = rest;
+                }
+            }
+            let record = record.trim();
+            let record = match record.starts_with('"') {
+                true => record.split('"').skip(1).step_by(2).collect(),
+                false => record.to_string(),
+            };
+            output.insert(name, record);
+        }
+        output
+    }
+
+    /// Reads keys from a file in the format of [`DkimKeys::parse`].
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<std::path::Path>) -> Result<Self, crate::Error> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    pub fn insert(&mut self, name: impl AsRef<str>, record: impl Into<String>) {
+        let name = name.as_ref().trim_end_matches('.').to_ascii_lowercase();
+        self.0.insert(name, record.into());
+    }
+}
+
+impl DkimKeyResolver for DkimKeys {
+    fn resolve(&self, name: &str) -> Result<Option<String>, crate::Error> {
+        Ok(self.0.get(&name.to_ascii_lowercase()).cloned())
+    }
+}
+
+/// The result of verifying a DKIM signature or ARC chain.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc8601#section-2.7.1
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum DkimResult {
+    Pass,
+
+    /// The signature or body hash doesn't match, e.g., because the message
+    /// was modified in transit.
+    Fail(String),
+
+    /// The signature can't be verified, e.g., because it's malformed, its
+    /// key was revoked, or its algorithm isn't supported.
+    PermError(String),
+
+    /// The key couldn't be resolved; trying again later may succeed.
+    TempError(String),
+}
+
+impl DkimResult {
+    pub fn is_pass(&self) -> bool {
+        *self == Self::Pass
+    }
+}
+
+/// A verified DKIM signature of a message.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct DkimVerification {
+    /// The signing domain, i.e., the `d=` tag.
+    pub domain: String,
+
+    /// The key selector, i.e., the `s=` tag.
+    pub selector: String,
+
+    /// The signing identity, i.e., the `i=` tag, e.g., `@example.com`.
+    pub identity: Option<String>,
+
+    pub result: DkimResult,
+}
+
+/// The result of verifying the ARC chain of a message.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc8617#section-5.2
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct ArcVerification {
+    /// `None` if the message has no ARC sets.
+    pub result: Option<DkimResult>,
+
+    /// The domains that sealed the chain, from the first intermediary to the
+    /// last.
+    pub sealers: Vec<String>,
+}
+
+/// Verifies the DKIM signatures of a raw message.
+///
+/// The `x=` expiration isn't enforced, since archived mail is verified long
+/// after its signatures were meant to expire.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc6376#section-6
+pub fn verify_dkim(raw: &[u8], resolver: &impl DkimKeyResolver) -> Vec<DkimVerification> {
+    let raw = crlf(raw);
+    let (headers, body) = split_message(&raw);
+    headers
+        .iter()
+        .filter(|header| header.is("DKIM-Signature"))
+        .map(|header| {
+            let tags = header.tags();
+            let result = match Signature::parse(&tags, true) {
+                Ok(signature) => signature.verify(header, &headers, body, resolver),
+                Err(error) => error,
+            };
+            DkimVerification {
+                domain: tags
+                    .get("d")
+                    .map(|d| d.to_ascii_lowercase())
+                    .unwrap_or_default(),
+                selector: tags.get("s").cloned().unwrap_or_default(),
+                identity: tags.get("i").cloned(),
+                result,
+            }
+        })
+        .collect()
+}
+
+/// Verifies the ARC chain of a raw message, i.e., the latest
+/// `ARC-Message-Signature` and all `ARC-Seal`s.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc8617#section-5.2
+pub fn verify_arc(raw: &[u8], resolver: &impl DkimKeyResolver) -> ArcVerification {
+    let raw = crlf(raw);
+    let (headers, body) = split_message(&raw);
+    let mut output = ArcVerification {
+        result: None,
+        sealers: Vec::new(),
+    };
+
+    // Collect the ARC sets, i.e., the ARC-Authentication-Results,
+    // ARC-Message-Signature, and ARC-Seal of each instance:
+    let mut sets: BTreeMap<usize, [Vec<&Header>; 3]> = BTreeMap::new();
+    for header in &headers {
+        let Some(kind) = [
+            "ARC-Authentication-Results",
+            "ARC-Message-Signature",
+            "ARC-Seal",
+        ]
+        .iter()
+        .position(|name| header.is(name)) else {
+            continue;
+        };
+        // The ARC-Authentication-Results isn't a tag list, but starts with
+        // the instance tag:
+        let instance = match kind {
+            0 => header
+                .value()
+                .split(';')
+                .next()
+                .and_then(|tag| tag.trim().strip_prefix("i="))
+                .map(ToString::to_string),
+            _ => header.tags().remove("i"),
+        };
+        let instance = instance
+            .and_then(|i| i.trim().parse().ok())
+            .unwrap_or_default();
+        sets.entry(instance).or_default()[kind].push(header);
+    }
+    let Some(&count) = sets.keys().next_back() else {
+        return output;
+    };
+    let fail = |reason: &str| Some(DkimResult::Fail(reason.into()));
+    if count > 50
+        || !sets.keys().copied().eq(1..=count)
+        || sets
+            .values()
+            .any(|set| set.iter().any(|headers| headers.len() != 1))
+    {
+        output.result = fail("invalid ARC chain structure");
+        return output;
+    }
+    let set = |i: usize| sets[&i].each_ref().map(|headers| headers[0]);
+
+    // Check the chain validation statuses:
+    for i in 1..=count {
+        let seal = set(i)[2].tags();
+        let expected = if i == 1 { "none" } else { "pass" };
+        match seal.get("cv").map(|cv| cv.to_ascii_lowercase()) {
+            Some(cv) if cv == expected => {},
+            Some(cv) if cv == "fail" => {
+                output.result = fail("the ARC chain was already broken");
+                return output;
+            },
+            _ => {
+                output.result = fail("invalid ARC-Seal cv= tag");
+                return output;
+            },
+        }
+    }
+
+    // Verify the latest message signature:
+    let message_signature = set(count)[1];
+    let result = match Signature::parse(&message_signature.tags(), false) {
+        Ok(signature) => signature.verify(message_signature, &headers, body, resolver),
+        Err(error) => error,
+    };
+    if !result.is_pass() {
+        output.result = Some(result);
+        return output;
+    }
+
+    // Verify the seals, from the latest to the first:
+    for i in (1..=count).rev() {
+        let seal = set(i)[2];
+        let mut signature = match Signature::parse(&seal.tags(), false) {
+            Ok(signature) => signature,
+            Err(error) => {
+                output.result = Some(error);
+                return output;
+            },
+        };
+        signature.header_canonicalization = Canonicalization::Relaxed;
+        let mut data = Vec::new();
+        for j in 1..=i {
+            for header in set(j) {
+                if j == i && header.is("ARC-Seal") {
+                    data.extend(
+                        signature
+                            .header_canonicalization
+                            .header(&header.without_b()),
+                    );
+                } else {
+                    data.extend(signature.header_canonicalization.header(header.raw));
+                    data.extend(b"\r\n");
+                }
+            }
+        }
+        let result = signature.verify_data(&data, resolver);
+        if !result.is_pass() {
+            output.result = Some(result);
+            return output;
+        }
+    }
+
+    output.sealers = (1..=count)
+        .filter_map(|i| set(i)[2].tags().get("d").map(|d| d.to_ascii_lowercase()))
+        .collect();
+    output.result = Some(DkimResult::Pass);
+    output
+}
+
+/// A header field of a raw message.
+#[derive(Debug)]
+struct Header<'a> {
+    name: &'a str,
+
+    /// The field, including the name and any folding, without the final
+    /// line break.
+    raw: &'a str,
+}
+
+impl Header<'_> {
+    fn is(&self, name: &str) -> bool {
+        self.name.eq_ignore_ascii_case(name)
+    }
+
+    fn value(&self) -> &str {
+        &self.raw[self.raw.find(':').map_or(self.raw.len(), |i| i + 1)..]
+    }
+
+    /// Parses the value as a tag list, e.g., `v=1; a=rsa-sha256`.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc6376#section-3.2
+    fn tags(&self) -> BTreeMap<String, String> {
+        self.value()
+            .split(';')
+            .filter_map(|tag| tag.split_once('='))
+            .map(|(name, value)| (name.trim().to_string(), unfold(value).trim().to_string()))
+            .collect()
+    }
+
+    /// Returns the field with the value of its `b=` tag removed.
+    fn without_b(&self) -> String {
+        let start = self.raw.len() - self.value().len();
+        let mut output = self.raw[..start].to_string();
+        let mut tags = self.value().split(';').peekable();
+        while let Some(tag) = tags.next() {
+            match tag.split_once('=') {
+                Some((name, _)) if name.trim() == "b" => {
+                    output.push_str(name);
+                    output.push('=');
+                },
+                _ => output.push_str(tag),
+            }
+            if tags.peek().is_some() {
+                output.push(';');
+            }
+        }
+        output
+    }
+}
+
+/// See: https://datatracker.ietf.org/doc/html/rfc6376#section-3.4
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Canonicalization {
+    Simple,
+    Relaxed,
+}
+
+impl Canonicalization {
+    fn header(self, raw: &str) -> Vec<u8> {
+        match self {
+            Self::Simple => raw.as_bytes().to_vec(),
+            Self::Relaxed => {
+                let (name, value) = raw.split_once(':').unwrap_or((raw, ""));
+                let mut output = name.trim_end().to_ascii_lowercase().into_bytes();
+                output.push(b':');
+                let value = compress_whitespace(unfold(value).as_bytes());
+                output.extend_from_slice(value.trim_ascii());
+                output
+            },
+        }
+    }
+
+    fn body(self, body: &[u8]) -> Vec<u8> {
+        let mut lines: Vec<Cow<[u8]>> = body
+            .split(|byte| *byte == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .map(|line| match self {
+                Self::Simple => Cow::Borrowed(line),
+                Self::Relaxed => {
+                    let mut line = compress_whitespace(line);
+                    if line.last() == Some(&b' ') {
+                        line.pop();
+                    }
+                    Cow::Owned(line)
+                },
+            })
+            .collect();
+        while lines.last().is_some_and(|line| line.is_empty()) {
+            lines.pop();
+        }
+        let mut output = Vec::new();
+        for line in &lines {
+            output.extend_from_slice(line);
+            output.extend_from_slice(b"\r\n");
+        }
+        if output.is_empty() && self == Self::Simple {
+            output.extend_from_slice(b"\r\n");
+        }
+        output
+    }
+}
+
+/// A parsed `DKIM-Signature`, `ARC-Message-Signature`, or `ARC-Seal`.
+struct Signature {
+    algorithm: Algorithm,
+    domain: String,
+    selector: String,
+    identity: Option<String>,
+    headers: Vec<String>,
+    header_canonicalization: Canonicalization,
+    body_canonicalization: Canonicalization,
+    body_hash: Vec<u8>,
+    body_length: Option<usize>,
+    signature: Vec<u8>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Algorithm {
+    RsaSha256,
+    Ed25519Sha256,
+}
+
+impl Signature {
+    fn parse(tags: &BTreeMap<String, String>, is_dkim: bool) -> Result<Self, DkimResult> {
+        let error = |reason: &str| DkimResult::PermError(reason.into());
+        let tag = |name: &str| {
+            tags.get(name)
+                .ok_or_else(|| error(&format!("missing {}= tag", name)))
+        };
+        let base64 = |name: &str| -> Result<Vec<u8>, DkimResult> {
+            let value: String = tag(name)?.split_whitespace().collect();
+            BASE64
+                .decode(value)
+                .map_err(|_| error(&format!("invalid {}= tag", name)))
+        };
+
+        if is_dkim && tag("v")? != "1" {
+            return Err(error("unsupported version"));
+        }
+        let algorithm = match tag("a")?.to_ascii_lowercase().as_str() {
+            "rsa-sha256" => Algorithm::RsaSha256,
+            "ed25519-sha256" => Algorithm::Ed25519Sha256,
+            // See: https://datatracker.ietf.org/doc/html/rfc8301#section-3.1
+            "rsa-sha1" => return Err(error("rsa-sha1 is no longer secure")),
+            _ => return Err(error("unsupported algorithm")),
+        };
+        let (header_canonicalization, body_canonicalization) =
+            match tags.get("c").map(|c| c.to_ascii_lowercase()) {
+                None => ("simple".into(), "simple".into()),
+                Some(c) => match c.split_once('/') {
+                    Some((header, body)) => (header.to_string(), body.to_string()),
+                    None => (c, "simple".into()),
+                },
+            };
+        let canonicalization = |name: &str| match name.trim() {
+            "simple" => Ok(Canonicalization::Simple),
+            "relaxed" => Ok(Canonicalization::Relaxed),
+            _ => Err(error("unsupported canonicalization")),
+        };
+        let domain = tag("d")?.to_ascii_lowercase();
+        // The ARC i= tag is the instance number rather than an identity:
+        let identity = tags.get("i").filter(|_| is_dkim).cloned();
+        if let Some(ref identity) = identity {
+            let (_, identity_domain) = identity.rsplit_once('@').unwrap_or_default();
+            let identity_domain = identity_domain.to_ascii_lowercase();
+            if identity_domain != domain && !identity_domain.ends_with(&format!(".{}", domain)) {
+                return Err(error("i= tag isn't within d= domain"));
+            }
+        }
+        let headers: Vec<String> = match tags.get("h") {
+            Some(h) => h
+                .split(':')
+                .map(|name| name.trim().to_ascii_lowercase())
+                .filter(|name| !name.is_empty())
+                .collect(),
+            None => Vec::new(),
+        };
+        if is_dkim && !headers.iter().any(|name| name == "from") {
+            return Err(error("From header isn't signed"));
+        }
+
+        Ok(Self {
+            algorithm,
+            selector: tag("s")?.clone(),
+            domain,
+            identity,
+            header_canonicalization: canonicalization(&header_canonicalization)?,
+            body_canonicalization: canonicalization(&body_canonicalization)?,
+            // Only ARC-Seals don't hash the body:
+            body_hash: match is_dkim || tags.contains_key("bh") {
+                true => base64("bh")?,
+                false => Vec::new(),
+            },
+            body_length: match tags.get("l") {
+                Some(l) => Some(l.parse().map_err(|_| error("invalid l= tag"))?),
+                None => None,
+            },
+            headers,
+            signature: base64("b")?,
+        })
+    }
+
+    /// Verifies the body hash and the signature of the signed headers.
+    fn verify(
+        &self,
+        header: &Header,
+        headers: &[Header],
+        body: &[u8],
+        resolver: &impl DkimKeyResolver,
+    ) -> DkimResult {
+        let mut body = self.body_canonicalization.body(body);
+        if let Some(length) = self.body_length {
+            if length > body.len() {
+                return DkimResult::PermError("l= tag exceeds the body length".into());
+            }
+            body.truncate(length);
+        }
+        if Sha256::digest(&body).as_slice() != self.body_hash {
+            return DkimResult::Fail("body hash did not verify".into());
+        }
+
+        // Sign the last instance of each header not signed yet, bottom up:
+        let mut signed = Vec::new();
+        let mut used: BTreeMap<&str, usize> = BTreeMap::new();
+        for name in &self.headers {
+            let skip = used.entry(name).or_default();
+            let instance = headers
+                .iter()
+                .rev()
+                .filter(|header| header.is(name))
+                .nth(*skip);
+            *skip += 1;
+            if let Some(instance) = instance {
+                signed.extend(self.header_canonicalization.header(instance.raw));
+                signed.extend(b"\r\n");
+            }
+        }
+        signed.extend(self.header_canonicalization.header(&header.without_b()));
+        self.verify_data(&signed, resolver)
+    }
+
+    /// Verifies the signature of some canonicalized data with the key from
+    /// the resolver.
+    fn verify_data(&self, data: &[u8], resolver: &impl DkimKeyResolver) -> DkimResult {
+        let name = format!("{}._domainkey.{}", self.selector, self.domain);
+        let record = match resolver.resolve(&name) {
+            Ok(Some(record)) => record,
+            Ok(None) => return DkimResult::PermError(format!("no key for {}", name)),
+            Err(error) => return DkimResult::TempError(error.to_string()),
+        };
+        let key = match Key::parse(&record) {
+            Ok(key) => key,
+            Err(error) => return error,
+        };
+        if key.algorithm != self.algorithm {
+            return DkimResult::PermError("key type doesn't match the algorithm".into());
+        }
+        if key.strict
+            && let Some(ref identity) = self.identity
+            && !identity
+                .to_ascii_lowercase()
+                .ends_with(&format!("@{}", self.domain))
+        {
+            return DkimResult::PermError("key requires i= to be the d= domain".into());
+        }
+
+        let hash = Sha256::digest(data);
+        let valid = match self.algorithm {
+            Algorithm::RsaSha256 => {
+                use rsa::{
+                    Pkcs1v15Sign, RsaPublicKey, pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey,
+                    traits::PublicKeyParts,
+                };
+                let Ok(public_key) = RsaPublicKey::from_public_key_der(&key.data)
+                    .or_else(|_| RsaPublicKey::from_pkcs1_der(&key.data))
+                else {
+                    return DkimResult::PermError("invalid RSA key".into());
+                };
+                // See: https://datatracker.ietf.org/doc/html/rfc8301#section-3.2
+                if public_key.size() < 1024 / 8 {
+                    return DkimResult::PermError("RSA key is shorter than 1024 bits".into());
+                }
+                public_key
+                    .verify(Pkcs1v15Sign::new::<Sha256>(), &hash, &self.signature)
+                    .is_ok()
+            },
+            // See: https://datatracker.ietf.org/doc/html/rfc8463#section-3
+            Algorithm::Ed25519Sha256 => {
+                use ed25519_dalek::{Signature, VerifyingKey};
+                let Some(public_key) = key
+                    .data
+                    .as_slice()
+                    .try_into()
+                    .ok()
+                    .and_then(|bytes| VerifyingKey::from_bytes(bytes).ok())
+                else {
+                    return DkimResult::PermError("invalid Ed25519 key".into());
+                };
+                let Ok(signature) = Signature::from_slice(&self.signature) else {
+                    return DkimResult::Fail("invalid Ed25519 signature".into());
+                };
+                public_key.verify_strict(&hash, &signature).is_ok()
+            },
+        };
+        match valid {
+            true => DkimResult::Pass,
+            false => DkimResult::Fail("signature did not verify".into()),
+        }
+    }
+}
+
+/// A public key record.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc6376#section-3.6.1
+struct Key {
+    algorithm: Algorithm,
+    data: Vec<u8>,
+
+    /// Whether the `i=` tag must be in the `d=` domain itself rather than a
+    /// subdomain, i.e., the `t=s` flag.
+    strict: bool,
+}
+
+impl Key {
+    fn parse(record: &str) -> Result<Self, DkimResult> {
+        let error = |reason: &str| DkimResult::PermError(reason.into());
+        let tags: BTreeMap<&str, &str> = record
+            .split(';')
+            .filter_map(|tag| tag.split_once('='))
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .collect();
+        if tags.get("v").is_some_and(|v| *v != "DKIM1") {
+            return Err(error("unsupported key version"));
+        }
+        let algorithm = match tags.get("k").copied().unwrap_or("rsa") {
+            "rsa" => Algorithm::RsaSha256,
+            "ed25519" => Algorithm::Ed25519Sha256,
+            _ => return Err(error("unsupported key type")),
+        };
+        if let Some(h) = tags.get("h")
+            && !h.split(':').any(|hash| hash.trim() == "sha256")
+        {
+            return Err(error("key doesn't allow sha256"));
+        }
+        let data: String = tags
+            .get("p")
+            .ok_or_else(|| error("missing p= tag in key"))?
+            .split_whitespace()
+            .collect();
+        if data.is_empty() {
+            return Err(error("key was revoked"));
+        }
+        Ok(Self {
+            algorithm,
+            data: BASE64.decode(data).map_err(|_| error("invalid key data"))?,
+            strict: tags
+                .get("t")
+                .is_some_and(|t| t.split(':').any(|flag| flag.trim() == "s")),
+        })
+    }
+}
+
+/// Converts bare line feeds to CRLF, as messages stored on Unix systems
+/// usually lack the carriage returns they were signed with.
+fn crlf(raw: &[u8]) -> Cow<'_, [u8]> {
+    if !raw
+        .iter()
+        .enumerate()
+        .any(|(i, byte)| *byte == b'\n' && (i == 0 || raw[i - 1] != b'\r'))
+    {
+        return Cow::Borrowed(raw);
+    }
+    let mut output = Vec::with_capacity(raw.len() + raw.len() / 32);
+    for (i, byte) in raw.iter().enumerate() {
+        if *byte == b'\n' && (i == 0 || raw[i - 1] != b'\r') {
+            output.push(b'\r');
+        }
+        output.push(*byte);
+    }
+    Cow::Owned(output)
+}
+
+/// Splits a raw message into its header fields and body.
+fn split_message(raw: &[u8]) -> (Vec<Header<'_>>, &[u8]) {
+    let (header, body) = match raw.windows(4).position(|window| window == b"\r\n\r\n") {
+        Some(end) => (&raw[..end + 2], &raw[end + 4..]),
+        None if raw.starts_with(b"\r\n") => (&raw[..0], &raw[2..]),
+        None => (raw, &raw[raw.len()..]),
+    };
+    // Header fields with invalid UTF-8 can't be verified anyway:
+    let header = match core::str::from_utf8(header) {
+        Ok(header) => header,
+        Err(error) => core::str::from_utf8(&header[..error.valid_up_to()]).unwrap_or_default(),
+    };
+    let mut headers = Vec::new();
+    let mut start = 0;
+    let lines: Vec<(usize, &str)> = header
+        .split_inclusive("\r\n")
+        .scan(0, |offset, line| {
+            let output = (*offset, line);
+            *offset += line.len();
+            Some(output)
+        })
+        .collect();
+    for (i, (offset, _)) in lines.iter().enumerate() {
+        let is_last_line = lines
+            .get(i + 1)
+            .is_none_or(|(_, next)| !next.starts_with([' ', '\t']));
+        if !is_last_line {
+            continue;
+        }
+        let end = offset + lines[i].1.trim_end_matches("\r\n").len();
+        let raw = &header[start..end];
+        if let Some((name, _)) = raw.split_once(':') {
+            headers.push(Header {
+                name: name.trim_end(),
+                raw,
+            });
+        }
+        start = lines.get(i + 1).map_or(end, |(offset, _)| *offset);
+    }
+    (headers, body)
+}
+
+/// Removes the line breaks of folded header values.
+fn unfold(value: &str) -> String {
+    value.replace("\r\n", "")
+}
+
+/// Reduces each sequence of whitespace to a single space.
+fn compress_whitespace(input: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    for (i, byte) in input.iter().enumerate() {
+        match byte {
+            b' ' | b'\t' if i > 0 && matches!(input[i - 1], b' ' | b'\t') => {},
+            b' ' | b'\t' => output.push(b' '),
+            _ => output.push(*byte),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RFC8463: &str = include_str!("../../test/dkim/rfc8463.eml");
+    const ARC: &str = include_str!("../../test/dkim/arc.eml");
+
+    fn keys() -> DkimKeys {
+        DkimKeys::parse(include_str!("../../test/dkim/keys.txt"))
+    }
+
+    #[test]
+    fn test_verify_dkim() {
+        let verifications = verify_dkim(RFC8463.as_bytes(), &keys());
+        assert_eq!(verifications.len(), 2);
+        assert!(verifications.iter().all(|verification| {
+            verification.domain == "football.example.com" && verification.result.is_pass()
+        }));
+        assert_eq!(verifications[0].selector, "brisbane");
+        assert_eq!(
+            verifications[1].identity.as_deref(),
+            Some("@football.example.com")
+        );
+
+        // Signatures survive CRLF line endings and added trace headers, but
+        // not modifications:
+        let crlf = RFC8463.replace('\n', "\r\n");
+        assert!(verify_dkim(crlf.as_bytes(), &keys())[0].result.is_pass());
+        let tampered = RFC8463.replace("We lost", "We won");
+        assert_eq!(
+            verify_dkim(tampered.as_bytes(), &keys())[0].result,
+            DkimResult::Fail("body hash did not verify".into())
+        );
+        let forwarded = format!("Received: from mx.example.net\n{}", RFC8463);
+        assert!(
+            verify_dkim(forwarded.as_bytes(), &keys())[0]
+                .result
+                .is_pass()
+        );
+        let tampered = RFC8463.replace("Is dinner", "Was dinner");
+        assert_eq!(
+            verify_dkim(tampered.as_bytes(), &keys())[1].result,
+            DkimResult::Fail("signature did not verify".into())
+        );
+    }
+
+    #[test]
+    fn test_verify_dkim_key_errors() {
+        let verifications = verify_dkim(RFC8463.as_bytes(), &DkimKeys::default());
+        assert!(matches!(verifications[0].result, DkimResult::PermError(_)));
+
+        let mut revoked = keys();
+        revoked.insert(
+            "brisbane._domainkey.football.example.com",
+            "v=DKIM1; k=ed25519; p=",
+        );
+        assert_eq!(
+            verify_dkim(RFC8463.as_bytes(), &revoked)[0].result,
+            DkimResult::PermError("key was revoked".into())
+        );
+
+        let offline = |_: &str| -> Result<Option<String>, crate::Error> {
+            Err(crate::Error::UnexpectedResponse("SERVFAIL".into()))
+        };
+        assert!(matches!(
+            verify_dkim(RFC8463.as_bytes(), &offline)[0].result,
+            DkimResult::TempError(_)
+        ));
+
+        let unhashed =
+            RFC8463.replacen(" bh=2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=;\n", "", 1);
+        assert_eq!(
+            verify_dkim(unhashed.as_bytes(), &keys())[0].result,
+            DkimResult::PermError("missing bh= tag".into())
+        );
+    }
+
+    #[test]
+    fn test_verify_arc() {
+        // The mailing list broke the DKIM signatures, but sealed the message:
+        assert!(
+            verify_dkim(ARC.as_bytes(), &keys())
+                .iter()
+                .all(|verification| !verification.result.is_pass())
+        );
+        let verification = verify_arc(ARC.as_bytes(), &keys());
+        assert_eq!(verification.result, Some(DkimResult::Pass));
+        assert_eq!(
+            verification.sealers,
+            ["lists.example.org", "mx.example.net"]
+        );
+
+        let tampered = ARC.replace("dkim=pass", "dkim=none");
+        assert_eq!(
+            verify_arc(tampered.as_bytes(), &keys()).result,
+            Some(DkimResult::Fail("signature did not verify".into()))
+        );
+        let tampered = ARC.replace("cv=none", "cv=pass");
+        assert_eq!(
+            verify_arc(tampered.as_bytes(), &keys()).result,
+            Some(DkimResult::Fail("invalid ARC-Seal cv= tag".into()))
+        );
+        assert_eq!(verify_arc(RFC8463.as_bytes(), &keys()).result, None);
+    }
+
+    #[test]
+    fn test_parse_keys() {
+        let keys = keys();
+        assert_eq!(
+            keys.resolve("ARC._domainkey.lists.example.org")
+                .unwrap()
+                .as_deref(),
+            Some("v=DKIM1; k=ed25519; p=11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=")
+        );
+        assert_eq!(keys.resolve("arc._domainkey.example.org").unwrap(), None);
+    }
+}
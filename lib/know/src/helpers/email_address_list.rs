@@ -1,29 +1,115 @@
 // This is free and unencumbered software released into the public domain.
 
 use crate::datatypes::EmailAddress;
+use alloc::collections::BTreeMap;
 
 #[derive(Debug, Clone, Default, Eq, Hash, PartialEq, PartialOrd, Ord)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct EmailAddressList(Vec<EmailAddress>);
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct EmailAddressList {
+    addresses: Vec<EmailAddress>,
+
+    #[cfg_attr(feature = "serde", serde(skip))]
+    names: BTreeMap<EmailAddress, String>,
+}
 
 impl EmailAddressList {
     pub fn into_vec(self) -> Vec<EmailAddress> {
-        self.0
+        self.addresses
+    }
+
+    /// Returns the display names of those addresses that have one.
+    pub fn names(&self) -> &BTreeMap<EmailAddress, String> {
+        &self.names
+    }
+
+    #[allow(unused)]
+    fn push(&mut self, address: EmailAddress, name: Option<&str>) {
+        if let Some(name) = name.map(str::trim).filter(|name| !name.is_empty()) {
+            self.names.insert(address.clone(), name.into());
+        }
+        self.addresses.push(address);
     }
 }
 
 #[cfg(feature = "mail-parser")]
 impl TryFrom<&mail_parser::Address<'_>> for EmailAddressList {
-    type Error = ();
+    type Error = crate::Error;
 
     fn try_from(input: &mail_parser::Address) -> Result<Self, Self::Error> {
         use mail_parser::Address::*;
-        match input {
-            List(addrs) => addrs.iter().try_fold(Self::default(), |mut output, addr| {
-                output.0.push(EmailAddress::try_from(addr)?);
-                Ok(output)
-            }),
-            Group(_groups) => todo!(), // TODO
+        let addrs: Vec<&mail_parser::Addr> = match input {
+            List(addrs) => addrs.iter().collect(),
+            Group(groups) => groups.iter().flat_map(|group| &group.addresses).collect(),
+        };
+        // Skip invalid addresses rather than losing the whole list:
+        let mut output = Self::default();
+        for addr in addrs {
+            if let Ok(address) = EmailAddress::try_from(addr) {
+                output.push(address, addr.name.as_deref());
+            }
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(feature = "mailparse")]
+impl TryFrom<&mailparse::MailHeader<'_>> for EmailAddressList {
+    type Error = crate::Error;
+
+    fn try_from(input: &mailparse::MailHeader) -> Result<Self, Self::Error> {
+        use mailparse::MailAddr;
+        let mut output = Self::default();
+        for addr in mailparse::addrparse_header(input)?.iter() {
+            let singles = match addr {
+                MailAddr::Single(single) => core::slice::from_ref(single),
+                MailAddr::Group(group) => group.addrs.as_slice(),
+            };
+            // Skip invalid addresses rather than losing the whole list:
+            for single in singles {
+                if let Ok(address) = single.addr.parse() {
+                    output.push(address, single.display_name.as_deref());
+                }
+            }
         }
+        Ok(output)
+    }
+}
+
+#[cfg(all(test, any(feature = "mail-parser", feature = "mailparse")))]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "mail-parser")]
+    #[test]
+    fn test_skip_invalid_mail_parser() {
+        let message = mail_parser::MessageParser::default()
+            .parse(b"To: Alice <a@example.org>, b@-bad.org, c@example.org\r\n\r\n")
+            .unwrap();
+        let list = EmailAddressList::try_from(message.to().unwrap()).unwrap();
+        assert_eq!(list.names().len(), 1);
+        assert_eq!(
+            list.into_vec(),
+            [
+                "a@example.org".parse().unwrap(),
+                "c@example.org".parse().unwrap()
+            ]
+        );
+    }
+
+    #[cfg(feature = "mailparse")]
+    #[test]
+    fn test_skip_invalid_mailparse() {
+        let (header, _) =
+            mailparse::parse_header(b"To: Alice <a@example.org>, b@-bad.org, c@example.org")
+                .unwrap();
+        let list = EmailAddressList::try_from(&header).unwrap();
+        assert_eq!(
+            list.into_vec(),
+            [
+                "a@example.org".parse().unwrap(),
+                "c@example.org".parse().unwrap()
+            ]
+        );
     }
 }
@@ -0,0 +1,256 @@
+// This is free and unencumbered software released into the public domain.
+
+use alloc::{string::String, vec::Vec};
+
+/// The parts of a plain-text email body: the new text written by the
+/// sender, the quoted messages it replies to, and the sender's signature.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct EmailBody {
+    /// The new text, without quotes and signature. Text interleaved with
+    /// quotes is joined into paragraphs.
+    pub text: String,
+
+    /// The quoted blocks, in order.
+    pub quotes: Vec<EmailQuote>,
+
+    /// The signature block, without the `-- ` delimiter.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc3676#section-4.3
+    pub signature: Option<String>,
+}
+
+/// A block of quoted text in an email body.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct EmailQuote {
+    /// The line introducing the quote, e.g., `On Mon, 7 Jul 2025, Alice
+    /// wrote:`, or the header block of an Outlook-style quote.
+    pub attribution: Option<String>,
+
+    /// The quoted text, with one level of `>` markers removed.
+    pub text: String,
+}
+
+impl EmailBody {
+    /// Splits a plain-text body into its parts.
+    ///
+    /// Recognizes `>`-prefixed quotes, attribution lines such as `On ...
+    /// wrote:`, Outlook-style `-----Original Message-----` separators and
+    /// header blocks, and `-- ` signature delimiters.
+    pub fn parse(input: &str) -> Self {
+        let lines: Vec<&str> = input.lines().collect();
+        let mut output = Self::default();
+        let mut paragraphs: Vec<String> = Vec::new();
+        let mut text: Vec<&str> = Vec::new();
+        let mut signature: Option<Vec<&str>> = None;
+
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i];
+
+            if let Some(quote) = outlook_quote(&lines[i..]) {
+                output.quotes.push(quote);
+                break;
+            }
+
+            let (attribution, start) = match attribution_len(&lines[i..]) {
+                Some(len) => (Some(lines[i..i + len].join(" ")), i + len),
+                None if is_quoted(line) => (None, i),
+                None => {
+                    match (&mut signature, line) {
+                        (None, "-- " | "--") => signature = Some(Vec::new()),
+                        (Some(signature), line) => signature.push(line),
+                        (None, line) => text.push(line),
+                    }
+                    i += 1;
+                    continue;
+                },
+            };
+
+            // A quote ends the current paragraph and signature:
+            paragraphs.extend(trim_lines(&text));
+            text.clear();
+            if let Some(lines) = signature.take() {
+                output.signature = output.signature.or(trim_lines(&lines));
+            }
+
+            let mut end = start;
+            while end < lines.len() && (is_quoted(lines[end]) || lines[end].trim().is_empty()) {
+                end += 1;
+            }
+            // Keep any trailing blank lines outside of the quote:
+            while end > start && lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+            // An attribution without `>` markers quotes the rest of the body:
+            if end == start {
+                end = lines.len();
+            }
+            let quoted: Vec<&str> = lines[start..end].iter().map(|line| unquote(line)).collect();
+            output.quotes.push(EmailQuote {
+                attribution,
+                text: trim_lines(&quoted).unwrap_or_default(),
+            });
+            i = end;
+        }
+
+        paragraphs.extend(trim_lines(&text));
+        if let Some(lines) = signature {
+            output.signature = output.signature.or(trim_lines(&lines));
+        }
+        output.text = paragraphs.join("\n\n");
+        output
+    }
+}
+
+impl From<&str> for EmailBody {
+    fn from(input: &str) -> Self {
+        Self::parse(input)
+    }
+}
+
+/// Returns the number of lines of an attribution such as `On Mon, 7 Jul
+/// 2025, Alice <alice@example.org> wrote:`, which clients may wrap.
+fn attribution_len(lines: &[&str]) -> Option<usize> {
+    const PREFIXES: [&str; 3] = ["On ", "Am ", "Le "];
+    const SUFFIXES: [&str; 4] = ["wrote:", "schrieb:", "a écrit :", "a écrit:"];
+    let is_attribution = |line: &str| SUFFIXES.iter().any(|suffix| line.ends_with(suffix));
+    let is_introduced = |line: &str| PREFIXES.iter().any(|prefix| line.starts_with(prefix));
+    let next_is_quoted = |rest: &[&str]| {
+        rest.iter()
+            .find(|line| !line.trim().is_empty())
+            .is_some_and(|line| is_quoted(line))
+    };
+    let first = lines.first()?.trim_end();
+    if is_quoted(first) {
+        return None;
+    }
+    if is_attribution(first) && (is_introduced(first) || next_is_quoted(&lines[1..])) {
+        return Some(1);
+    }
+    let second = lines.get(1)?.trim_end();
+    if is_introduced(first) && is_attribution(second) && !is_quoted(second) {
+        return Some(2);
+    }
+    None
+}
+
+/// Parses an Outlook-style quote, which quotes the rest of the body without
+/// `>` markers, under a separator line or a block of message headers.
+fn outlook_quote(lines: &[&str]) -> Option<EmailQuote> {
+    const HEADERS: [&str; 5] = ["From:", "Sent:", "Date:", "To:", "Subject:"];
+    let is_header = |line: &&str| HEADERS.iter().any(|header| line.starts_with(header));
+    let first = lines.first()?.trim();
+    let is_separator = (first.starts_with("-----")
+        && first.ends_with("-----")
+        && !first.trim_matches('-').trim().is_empty())
+        || (first.len() >= 20 && first.chars().all(|c| c == '_'));
+    let rest = match is_separator {
+        true => &lines[1..],
+        false if first.starts_with("From:") => lines,
+        false => return None,
+    };
+    let headers = rest.iter().take_while(|line| is_header(line)).count();
+    if !is_separator && headers < 3 {
+        return None;
+    }
+    if !is_separator
+        && !rest[..headers]
+            .iter()
+            .any(|line| line.starts_with("Sent:") || line.starts_with("Date:"))
+    {
+        return None;
+    }
+    let mut attribution: Vec<&str> = Vec::new();
+    if is_separator {
+        attribution.push(first);
+    }
+    attribution.extend(&rest[..headers]);
+    Some(EmailQuote {
+        attribution: Some(attribution.join("\n")),
+        text: trim_lines(&rest[headers..]).unwrap_or_default(),
+    })
+}
+
+fn is_quoted(line: &str) -> bool {
+    line.starts_with('>')
+}
+
+fn unquote(line: &str) -> &str {
+    match line.strip_prefix('>') {
+        Some(line) => line.strip_prefix(' ').unwrap_or(line),
+        None => line,
+    }
+}
+
+/// Joins lines, dropping leading and trailing blank lines, or returns
+/// `None` if all lines are blank.
+fn trim_lines(lines: &[&str]) -> Option<String> {
+    let start = lines.iter().position(|line| !line.trim().is_empty())?;
+    let end = lines.iter().rposition(|line| !line.trim().is_empty())?;
+    Some(lines[start..=end].join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bottom_posted_reply() {
+        let body = EmailBody::parse(
+            "On Mon, 7 Jul 2025 at 12:00, Alice <alice@example.org>\nwrote:\n> Lunch tomorrow?\n>\n> > Earlier\n\nSure, noon works.\n\n> Where?\n\nThe usual place.\n-- \nBob\nBuilder Inc.\n",
+        );
+        assert_eq!(body.text, "Sure, noon works.\n\nThe usual place.");
+        assert_eq!(body.quotes.len(), 2);
+        assert_eq!(
+            body.quotes[0].attribution.as_deref(),
+            Some("On Mon, 7 Jul 2025 at 12:00, Alice <alice@example.org> wrote:")
+        );
+        assert_eq!(body.quotes[0].text, "Lunch tomorrow?\n\n> Earlier");
+        assert_eq!(body.quotes[1].attribution, None);
+        assert_eq!(body.quotes[1].text, "Where?");
+        assert_eq!(body.signature.as_deref(), Some("Bob\nBuilder Inc."));
+    }
+
+    #[test]
+    fn test_top_posted_reply() {
+        let body = EmailBody::parse(
+            "Thanks!\n\n-- \nBob\n\nAlice wrote:\n\n> Here you go.\n> -- \n> Alice\n",
+        );
+        assert_eq!(body.text, "Thanks!");
+        assert_eq!(body.signature.as_deref(), Some("Bob"));
+        assert_eq!(body.quotes.len(), 1);
+        assert_eq!(body.quotes[0].attribution.as_deref(), Some("Alice wrote:"));
+        assert_eq!(body.quotes[0].text, "Here you go.\n-- \nAlice");
+    }
+
+    #[test]
+    fn test_outlook_reply() {
+        let body = EmailBody::parse(
+            "See below.\r\n\r\n-----Original Message-----\r\nFrom: Alice\r\nSent: Monday, July 7, 2025 12:00 PM\r\nTo: Bob\r\nSubject: Lunch\r\n\r\nLunch tomorrow?\r\n",
+        );
+        assert_eq!(body.text, "See below.");
+        assert_eq!(body.quotes.len(), 1);
+        assert!(
+            body.quotes[0]
+                .attribution
+                .as_ref()
+                .unwrap()
+                .ends_with("Subject: Lunch")
+        );
+        assert_eq!(body.quotes[0].text, "Lunch tomorrow?");
+
+        let body = EmailBody::parse(
+            "OK\n\nFrom: Alice\nSent: Monday\nTo: Bob\nSubject: Lunch\n\nLunch tomorrow?",
+        );
+        assert_eq!(body.text, "OK");
+        assert_eq!(body.quotes[0].text, "Lunch tomorrow?");
+    }
+
+    #[test]
+    fn test_unquoted_body() {
+        let body = EmailBody::parse("From: the desk of Bob\n\nOn second thought, no.");
+        assert_eq!(body.text, "From: the desk of Bob\n\nOn second thought, no.");
+        assert!(body.quotes.is_empty());
+        assert_eq!(body.signature, None);
+    }
+}
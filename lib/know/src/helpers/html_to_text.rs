@@ -0,0 +1,575 @@
+// This is free and unencumbered software released into the public domain.
+
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+
+/// Renders an HTML email body as plain text.
+///
+/// Links are numbered and listed as footnotes, lists are bulleted or
+/// numbered, table rows become lines with cells separated by `|`, and
+/// blockquotes are prefixed with `>`. Scripts, styles, hidden elements, and
+/// tracking pixels are dropped.
+pub fn html_to_text(input: &str) -> String {
+    let mut renderer = Renderer::default();
+    let mut rest = input;
+    while let Some(start) = rest.find('<') {
+        renderer.text(&decode_entities(&rest[..start]));
+        rest = &rest[start..];
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.split_once("-->").map_or("", |(_, rest)| rest);
+            continue;
+        }
+        let Some(tag) = Tag::parse(rest) else {
+            // A stray `<` is text:
+            renderer.text("<");
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[tag.len..];
+        if !tag.is_end && matches!(tag.name.as_str(), "script" | "style") {
+            // Raw text elements can contain anything but their end tag:
+            let end = find_ignore_ascii_case(rest, &format!("</{}", tag.name));
+            rest = &rest[end.unwrap_or(rest.len())..];
+            continue;
+        }
+        renderer.tag(&tag);
+    }
+    renderer.text(&decode_entities(rest));
+    renderer.finish()
+}
+
+/// An HTML start or end tag.
+#[derive(Debug, Default)]
+struct Tag {
+    name: String,
+    attrs: Vec<(String, String)>,
+    is_end: bool,
+    len: usize,
+}
+
+impl Tag {
+    /// Parses a tag at the start of the input, including declarations such
+    /// as `<!DOCTYPE html>`, which are returned without a name.
+    fn parse(input: &str) -> Option<Self> {
+        let bytes = input.as_bytes();
+        let mut output = Self::default();
+        let mut i = 1;
+        match bytes.get(i)? {
+            b'/' => {
+                output.is_end = true;
+                i += 1;
+            },
+            b'!' | b'?' => {
+                output.len = input.find('>')? + 1;
+                return Some(output);
+            },
+            c if c.is_ascii_alphabetic() => {},
+            _ => return None,
+        }
+        let name_len = input[i..]
+            .find(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>')
+            .unwrap_or(input.len() - i);
+        output.name = input[i..i + name_len].to_ascii_lowercase();
+        i += name_len;
+
+        loop {
+            while bytes
+                .get(i)
+                .is_some_and(|c| c.is_ascii_whitespace() || *c == b'/')
+            {
+                i += 1;
+            }
+            match bytes.get(i)? {
+                b'>' => {
+                    output.len = i + 1;
+                    return Some(output);
+                },
+                _ => {
+                    let len = input[i..]
+                        .find(|c: char| c.is_ascii_whitespace() || "/>=".contains(c))
+                        .unwrap_or(input.len() - i);
+                    let name = input[i..i + len].to_ascii_lowercase();
+                    i += len;
+                    while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                        i += 1;
+                    }
+                    let mut value = String::new();
+                    if bytes.get(i) == Some(&b'=') {
+                        i += 1;
+                        while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                            i += 1;
+                        }
+                        let (start, end) = match bytes.get(i)? {
+                            quote @ (b'"' | b'\'') => {
+                                let end = input[i + 1..].find(*quote as char)? + i + 1;
+                                (i + 1, end + 1)
+                            },
+                            _ => {
+                                let len = input[i..]
+                                    .find(|c: char| c.is_ascii_whitespace() || c == '>')
+                                    .unwrap_or(input.len() - i);
+                                (i, i + len)
+                            },
+                        };
+                        value = decode_entities(input[start..end].trim_end_matches(['"', '\'']))
+                            .into_owned();
+                        i = end;
+                    }
+                    output.attrs.push((name, value));
+                },
+            }
+        }
+    }
+
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn is_void(&self) -> bool {
+        matches!(
+            self.name.as_str(),
+            "area"
+                | "base"
+                | "br"
+                | "col"
+                | "embed"
+                | "hr"
+                | "img"
+                | "input"
+                | "link"
+                | "meta"
+                | "source"
+                | "track"
+                | "wbr"
+        )
+    }
+
+    /// Whether the element is hidden, e.g., an email preheader.
+    fn is_hidden(&self) -> bool {
+        let style: String = self
+            .attr("style")
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        self.attr("hidden").is_some()
+            || style.contains("display:none")
+            || style.contains("visibility:hidden")
+    }
+
+    /// Whether the element is an image of at most one pixel, which is only
+    /// there to track whether the message was opened.
+    fn is_tracking_pixel(&self) -> bool {
+        let is_tiny = |name: &str| {
+            self.attr(name).is_some_and(|value| {
+                value
+                    .trim_end_matches("px")
+                    .trim()
+                    .parse::<u32>()
+                    .is_ok_and(|size| size <= 1)
+            })
+        };
+        is_tiny("width") || is_tiny("height")
+    }
+}
+
+#[derive(Debug, Default)]
+struct Renderer {
+    output: String,
+    line: String,
+    bullet: Option<String>,
+    needs_space: bool,
+    /// The quote depth of a pending blank line between blocks.
+    blank_line: Option<usize>,
+    quote_depth: usize,
+    pre_depth: usize,
+    lists: Vec<Option<usize>>,
+    skipped: Option<(String, usize)>,
+    link: Option<(String, String)>,
+    links: Vec<String>,
+}
+
+impl Renderer {
+    fn tag(&mut self, tag: &Tag) {
+        if let Some((ref name, ref mut depth)) = self.skipped {
+            if *name == tag.name && !tag.is_void() {
+                match tag.is_end {
+                    true => *depth -= 1,
+                    false => *depth += 1,
+                }
+                if *depth == 0 {
+                    self.skipped = None;
+                }
+            }
+            return;
+        }
+        if tag.name.is_empty() {
+            return;
+        }
+        if !tag.is_end
+            && (matches!(tag.name.as_str(), "head" | "template" | "noscript") || tag.is_hidden())
+        {
+            if !tag.is_void() {
+                self.skipped = Some((tag.name.clone(), 1));
+            }
+            return;
+        }
+
+        match (tag.name.as_str(), tag.is_end) {
+            ("br", _) => {
+                self.end_line();
+                if self.pre_depth > 0 {
+                    self.needs_space = false;
+                }
+            },
+            ("hr", _) => {
+                self.block(true);
+                self.line.push_str("---");
+                self.block(true);
+            },
+            ("img", false) if !tag.is_tracking_pixel() => {
+                if let Some(alt) = tag.attr("alt").filter(|alt| !alt.trim().is_empty()) {
+                    self.text(alt);
+                }
+            },
+            ("a", false) => {
+                self.link = tag
+                    .attr("href")
+                    .map(|href| (href.trim().into(), String::new()));
+            },
+            ("a", true) => {
+                if let Some((href, text)) = self.link.take() {
+                    self.add_link(href, &text);
+                }
+            },
+            ("blockquote", is_end) => {
+                self.block(true);
+                match is_end {
+                    true => self.quote_depth = self.quote_depth.saturating_sub(1),
+                    false => self.quote_depth += 1,
+                }
+            },
+            ("pre", is_end) => {
+                self.block(true);
+                match is_end {
+                    true => self.pre_depth = self.pre_depth.saturating_sub(1),
+                    false => self.pre_depth += 1,
+                }
+            },
+            ("ul" | "ol", is_end) => {
+                self.block(self.lists.is_empty());
+                match is_end {
+                    true => _ = self.lists.pop(),
+                    false if tag.name == "ol" => {
+                        let start = tag.attr("start").and_then(|start| start.parse().ok());
+                        self.lists.push(Some(start.unwrap_or(1)))
+                    },
+                    false => self.lists.push(None),
+                }
+            },
+            ("li", false) => {
+                self.block(false);
+                let depth = self.lists.len().max(1);
+                let indent = "  ".repeat(depth - 1);
+                self.bullet = Some(match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}{}. ", indent, *number - 1)
+                    },
+                    _ => format!("{}- ", indent),
+                });
+            },
+            ("td" | "th", false) if !self.line.trim().is_empty() => {
+                self.line.push_str(" | ");
+                self.needs_space = false;
+            },
+            ("p" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "table" | "dl" | "figure", _) => {
+                self.block(true)
+            },
+            (
+                "div" | "tr" | "li" | "dt" | "dd" | "section" | "article" | "header" | "footer"
+                | "center" | "address" | "caption" | "tbody" | "thead" | "tfoot",
+                _,
+            ) => self.block(false),
+            _ => {},
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if self.skipped.is_some() {
+            return;
+        }
+        if let Some((_, ref mut link_text)) = self.link {
+            link_text.push_str(text);
+        }
+        if self.pre_depth > 0 {
+            for (i, line) in text.split('\n').enumerate() {
+                if i > 0 {
+                    self.end_line();
+                }
+                if !line.is_empty() {
+                    self.start_line();
+                    self.line.push_str(line.trim_end_matches('\r'));
+                }
+            }
+            return;
+        }
+        for c in text.chars() {
+            // Drop invisible characters, which pad email preheaders:
+            if matches!(
+                c,
+                '\u{ad}' | '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{feff}'
+            ) {
+                continue;
+            }
+            if c.is_whitespace() && c != '\u{a0}' {
+                self.needs_space = true;
+                continue;
+            }
+            self.start_line();
+            if self.needs_space && !self.line.is_empty() && !self.line.ends_with(' ') {
+                self.line.push(' ');
+            }
+            self.needs_space = false;
+            self.line.push(match c {
+                '\u{a0}' => ' ',
+                c => c,
+            });
+        }
+    }
+
+    fn add_link(&mut self, href: String, text: &str) {
+        let lowercase = href.to_ascii_lowercase();
+        let is_external = ["http://", "https://", "ftp://"]
+            .iter()
+            .any(|scheme| lowercase.starts_with(scheme));
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let bare = href
+            .split_once("://")
+            .map_or(href.as_str(), |(_, rest)| rest);
+        if !is_external || text == href || text == bare.trim_end_matches('/') {
+            return;
+        }
+        let number = match self.links.iter().position(|link| *link == href) {
+            Some(i) => i + 1,
+            None => {
+                self.links.push(href);
+                self.links.len()
+            },
+        };
+        self.start_line();
+        self.line.push_str(&format!("[{}]", number));
+    }
+
+    /// Emits the blank line pending before new content, quoted as deep as
+    /// the shallower of the blocks it separates.
+    fn start_line(&mut self) {
+        if !self.line.is_empty() {
+            return;
+        }
+        if let Some(depth) = self.blank_line.take()
+            && !self.output.is_empty()
+        {
+            let prefix = "> ".repeat(depth.min(self.quote_depth));
+            self.output.push_str(prefix.trim_end());
+            self.output.push('\n');
+        }
+    }
+
+    /// Ends the current line, if it has any content.
+    fn end_line(&mut self) {
+        let line = self.line.trim_end();
+        if line.is_empty() && self.bullet.is_none() {
+            self.line.clear();
+            return;
+        }
+        let prefix = "> ".repeat(self.quote_depth);
+        let indent = match self.bullet.take() {
+            Some(bullet) => bullet,
+            None => "  ".repeat(self.lists.len()),
+        };
+        let line = format!("{}{}{}", prefix, indent, line);
+        self.output.push_str(line.trim_end());
+        self.output.push('\n');
+        self.line.clear();
+        self.needs_space = false;
+    }
+
+    /// Ends the current block, separating it from the next one by a blank
+    /// line if `is_paragraph`.
+    fn block(&mut self, is_paragraph: bool) {
+        if !self.line.trim().is_empty() {
+            self.end_line();
+        }
+        self.needs_space = false;
+        if is_paragraph {
+            let depth = self.blank_line.unwrap_or(self.quote_depth);
+            self.blank_line = Some(depth.min(self.quote_depth));
+        }
+    }
+
+    fn finish(mut self) -> String {
+        if let Some((href, text)) = self.link.take() {
+            self.add_link(href, &text);
+        }
+        self.end_line();
+        let mut output = self.output.trim_end().to_string();
+        if !self.links.is_empty() {
+            output.push_str("\n\n");
+            for (i, link) in self.links.iter().enumerate() {
+                output.push_str(&format!("[{}] {}\n", i + 1, link));
+            }
+            output.truncate(output.trim_end().len());
+        }
+        // Collapse runs of blank lines left by empty blocks:
+        let mut collapsed = String::with_capacity(output.len());
+        let mut blank_lines = 0;
+        for line in output.trim_start_matches('\n').lines() {
+            let is_blank = line.trim_start_matches('>').trim().is_empty();
+            blank_lines = if is_blank { blank_lines + 1 } else { 0 };
+            if blank_lines <= 1 {
+                collapsed.push_str(line);
+                collapsed.push('\n');
+            }
+        }
+        collapsed.truncate(collapsed.trim_end().len());
+        collapsed
+    }
+}
+
+/// Decodes character references, e.g., `&amp;` and `&#8212;`.
+///
+/// See: https://html.spec.whatwg.org/multipage/syntax.html#character-references
+fn decode_entities(input: &str) -> Cow<'_, str> {
+    if !input.contains('&') {
+        return Cow::Borrowed(input);
+    }
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('&') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..end + 1])?, end + 2)));
+        match decoded {
+            Some((c, len)) => {
+                output.push(c);
+                rest = &rest[len..];
+            },
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    output.push_str(rest);
+    Cow::Owned(output)
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "shy" => '\u{ad}',
+        "zwnj" => '\u{200c}',
+        "zwj" => '\u{200d}',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "laquo" => '«',
+        "raquo" => '»',
+        "bull" => '•',
+        "middot" => '·',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "euro" => '€',
+        "pound" => '£',
+        "yen" => '¥',
+        "deg" => '°',
+        "times" => '×',
+        _ => return None,
+    })
+}
+
+fn find_ignore_ascii_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paragraphs_and_links() {
+        let html = r#"<!DOCTYPE html><html><head><title>Newsletter</title>
+            <style>p { color: red; }</style></head>
+            <body><div style="display: none">Preheader text</div>
+            <p>Hello&nbsp;Bob,<br>see <a href="https://example.org/a">our
+            site</a> &amp; <a href="https://example.org/a">this</a>.</p>
+            <script>document.write("<p>x</p>")</script>
+            <p>Visit https://example.org/b or <a href="https://example.org/b">https://example.org/b</a>.</p>
+            <img src="https://t.example.org/open.gif" width="1" height="1" alt="">
+            <img src="logo.png" alt="Example Corp">
+            </body></html>"#;
+        assert_eq!(
+            html_to_text(html),
+            "Hello Bob,\nsee our site[1] & this[1].\n\nVisit https://example.org/b or https://example.org/b.\n\nExample Corp\n\n[1] https://example.org/a"
+        );
+    }
+
+    #[test]
+    fn test_lists_and_tables() {
+        let html = "<p>Agenda:</p><ol><li>Intro</li><li>Plans<ul><li>Q3</li><li>Q4</li></ul></li></ol>\
+            <table><tr><th>Name</th><th>Role</th></tr><tr><td>Alice</td><td>CTO</td></tr></table>";
+        assert_eq!(
+            html_to_text(html),
+            "Agenda:\n\n1. Intro\n2. Plans\n  - Q3\n  - Q4\n\nName | Role\nAlice | CTO"
+        );
+    }
+
+    #[test]
+    fn test_blockquotes_and_pre() {
+        let html = "<p>Yes.</p><blockquote><p>Lunch?</p><p>At &lt;noon&gt;</p></blockquote>\
+            <pre>  fn main() {\n      todo!()\n  }</pre>";
+        assert_eq!(
+            html_to_text(html),
+            "Yes.\n\n> Lunch?\n>\n> At <noon>\n\n  fn main() {\n      todo!()\n  }"
+        );
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(
+            decode_entities("a &lt; b &#8212; &#x263A; &bogus; & c"),
+            "a < b — ☺ &bogus; & c"
+        );
+    }
+}
@@ -0,0 +1,58 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{classes::EmailMessage, datatypes::DateTime};
+
+/// A message returned by an IMAP `FETCH` command, together with the
+/// mailbox attributes that aren't part of the message itself.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc9051#section-7.5.2
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct ImapFetch {
+    /// The message sequence number in the selected mailbox.
+    pub sequence_number: u32,
+
+    /// See: https://datatracker.ietf.org/doc/html/rfc9051#section-2.3.1.1
+    pub uid: Option<u32>,
+
+    /// The `INTERNALDATE`, i.e., when the server received the message.
+    pub internal_date: Option<DateTime>,
+
+    /// The `RFC822.SIZE` of the message, in octets.
+    pub size: Option<u32>,
+
+    pub message: EmailMessage,
+}
+
+impl TryFrom<&imap_proto::Response<'_>> for ImapFetch {
+    type Error = crate::Error;
+
+    fn try_from(input: &imap_proto::Response) -> Result<Self, Self::Error> {
+        use imap_proto::types::AttributeValue::*;
+        let imap_proto::Response::Fetch(sequence_number, attrs) = input else {
+            return Err(crate::Error::UnexpectedResponse(format!("{:?}", input)));
+        };
+        let mut output = Self {
+            sequence_number: *sequence_number,
+            message: attrs.as_slice().try_into()?,
+            ..Default::default()
+        };
+        for attr in attrs {
+            match attr {
+                Uid(uid) => output.uid = Some(*uid),
+                InternalDate(date) => output.internal_date = Some(parse_imap_date(date)?),
+                Rfc822Size(size) => output.size = Some(*size),
+                _ => {},
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Parses an IMAP `INTERNALDATE`, e.g., `17-Jul-1996 02:44:25 -0700`.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc9051#section-9
+pub(crate) fn parse_imap_date(input: &str) -> Result<DateTime, crate::Error> {
+    jiff::Zoned::strptime("%d-%b-%Y %H:%M:%S %z", input.trim())
+        .map(DateTime::from)
+        .map_err(|error| crate::Error::InvalidHeader("INTERNALDATE", error.to_string()))
+}
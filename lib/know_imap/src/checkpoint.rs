@@ -0,0 +1,124 @@
+// This is free and unencumbered software released into the public domain.
+
+use std::{collections::BTreeMap, fmt, fs, io, path::Path, str::FromStr};
+
+/// The sync state of a single mailbox.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc4549#section-4.1
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct MailboxCheckpoint {
+    /// The `UIDVALIDITY` of the mailbox when it was last synced.
+    pub uid_validity: u32,
+
+    /// The highest UID fetched so far.
+    pub last_uid: u32,
+}
+
+/// The sync state of all mailboxes, so that re-runs only fetch new
+/// messages.
+///
+/// Checkpoints are stored as text, one mailbox per line, with the
+/// `UIDVALIDITY`, the last UID, and the mailbox name separated by tabs.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Checkpoint {
+    mailboxes: BTreeMap<String, MailboxCheckpoint>,
+}
+
+impl Checkpoint {
+    /// Loads a checkpoint file, returning an empty checkpoint if the file
+    /// doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(input) => input
+                .parse()
+                .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Saves this checkpoint, replacing the file atomically.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let path = path.as_ref();
+        let tmp_path = path.with_extension("tmp");
+        fs::write(&tmp_path, self.to_string())?;
+        fs::rename(tmp_path, path)
+    }
+
+    pub fn get(&self, mailbox: &str) -> Option<&MailboxCheckpoint> {
+        self.mailboxes.get(mailbox)
+    }
+
+    pub fn set(&mut self, mailbox: &str, checkpoint: MailboxCheckpoint) {
+        self.mailboxes.insert(mailbox.into(), checkpoint);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &MailboxCheckpoint)> {
+        self.mailboxes
+            .iter()
+            .map(|(mailbox, checkpoint)| (mailbox.as_str(), checkpoint))
+    }
+}
+
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (mailbox, checkpoint) in &self.mailboxes {
+            writeln!(
+                f,
+                "{}\t{}\t{}",
+                checkpoint.uid_validity, checkpoint.last_uid, mailbox
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Checkpoint {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut output = Self::default();
+        for line in input.lines().filter(|line| !line.is_empty()) {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(uid_validity), Some(last_uid), Some(mailbox)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return Err(format!("invalid checkpoint line: {}", line));
+            };
+            let checkpoint = MailboxCheckpoint {
+                uid_validity: uid_validity.parse().map_err(|_| line.to_string())?,
+                last_uid: last_uid.parse().map_err(|_| line.to_string())?,
+            };
+            output.set(mailbox, checkpoint);
+        }
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_and_load() {
+        let path =
+            std::env::temp_dir().join(format!("know-imap-{}.checkpoint", std::process::id()));
+        assert_eq!(Checkpoint::load(&path).unwrap(), Checkpoint::default());
+
+        let mut checkpoint = Checkpoint::default();
+        let inbox = MailboxCheckpoint {
+            uid_validity: 1234,
+            last_uid: 42,
+        };
+        checkpoint.set("INBOX", inbox);
+        checkpoint.set("Sent Items", MailboxCheckpoint::default());
+        checkpoint.save(&path).unwrap();
+
+        let loaded = Checkpoint::load(&path).unwrap();
+        assert_eq!(loaded, checkpoint);
+        assert_eq!(loaded.get("INBOX"), Some(&inbox));
+        assert_eq!(loaded.iter().count(), 2);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,431 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::{Checkpoint, Error, MailboxCheckpoint};
+use imap_proto::{
+    AttributeValue, MailboxDatum, NameAttribute, RequestId, Response, ResponseCode, Status,
+};
+use know::helpers::ImapFetch;
+use std::io::{Read, Write};
+
+/// The maximum number of messages fetched by a single `UID FETCH` command.
+pub const FETCH_BATCH_SIZE: usize = 100;
+
+/// The result of fetching a single message. A message that can't be
+/// converted doesn't abort the sync, but is reported with its UID.
+pub type FetchResult = Result<ImapFetch, (u32, know::Error)>;
+
+/// The state of a mailbox, as reported by `SELECT`.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc9051#section-6.3.2
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Mailbox {
+    pub exists: u32,
+    pub uid_validity: Option<u32>,
+    pub uid_next: Option<u32>,
+}
+
+/// A blocking IMAP client for ingesting messages.
+///
+/// The client speaks IMAP over any stream, such as a TLS session over a
+/// TCP connection, or a [`FakeServer`](crate::fake::FakeServer)
+/// connection in tests.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc9051
+#[derive(Debug)]
+pub struct Client<S: Read + Write> {
+    stream: S,
+    buffer: Vec<u8>,
+    next_tag: u32,
+}
+
+impl<S: Read + Write> Client<S> {
+    /// Starts a session over the given stream, reading the server greeting.
+    pub fn new(stream: S) -> Result<Self, Error> {
+        let mut client = Self {
+            stream,
+            buffer: Vec::new(),
+            next_tag: 1,
+        };
+        match client.read_response()? {
+            Response::Data {
+                status: Status::Ok | Status::PreAuth,
+                ..
+            } => Ok(client),
+            response => Err(Error::Protocol(format!("{:?}", response))),
+        }
+    }
+
+    pub fn login(&mut self, username: &str, password: &str) -> Result<(), Error> {
+        self.run(&format!("LOGIN {} {}", quote(username)?, quote(password)?))?;
+        Ok(())
+    }
+
+    /// Returns the names of all selectable mailboxes.
+    pub fn list(&mut self) -> Result<Vec<String>, Error> {
+        Ok(self
+            .run("LIST \"\" \"*\"")?
+            .into_iter()
+            .filter_map(|response| match response {
+                Response::MailboxData(MailboxDatum::List {
+                    name_attributes,
+                    name,
+                    ..
+                }) if !name_attributes.contains(&NameAttribute::NoSelect) => Some(name.into()),
+                _ => None,
+            })
+            .collect())
+    }
+
+    pub fn select(&mut self, mailbox: &str) -> Result<Mailbox, Error> {
+        let mut output = Mailbox::default();
+        for response in self.run(&format!("SELECT {}", quote(mailbox)?))? {
+            match response {
+                Response::MailboxData(MailboxDatum::Exists(exists)) => output.exists = exists,
+                Response::Data {
+                    code: Some(ResponseCode::UidValidity(uid_validity)),
+                    ..
+                } => output.uid_validity = Some(uid_validity),
+                Response::Data {
+                    code: Some(ResponseCode::UidNext(uid_next)),
+                    ..
+                } => output.uid_next = Some(uid_next),
+                _ => {},
+            }
+        }
+        Ok(output)
+    }
+
+    /// Fetches the messages in the selected mailbox with a UID greater than
+    /// `last_uid`, in UID order.
+    ///
+    /// The UIDs are searched for first, and the messages then fetched in
+    /// batches of [`FETCH_BATCH_SIZE`], so that a large mailbox isn't
+    /// downloaded in a single response.
+    pub fn fetch_since(&mut self, last_uid: u32) -> Result<Vec<FetchResult>, Error> {
+        let command = format!("UID SEARCH UID {}:*", last_uid.saturating_add(1));
+        let mut uids: Vec<u32> = Vec::new();
+        for response in self.run(&command)? {
+            if let Response::MailboxData(MailboxDatum::Search(found)) = response {
+                uids.extend(found);
+            }
+        }
+        // `n:*` always matches the last message, even if its UID is
+        // lower than `n`:
+        uids.retain(|&uid| uid > last_uid);
+        uids.sort_unstable();
+        uids.dedup();
+
+        let mut output: Vec<FetchResult> = Vec::new();
+        for batch in uids.chunks(FETCH_BATCH_SIZE) {
+            let set: Vec<String> = batch.iter().map(ToString::to_string).collect();
+            let command = format!(
+                "UID FETCH {} (UID FLAGS INTERNALDATE RFC822.SIZE BODY.PEEK[])",
+                set.join(",")
+            );
+            for response in self.run(&command)? {
+                let Response::Fetch(_, ref attrs) = response else {
+                    continue;
+                };
+                let Some(uid) = attrs.iter().find_map(|attr| match attr {
+                    AttributeValue::Uid(uid) => Some(*uid),
+                    _ => None,
+                }) else {
+                    continue;
+                };
+                if !batch.contains(&uid) {
+                    continue;
+                }
+                output.push(ImapFetch::try_from(&response).map_err(|error| (uid, error)));
+            }
+        }
+        output.sort_by_key(|result| match result {
+            Ok(fetch) => fetch.uid.unwrap_or_default(),
+            Err((uid, _)) => *uid,
+        });
+        Ok(output)
+    }
+
+    /// Fetches the messages added to a mailbox since the checkpoint, and
+    /// advances the checkpoint past them.
+    ///
+    /// If the mailbox's `UIDVALIDITY` has changed, all messages are fetched
+    /// again.
+    pub fn sync(
+        &mut self,
+        mailbox: &str,
+        checkpoint: &mut Checkpoint,
+    ) -> Result<Vec<FetchResult>, Error> {
+        let uid_validity = self.select(mailbox)?.uid_validity.unwrap_or_default();
+        let mut state = match checkpoint.get(mailbox) {
+            Some(state) if state.uid_validity == uid_validity => *state,
+            _ => MailboxCheckpoint {
+                uid_validity,
+                last_uid: 0,
+            },
+        };
+        let output = self.fetch_since(state.last_uid)?;
+        for result in &output {
+            let uid = match result {
+                Ok(fetch) => fetch.uid.unwrap_or_default(),
+                Err((uid, _)) => *uid,
+            };
+            state.last_uid = state.last_uid.max(uid);
+        }
+        checkpoint.set(mailbox, state);
+        Ok(output)
+    }
+
+    /// Syncs every selectable mailbox, returning the results by mailbox.
+    pub fn sync_all(
+        &mut self,
+        checkpoint: &mut Checkpoint,
+    ) -> Result<Vec<(String, Vec<FetchResult>)>, Error> {
+        let mut output = Vec::new();
+        for mailbox in self.list()? {
+            let results = self.sync(&mailbox, checkpoint)?;
+            output.push((mailbox, results));
+        }
+        Ok(output)
+    }
+
+    pub fn logout(mut self) -> Result<(), Error> {
+        self.run("LOGOUT")?;
+        Ok(())
+    }
+
+    /// Sends a command and collects the untagged responses up to its
+    /// completion.
+    ///
+    /// A command containing literals is sent one line at a time, waiting
+    /// for the server's continuation request after each literal's size.
+    fn run(&mut self, command: &str) -> Result<Vec<Response<'static>>, Error> {
+        let tag = format!("A{:04}", self.next_tag);
+        self.next_tag += 1;
+        let mut output = Vec::new();
+        let mut lines = command.split("\r\n").peekable();
+        write!(self.stream, "{} ", tag)?;
+        while let Some(line) = lines.next() {
+            write!(self.stream, "{}\r\n", line)?;
+            self.stream.flush()?;
+            if lines.peek().is_none() {
+                break;
+            }
+            loop {
+                match self.read_response()? {
+                    Response::Continue { .. } => break,
+                    Response::Done {
+                        tag: RequestId(done_tag),
+                        information,
+                        ..
+                    } if done_tag == tag => {
+                        return Err(Error::Command(information.unwrap_or_default().into()));
+                    },
+                    response => output.push(response),
+                }
+            }
+        }
+
+        loop {
+            match self.read_response()? {
+                Response::Done {
+                    tag: RequestId(done_tag),
+                    status,
+                    information,
+                    ..
+                } if done_tag == tag => {
+                    return match status {
+                        Status::Ok => Ok(output),
+                        _ => Err(Error::Command(information.unwrap_or_default().into())),
+                    };
+                },
+                response => output.push(response),
+            }
+        }
+    }
+
+    fn read_response(&mut self) -> Result<Response<'static>, Error> {
+        loop {
+            if !self.buffer.is_empty() {
+                match Response::from_bytes(&self.buffer) {
+                    Ok((rest, response)) => {
+                        let consumed = self.buffer.len() - rest.len();
+                        let response = response.into_owned();
+                        self.buffer.drain(..consumed);
+                        return Ok(response);
+                    },
+                    Err(nom::Err::Incomplete(_)) => {},
+                    Err(_) => {
+                        let line = self
+                            .buffer
+                            .split(|&b| b == b'\n')
+                            .next()
+                            .unwrap_or_default();
+                        return Err(Error::Protocol(String::from_utf8_lossy(line).into()));
+                    },
+                }
+            }
+            let mut chunk = [0; 8192];
+            match self.stream.read(&mut chunk)? {
+                0 => Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?,
+                n => self.buffer.extend_from_slice(&chunk[..n]),
+            }
+        }
+    }
+}
+
+/// Formats a string as an IMAP quoted string, or as a literal if it isn't
+/// ASCII. CR, LF and NUL can't be sent either way.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc9051#section-4.3
+fn quote(input: &str) -> Result<String, Error> {
+    if input.contains(['\r', '\n', '\0']) {
+        return Err(Error::InvalidArgument);
+    }
+    if !input.is_ascii() {
+        return Ok(format!("{{{}}}\r\n{}", input.len(), input));
+    }
+    Ok(format!(
+        "\"{}\"",
+        input.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::FakeServer;
+
+    fn message(n: u32) -> String {
+        format!(
+            "Date: Wed, 1 Jan 2025 12:00:00 +0000\r\nFrom: alice@example.org\r\nSubject: Message {n}\r\nMessage-ID: <{n}@example.org>\r\n\r\nHello {n}\r\n"
+        )
+    }
+
+    fn session(server: &FakeServer) -> Client<crate::fake::FakeConnection> {
+        let mut client = Client::new(server.connect()).unwrap();
+        client.login("alice", "secret").unwrap();
+        client
+    }
+
+    fn subjects(results: &[FetchResult]) -> Vec<String> {
+        results
+            .iter()
+            .map(|result| result.as_ref().unwrap().message.subject.clone().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_login_failure() {
+        let server = FakeServer::new("alice", "secret");
+        let mut client = Client::new(server.connect()).unwrap();
+        assert!(matches!(
+            client.login("alice", "wrong"),
+            Err(Error::Command(_))
+        ));
+        assert!(matches!(client.list(), Err(Error::Command(_))));
+    }
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("a \"b\" \\c").unwrap(), "\"a \\\"b\\\" \\\\c\"");
+        assert_eq!(quote("Entwürfe").unwrap(), "{9}\r\nEntwürfe");
+        assert!(matches!(
+            quote("secret\r\nA0002 LOGOUT"),
+            Err(Error::InvalidArgument)
+        ));
+        assert!(matches!(quote("a\0b"), Err(Error::InvalidArgument)));
+    }
+
+    #[test]
+    fn test_list_and_select() {
+        let server = FakeServer::new("alice", "secret");
+        server.add_mailbox("Sent Items", 7);
+        server.append("Sent Items", &[], message(1));
+
+        let mut client = session(&server);
+        assert_eq!(client.list().unwrap(), ["INBOX", "Sent Items"]);
+        let mailbox = client.select("Sent Items").unwrap();
+        assert_eq!(mailbox.exists, 1);
+        assert_eq!(mailbox.uid_validity, Some(7));
+        assert_eq!(mailbox.uid_next, Some(2));
+        assert!(client.select("Missing").is_err());
+        assert!(client.select("Entwürfe").is_err());
+        server.add_mailbox("Entwürfe", 1);
+        assert_eq!(client.select("Entwürfe").unwrap().uid_validity, Some(1));
+        client.logout().unwrap();
+    }
+
+    #[test]
+    fn test_incremental_sync() {
+        let server = FakeServer::new("alice", "secret");
+        server.append("INBOX", &["\\Seen"], message(1));
+        server.append("INBOX", &[], message(2));
+        let mut checkpoint = Checkpoint::default();
+
+        let results = session(&server).sync("INBOX", &mut checkpoint).unwrap();
+        assert_eq!(subjects(&results), ["Message 1", "Message 2"]);
+        let first = results[0].as_ref().unwrap();
+        assert_eq!(first.uid, Some(1));
+        assert!(first.message.flags.seen);
+        assert_eq!(checkpoint.get("INBOX").unwrap().last_uid, 2);
+
+        // A re-run only fetches new messages:
+        let results = session(&server).sync("INBOX", &mut checkpoint).unwrap();
+        assert!(results.is_empty());
+        server.append("INBOX", &[], message(3));
+        let results = session(&server).sync("INBOX", &mut checkpoint).unwrap();
+        assert_eq!(subjects(&results), ["Message 3"]);
+        assert_eq!(checkpoint.get("INBOX").unwrap().last_uid, 3);
+
+        // A new UIDVALIDITY invalidates the checkpoint:
+        server.reset_uid_validity("INBOX", 2);
+        let results = session(&server).sync("INBOX", &mut checkpoint).unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(
+            checkpoint.get("INBOX"),
+            Some(&MailboxCheckpoint {
+                uid_validity: 2,
+                last_uid: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_fetch_in_batches() {
+        let server = FakeServer::new("alice", "secret");
+        let count = FETCH_BATCH_SIZE as u32 * 2 + 1;
+        for n in 1..=count {
+            server.append("INBOX", &[], message(n));
+        }
+        let mut client = session(&server);
+        client.select("INBOX").unwrap();
+        let results = client.fetch_since(1).unwrap();
+        assert_eq!(results.len(), count as usize - 1);
+        assert_eq!(results[0].as_ref().unwrap().uid, Some(2));
+        assert_eq!(results.last().unwrap().as_ref().unwrap().uid, Some(count));
+        assert!(client.fetch_since(count).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_bad_message_does_not_abort_sync() {
+        let server = FakeServer::new("alice", "secret");
+        server.add_mailbox("Archive", 1);
+        server.append("INBOX", &[], "Subject: No date\r\n\r\nHi\r\n");
+        server.append("INBOX", &[], message(2));
+        server.append("Archive", &[], message(3));
+        let mut checkpoint = Checkpoint::default();
+
+        let results = session(&server).sync_all(&mut checkpoint).unwrap();
+        assert_eq!(results.len(), 2);
+        let (ref archive, ref archived) = results[0];
+        assert_eq!(archive, "Archive");
+        assert_eq!(subjects(archived), ["Message 3"]);
+        let (_, ref inbox) = results[1];
+        assert!(matches!(
+            inbox[0],
+            Err((1, know::Error::MissingHeader("Date")))
+        ));
+        assert_eq!(subjects(&inbox[1..]), ["Message 2"]);
+        assert_eq!(checkpoint.get("INBOX").unwrap().last_uid, 2);
+    }
+}
@@ -0,0 +1,25 @@
+// This is free and unencumbered software released into the public domain.
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// The server answered a command with `NO` or `BAD`.
+    #[error("IMAP command failed: {0}")]
+    Command(String),
+
+    /// A command argument contains CR, LF or NUL, which IMAP can't
+    /// transmit.
+    #[error("IMAP command argument contains CR, LF or NUL")]
+    InvalidArgument,
+
+    /// The server sent something that isn't a valid IMAP response.
+    #[error("malformed IMAP response: {0}")]
+    Protocol(String),
+
+    #[error(transparent)]
+    Know(#[from] know::Error),
+}
@@ -0,0 +1,383 @@
+// This is free and unencumbered software released into the public domain.
+
+//! A scripted, in-process IMAP server for testing ingestion offline.
+//!
+//! The server implements just enough of IMAP4rev1 for [`Client`]: `LOGIN`,
+//! `LIST`, `SELECT`, `UID SEARCH`, `UID FETCH`, `NOOP`, and `LOGOUT`.
+//! Mailboxes and messages can be added between sessions to simulate new
+//! mail.
+//!
+//! [`Client`]: crate::Client
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, Read, Write},
+    ops::RangeInclusive,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+/// The `INTERNALDATE` reported for all messages.
+const INTERNAL_DATE: &str = "01-Jan-2025 00:00:00 +0000";
+
+/// The state of a fake IMAP server, shared by all its connections.
+#[derive(Clone, Debug, Default)]
+pub struct FakeServer(Arc<Mutex<State>>);
+
+#[derive(Debug, Default)]
+struct State {
+    username: String,
+    password: String,
+    mailboxes: BTreeMap<String, FakeMailbox>,
+}
+
+#[derive(Debug, Default)]
+struct FakeMailbox {
+    uid_validity: u32,
+    uid_next: u32,
+    messages: Vec<FakeMessage>,
+}
+
+#[derive(Debug)]
+struct FakeMessage {
+    uid: u32,
+    flags: Vec<String>,
+    data: Vec<u8>,
+}
+
+impl FakeServer {
+    /// Creates a server accepting the given credentials, with an empty
+    /// `INBOX`.
+    pub fn new(username: &str, password: &str) -> Self {
+        let server = Self(Arc::new(Mutex::new(State {
+            username: username.into(),
+            password: password.into(),
+            ..Default::default()
+        })));
+        server.add_mailbox("INBOX", 1);
+        server
+    }
+
+    pub fn add_mailbox(&self, name: &str, uid_validity: u32) {
+        self.state().mailboxes.insert(
+            name.into(),
+            FakeMailbox {
+                uid_validity,
+                uid_next: 1,
+                messages: Vec::new(),
+            },
+        );
+    }
+
+    /// Appends a message to a mailbox, returning its UID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mailbox doesn't exist.
+    pub fn append(&self, mailbox: &str, flags: &[&str], data: impl Into<Vec<u8>>) -> u32 {
+        let mut state = self.state();
+        let mailbox = state.mailboxes.get_mut(mailbox).expect("mailbox exists");
+        let uid = mailbox.uid_next;
+        mailbox.uid_next += 1;
+        mailbox.messages.push(FakeMessage {
+            uid,
+            flags: flags.iter().map(ToString::to_string).collect(),
+            data: data.into(),
+        });
+        uid
+    }
+
+    /// Changes the `UIDVALIDITY` of a mailbox and renumbers its messages,
+    /// as servers do when they can't preserve UIDs.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mailbox doesn't exist.
+    pub fn reset_uid_validity(&self, mailbox: &str, uid_validity: u32) {
+        let mut state = self.state();
+        let mailbox = state.mailboxes.get_mut(mailbox).expect("mailbox exists");
+        mailbox.uid_validity = uid_validity;
+        mailbox.uid_next = 1;
+        for message in &mut mailbox.messages {
+            message.uid = mailbox.uid_next;
+            mailbox.uid_next += 1;
+        }
+    }
+
+    /// Opens a new connection, with the server greeting ready to be read.
+    pub fn connect(&self) -> FakeConnection {
+        FakeConnection {
+            server: self.clone(),
+            input: Vec::new(),
+            line: Vec::new(),
+            literal: None,
+            output: VecDeque::from(
+                b"* OK [CAPABILITY IMAP4rev1] Fake IMAP server ready\r\n".to_vec(),
+            ),
+            is_authenticated: false,
+            selected: None,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap_or_else(|error| error.into_inner())
+    }
+}
+
+/// A client connection to a [`FakeServer`].
+///
+/// Commands are processed as soon as a complete line has been written, so
+/// that their responses can be read right away. Synchronizing literals are
+/// accepted and passed on to the command as quoted strings.
+#[derive(Debug)]
+pub struct FakeConnection {
+    server: FakeServer,
+    input: Vec<u8>,
+    line: Vec<u8>,
+    literal: Option<usize>,
+    output: VecDeque<u8>,
+    is_authenticated: bool,
+    selected: Option<String>,
+}
+
+impl FakeConnection {
+    fn handle(&mut self, line: &str) {
+        let args = tokenize(line);
+        let (tag, command) = match args.as_slice() {
+            [tag, command, ..] => (tag.clone(), command.to_ascii_uppercase()),
+            _ => return self.reply("*", "BAD", "Invalid command"),
+        };
+        let args = &args[2..];
+        match (command.as_str(), args) {
+            ("CAPABILITY", []) => {
+                self.write("* CAPABILITY IMAP4rev1\r\n");
+                self.reply(&tag, "OK", "CAPABILITY completed");
+            },
+            ("NOOP", []) => self.reply(&tag, "OK", "NOOP completed"),
+            ("LOGOUT", []) => {
+                self.write("* BYE Logging out\r\n");
+                self.reply(&tag, "OK", "LOGOUT completed");
+            },
+            ("LOGIN", [username, password]) => {
+                let state = self.server.state();
+                let is_valid = *username == state.username && *password == state.password;
+                drop(state);
+                if is_valid {
+                    self.is_authenticated = true;
+                    self.reply(&tag, "OK", "LOGIN completed");
+                } else {
+                    self.reply(&tag, "NO", "[AUTHENTICATIONFAILED] Invalid credentials");
+                }
+            },
+            (_, _) if !self.is_authenticated => self.reply(&tag, "NO", "Not authenticated"),
+            ("LIST", [_, _]) => {
+                let names: Vec<String> = self.server.state().mailboxes.keys().cloned().collect();
+                for name in names {
+                    self.write(&format!("* LIST () \"/\" {}\r\n", quote(&name)));
+                }
+                self.reply(&tag, "OK", "LIST completed");
+            },
+            ("SELECT", [name]) => {
+                let state = self.server.state();
+                let Some(mailbox) = state.mailboxes.get(name) else {
+                    drop(state);
+                    self.selected = None;
+                    return self.reply(&tag, "NO", "No such mailbox");
+                };
+                let response = format!(
+                    "* {} EXISTS\r\n* OK [UIDVALIDITY {}] UIDs valid\r\n* OK [UIDNEXT {}] Predicted next UID\r\n",
+                    mailbox.messages.len(),
+                    mailbox.uid_validity,
+                    mailbox.uid_next
+                );
+                drop(state);
+                self.write(&response);
+                self.selected = Some(name.clone());
+                self.reply(&tag, "OK", "[READ-WRITE] SELECT completed");
+            },
+            ("UID", [subcommand, criterion, set])
+                if subcommand.eq_ignore_ascii_case("SEARCH")
+                    && criterion.eq_ignore_ascii_case("UID") =>
+            {
+                let Some(ref selected) = self.selected else {
+                    return self.reply(&tag, "BAD", "No mailbox selected");
+                };
+                let Some(set) = parse_uid_set(set) else {
+                    return self.reply(&tag, "BAD", "Invalid UID set");
+                };
+                let state = self.server.state();
+                let messages = &state.mailboxes[selected].messages;
+                let uids: Vec<String> = messages
+                    .iter()
+                    .filter(|message| uid_set_contains(&set, messages, message.uid))
+                    .map(|message| format!(" {}", message.uid))
+                    .collect();
+                drop(state);
+                self.write(&format!("* SEARCH{}\r\n", uids.concat()));
+                self.reply(&tag, "OK", "UID SEARCH completed");
+            },
+            ("UID", [subcommand, set, ..]) if subcommand.eq_ignore_ascii_case("FETCH") => {
+                let Some(ref selected) = self.selected else {
+                    return self.reply(&tag, "BAD", "No mailbox selected");
+                };
+                let Some(set) = parse_uid_set(set) else {
+                    return self.reply(&tag, "BAD", "Invalid UID set");
+                };
+                let mut response = Vec::new();
+                let state = self.server.state();
+                let messages = &state.mailboxes[selected].messages;
+                for (i, message) in messages.iter().enumerate() {
+                    if !uid_set_contains(&set, messages, message.uid) {
+                        continue;
+                    }
+                    response.extend_from_slice(
+                        format!(
+                            "* {} FETCH (UID {} FLAGS ({}) INTERNALDATE \"{}\" RFC822.SIZE {} BODY[] {{{}}}\r\n",
+                            i + 1,
+                            message.uid,
+                            message.flags.join(" "),
+                            INTERNAL_DATE,
+                            message.data.len(),
+                            message.data.len()
+                        )
+                        .as_bytes(),
+                    );
+                    response.extend_from_slice(&message.data);
+                    response.extend_from_slice(b")\r\n");
+                }
+                drop(state);
+                self.output.extend(response);
+                self.reply(&tag, "OK", "UID FETCH completed");
+            },
+            _ => self.reply(&tag, "BAD", "Unsupported command"),
+        }
+    }
+
+    fn write(&mut self, response: &str) {
+        self.output.extend(response.as_bytes());
+    }
+
+    fn reply(&mut self, tag: &str, status: &str, text: &str) {
+        self.write(&format!("{} {} {}\r\n", tag, status, text));
+    }
+}
+
+impl Read for FakeConnection {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.output.read(buf)
+    }
+}
+
+impl Write for FakeConnection {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.input.extend_from_slice(buf);
+        loop {
+            if let Some(size) = self.literal {
+                if self.input.len() < size {
+                    break;
+                }
+                let literal: Vec<u8> = self.input.drain(..size).collect();
+                self.line
+                    .extend_from_slice(quote(&String::from_utf8_lossy(&literal)).as_bytes());
+                self.literal = None;
+                continue;
+            }
+            let Some(end) = self.input.windows(2).position(|w| w == b"\r\n") else {
+                break;
+            };
+            self.line.extend(self.input.drain(..end));
+            self.input.drain(..2);
+            if let Some(size) = literal_size(&self.line) {
+                self.line
+                    .truncate(self.line.len() - size.to_string().len() - 2);
+                self.literal = Some(size);
+                self.write("+ Ready for literal data\r\n");
+                continue;
+            }
+            let line = std::mem::take(&mut self.line);
+            self.handle(&String::from_utf8_lossy(&line));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Splits a command line into atoms and quoted strings, keeping
+/// parenthesized lists together.
+fn tokenize(line: &str) -> Vec<String> {
+    let mut output = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            ' ' => {
+                chars.next();
+            },
+            '"' => {
+                chars.next();
+                let mut token = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => token.extend(chars.next()),
+                        '"' => break,
+                        c => token.push(c),
+                    }
+                }
+                output.push(token);
+            },
+            _ => {
+                let mut token = String::new();
+                let mut depth = 0;
+                while let Some(&c) = chars.peek() {
+                    match c {
+                        ' ' if depth == 0 => break,
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        _ => {},
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                output.push(token);
+            },
+        }
+    }
+    output
+}
+
+/// Returns the size of the literal announced at the end of a line, as in
+/// `{42}`.
+fn literal_size(line: &[u8]) -> Option<usize> {
+    let line = line.strip_suffix(b"}")?;
+    let start = line.iter().rposition(|&b| b == b'{')?;
+    std::str::from_utf8(&line[start + 1..]).ok()?.parse().ok()
+}
+
+/// Parses a comma-separated UID set of `n`, `n:m`, or `n:*` ranges.
+fn parse_uid_set(input: &str) -> Option<Vec<RangeInclusive<u32>>> {
+    let parse = |input: &str| match input {
+        "*" => Some(u32::MAX),
+        _ => input.parse().ok(),
+    };
+    input
+        .split(',')
+        .map(|range| match range.split_once(':') {
+            Some((start, end)) => Some(parse(start)?..=parse(end)?),
+            None => parse(range).map(|uid| uid..=uid),
+        })
+        .collect()
+}
+
+/// Checks whether a UID set matches a message. As `*` stands for the
+/// highest UID in use, a range ending with `*` always matches the last
+/// message.
+fn uid_set_contains(set: &[RangeInclusive<u32>], messages: &[FakeMessage], uid: u32) -> bool {
+    let last_uid = messages.last().map(|message| message.uid);
+    set.iter()
+        .any(|range| range.contains(&uid) || (range.end() == &u32::MAX && Some(uid) == last_uid))
+}
+
+fn quote(input: &str) -> String {
+    format!("\"{}\"", input.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -0,0 +1,10 @@
+// This is free and unencumbered software released into the public domain.
+
+mod checkpoint;
+mod client;
+mod error;
+pub mod fake;
+
+pub use checkpoint::*;
+pub use client::*;
+pub use error::*;
@@ -0,0 +1,313 @@
+// This is free and unencumbered software released into the public domain.
+
+use crate::Error;
+use know::{classes::EmailMessage, datatypes::EmailAddress};
+use std::io::{BufRead, BufReader, Read, Write};
+
+/// A reply from an SMTP server.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc5321#section-4.2
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Reply {
+    pub code: u16,
+    pub lines: Vec<String>,
+}
+
+impl Reply {
+    pub fn is_positive(&self) -> bool {
+        (200..400).contains(&self.code)
+    }
+}
+
+/// A blocking SMTP client for message submission.
+///
+/// The client speaks SMTP over any stream, such as a TLS session over a
+/// TCP connection to port 465.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc6409
+#[derive(Debug)]
+pub struct Client<S: Read + Write> {
+    stream: BufReader<S>,
+    extensions: Vec<String>,
+}
+
+impl<S: Read + Write> Client<S> {
+    /// Starts a session over the given stream, reading the server greeting
+    /// and introducing this client as `domain`.
+    pub fn new(stream: S, domain: &str) -> Result<Self, Error> {
+        let mut client = Self {
+            stream: BufReader::new(stream),
+            extensions: Vec::new(),
+        };
+        let greeting = client.read_reply()?;
+        if greeting.code != 220 {
+            return Err(Error::Rejected(greeting.code, greeting.lines.join(" ")));
+        }
+        let reply = client.command(&format!("EHLO {}", domain))?;
+        client.extensions = reply.lines.into_iter().skip(1).collect();
+        Ok(client)
+    }
+
+    /// Returns the service extensions advertised in reply to `EHLO`,
+    /// e.g., `AUTH PLAIN LOGIN`.
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    /// Returns whether the server advertised the given extension, e.g.,
+    /// `SMTPUTF8`.
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.iter().any(|extension| {
+            let keyword = extension.split(' ').next().unwrap_or_default();
+            keyword.eq_ignore_ascii_case(name)
+        })
+    }
+
+    /// Authenticates with the `PLAIN` mechanism.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc4616
+    pub fn login(&mut self, username: &str, password: &str) -> Result<(), Error> {
+        use base64::{Engine as _, engine::general_purpose::STANDARD};
+        let credentials = STANDARD.encode(format!("\0{}\0{}", username, password));
+        self.command(&format!("AUTH PLAIN {}", credentials))?;
+        Ok(())
+    }
+
+    /// Submits a message to all of its `To`, `Cc`, and `Bcc` recipients,
+    /// failing if the server rejects any of them.
+    ///
+    /// The envelope sender is the `Sender` address, or else the first `From`
+    /// address. `Bcc` recipients are only given in the envelope, never in
+    /// the message headers.
+    pub fn send(&mut self, message: &EmailMessage) -> Result<(), Error> {
+        let sender = message
+            .sender
+            .as_ref()
+            .or(message.from.first())
+            .ok_or(Error::MissingSender)?;
+        let mut recipients: Vec<&EmailAddress> = Vec::new();
+        for recipient in message.to.iter().chain(&message.cc).chain(&message.bcc) {
+            if !recipients.contains(&recipient) {
+                recipients.push(recipient);
+            }
+        }
+        if recipients.is_empty() {
+            return Err(Error::MissingRecipients);
+        }
+
+        let headers = EmailMessage {
+            bcc: Vec::new(),
+            ..message.clone()
+        };
+        let data = headers.mime().to_string();
+
+        let result = self.transaction(sender, &recipients, &data);
+        if result.is_err() {
+            // Abort the transaction, but report the original error:
+            let _ = self.command("RSET");
+        }
+        result
+    }
+
+    pub fn quit(mut self) -> Result<(), Error> {
+        self.command("QUIT")?;
+        Ok(())
+    }
+
+    /// See: https://datatracker.ietf.org/doc/html/rfc5321#section-3.3
+    fn transaction(
+        &mut self,
+        sender: &EmailAddress,
+        recipients: &[&EmailAddress],
+        data: &str,
+    ) -> Result<(), Error> {
+        // Internationalized domains in their ASCII form work without SMTPUTF8,
+        // but non-ASCII local parts need it:
+        let path =
+            |address: &EmailAddress| address.to_ascii().unwrap_or_else(|| address.to_string());
+        let mut parameters = "";
+        if let Some(address) = std::iter::once(&sender)
+            .chain(recipients)
+            .find(|address| address.to_ascii().is_none())
+        {
+            if !self.has_extension("SMTPUTF8") {
+                return Err(Error::Utf8Unsupported(address.to_string()));
+            }
+            parameters = " SMTPUTF8";
+        }
+        self.command(&format!("MAIL FROM:<{}>{}", path(sender), parameters))?;
+        for recipient in recipients {
+            self.command(&format!("RCPT TO:<{}>", path(recipient)))?;
+        }
+        self.command("DATA")?;
+        let stream = self.stream.get_mut();
+        for line in data.split_terminator("\r\n") {
+            // See: https://datatracker.ietf.org/doc/html/rfc5321#section-4.5.2
+            if line.starts_with('.') {
+                stream.write_all(b".")?;
+            }
+            write!(stream, "{}\r\n", line)?;
+        }
+        self.command(".")?;
+        Ok(())
+    }
+
+    /// Sends a command line and reads the reply, failing on a negative
+    /// reply.
+    fn command(&mut self, command: &str) -> Result<Reply, Error> {
+        let stream = self.stream.get_mut();
+        write!(stream, "{}\r\n", command)?;
+        stream.flush()?;
+        let reply = self.read_reply()?;
+        match reply.is_positive() {
+            true => Ok(reply),
+            false => Err(Error::Rejected(reply.code, reply.lines.join(" "))),
+        }
+    }
+
+    /// Reads a possibly multiline reply.
+    fn read_reply(&mut self) -> Result<Reply, Error> {
+        let mut reply = Reply::default();
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line)? == 0 {
+                Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+            }
+            let line = line.trim_end_matches(['\r', '\n']);
+            let Some(code) = line.get(..3).and_then(|code| code.parse().ok()) else {
+                return Err(Error::Protocol(line.into()));
+            };
+            reply.code = code;
+            reply.lines.push(line.get(4..).unwrap_or_default().into());
+            if line.as_bytes().get(3) != Some(&b'-') {
+                return Ok(reply);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sink::SmtpSink;
+    use std::net::TcpStream;
+
+    fn message() -> EmailMessage {
+        EmailMessage {
+            date: "2025-07-07T12:00:00Z".parse().unwrap(),
+            from: vec!["alice@example.org".parse().unwrap()],
+            to: vec!["bob@example.org".parse().unwrap()],
+            cc: vec!["carol@example.org".parse().unwrap()],
+            bcc: vec![
+                "dave@example.org".parse().unwrap(),
+                "bob@example.org".parse().unwrap(),
+            ],
+            // Lines starting with a dot need dot-stuffing:
+            body: Some("Hi Bob!\n.\n.hidden".into()),
+            ..Default::default()
+        }
+    }
+
+    fn session(sink: &SmtpSink) -> Client<TcpStream> {
+        let mut client =
+            Client::new(TcpStream::connect(sink.addr()).unwrap(), "localhost").unwrap();
+        client.login("alice", "secret").unwrap();
+        client
+    }
+
+    #[test]
+    fn test_send() {
+        let sink = SmtpSink::start().unwrap();
+        let mut client = session(&sink);
+        assert!(client.extensions().iter().any(|ext| ext == "AUTH PLAIN"));
+        client.send(&message()).unwrap();
+        client.quit().unwrap();
+
+        let received = sink.received();
+        assert_eq!(received.len(), 1);
+        let envelope = &received[0];
+        assert_eq!(envelope.mail_from, "alice@example.org");
+        assert_eq!(
+            envelope.rcpt_to,
+            ["bob@example.org", "carol@example.org", "dave@example.org"]
+        );
+
+        let parsed = mailparse::parse_mail(&envelope.data).unwrap();
+        let actual = EmailMessage::try_from(&parsed).unwrap();
+        assert_eq!(
+            actual,
+            EmailMessage {
+                bcc: Vec::new(),
+                ..message()
+            }
+        );
+        let data = String::from_utf8_lossy(&envelope.data);
+        assert!(!data.to_ascii_lowercase().contains("\r\nbcc:"));
+        assert!(!data.contains("dave@example.org"));
+    }
+
+    #[test]
+    fn test_rejected_recipient() {
+        let sink = SmtpSink::start().unwrap();
+        sink.reject_recipient("carol@example.org");
+        let mut client = session(&sink);
+        assert!(matches!(
+            client.send(&message()),
+            Err(Error::Rejected(550, _))
+        ));
+
+        // The session is still usable after the failed transaction:
+        let message = EmailMessage {
+            cc: Vec::new(),
+            ..message()
+        };
+        client.send(&message).unwrap();
+        assert_eq!(sink.received().len(), 1);
+    }
+
+    #[test]
+    fn test_smtputf8() {
+        let sink = SmtpSink::start().unwrap();
+        let mut client = session(&sink);
+        assert!(client.has_extension("smtputf8"));
+        let message = EmailMessage {
+            to: vec!["jörg@例え.jp".parse().unwrap()],
+            cc: Vec::new(),
+            bcc: Vec::new(),
+            ..message()
+        };
+        client.send(&message).unwrap();
+        let received = sink.received();
+        assert!(received[0].smtputf8);
+        assert_eq!(received[0].rcpt_to, ["jörg@例え.jp"]);
+
+        sink.disable_smtputf8();
+        let mut client = session(&sink);
+        assert!(matches!(
+            client.send(&message),
+            Err(Error::Utf8Unsupported(address)) if address == "jörg@例え.jp"
+        ));
+        assert_eq!(sink.received().len(), 1);
+    }
+
+    #[test]
+    fn test_missing_addresses() {
+        let sink = SmtpSink::start().unwrap();
+        let mut client = session(&sink);
+        let message = EmailMessage {
+            from: Vec::new(),
+            ..message()
+        };
+        assert!(matches!(client.send(&message), Err(Error::MissingSender)));
+        let message = EmailMessage {
+            to: Vec::new(),
+            cc: Vec::new(),
+            bcc: Vec::new(),
+            ..self::message()
+        };
+        assert!(matches!(
+            client.send(&message),
+            Err(Error::MissingRecipients)
+        ));
+    }
+}
@@ -0,0 +1,32 @@
+// This is free and unencumbered software released into the public domain.
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
+    /// The server rejected a command with a 4xx or 5xx reply.
+    #[error("SMTP command rejected: {0} {1}")]
+    Rejected(u16, String),
+
+    /// The server sent something that isn't a valid SMTP reply.
+    #[error("malformed SMTP reply: {0}")]
+    Protocol(String),
+
+    /// The message has neither a `Sender` nor a `From` address.
+    #[error("message has no sender")]
+    MissingSender,
+
+    /// An address has a non-ASCII local part, but the server doesn't
+    /// support the `SMTPUTF8` extension.
+    ///
+    /// See: https://datatracker.ietf.org/doc/html/rfc6531
+    #[error("server doesn't support SMTPUTF8, needed for {0}")]
+    Utf8Unsupported(String),
+
+    /// The message has no `To`, `Cc`, or `Bcc` addresses.
+    #[error("message has no recipients")]
+    MissingRecipients,
+}
@@ -0,0 +1,8 @@
+// This is free and unencumbered software released into the public domain.
+
+mod client;
+mod error;
+pub mod sink;
+
+pub use client::*;
+pub use error::*;
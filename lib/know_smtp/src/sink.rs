@@ -0,0 +1,189 @@
+// This is free and unencumbered software released into the public domain.
+
+//! A local SMTP server that accepts and records all mail, for testing
+//! submission offline.
+//!
+//! The sink listens on a loopback port and serves each connection on a
+//! background thread. It accepts any credentials, and rejects only the
+//! recipients it was told to reject.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc, Mutex, MutexGuard,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread,
+};
+
+/// A message as received by the sink.
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Envelope {
+    pub mail_from: String,
+    pub rcpt_to: Vec<String>,
+
+    /// Whether `MAIL FROM` had the `SMTPUTF8` parameter.
+    pub smtputf8: bool,
+
+    /// The message data, with dot-stuffing removed.
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    received: Vec<Envelope>,
+    rejected_recipients: Vec<String>,
+    is_smtputf8_disabled: bool,
+}
+
+/// A running SMTP sink, which stops when dropped.
+#[derive(Debug)]
+pub struct SmtpSink {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    is_stopped: Arc<AtomicBool>,
+}
+
+impl SmtpSink {
+    /// Starts a sink on an ephemeral loopback port.
+    pub fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let sink = Self {
+            addr: listener.local_addr()?,
+            state: Arc::default(),
+            is_stopped: Arc::default(),
+        };
+        let (state, is_stopped) = (sink.state.clone(), sink.is_stopped.clone());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if is_stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let state = state.clone();
+                thread::spawn(move || serve(stream, &state));
+            }
+        });
+        Ok(sink)
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Makes the sink reject the given recipient address with a 550 reply.
+    pub fn reject_recipient(&self, address: &str) {
+        lock(&self.state).rejected_recipients.push(address.into());
+    }
+
+    /// Makes the sink stop advertising `SMTPUTF8` to new sessions.
+    pub fn disable_smtputf8(&self) {
+        lock(&self.state).is_smtputf8_disabled = true;
+    }
+
+    /// Returns the messages received so far.
+    pub fn received(&self) -> Vec<Envelope> {
+        lock(&self.state).received.clone()
+    }
+}
+
+impl Drop for SmtpSink {
+    fn drop(&mut self) {
+        self.is_stopped.store(true, Ordering::SeqCst);
+        // Wake up the listener thread:
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state.lock().unwrap_or_else(|error| error.into_inner())
+}
+
+/// Serves a single SMTP session.
+///
+/// See: https://datatracker.ietf.org/doc/html/rfc5321#section-4.1
+fn serve(stream: TcpStream, state: &Mutex<State>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut envelope = Envelope::default();
+    writer.write_all(b"220 localhost SMTP sink ready\r\n")?;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        let verb = line
+            .split(' ')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        let argument = |prefix: &str| {
+            line.get(prefix.len()..)
+                .filter(|_| line[..prefix.len()].eq_ignore_ascii_case(prefix))
+                .map(|arg| {
+                    let (path, _) = arg.trim().split_once(' ').unwrap_or((arg.trim(), ""));
+                    path.trim_start_matches('<').trim_end_matches('>')
+                })
+        };
+        let reply: &[u8] = match verb.as_str() {
+            "EHLO" if lock(state).is_smtputf8_disabled => {
+                b"250-localhost\r\n250-AUTH PLAIN\r\n250 8BITMIME\r\n"
+            },
+            "EHLO" => b"250-localhost\r\n250-AUTH PLAIN\r\n250-8BITMIME\r\n250 SMTPUTF8\r\n",
+            "HELO" | "NOOP" => b"250 OK\r\n",
+            "AUTH" => b"235 Authentication succeeded\r\n",
+            "RSET" => {
+                envelope = Envelope::default();
+                b"250 OK\r\n"
+            },
+            "MAIL" => match argument("MAIL FROM:") {
+                Some(mail_from) => {
+                    envelope = Envelope {
+                        mail_from: mail_from.into(),
+                        smtputf8: line.to_ascii_uppercase().ends_with(" SMTPUTF8"),
+                        ..Default::default()
+                    };
+                    b"250 OK\r\n"
+                },
+                None => b"501 Syntax error\r\n",
+            },
+            "RCPT" => match argument("RCPT TO:") {
+                Some(rcpt_to) if lock(state).rejected_recipients.iter().any(|r| r == rcpt_to) => {
+                    b"550 Mailbox unavailable\r\n"
+                },
+                Some(rcpt_to) => {
+                    envelope.rcpt_to.push(rcpt_to.into());
+                    b"250 OK\r\n"
+                },
+                None => b"501 Syntax error\r\n",
+            },
+            "DATA" if envelope.rcpt_to.is_empty() => b"503 No valid recipients\r\n",
+            "DATA" => {
+                writer.write_all(b"354 End data with <CR><LF>.<CR><LF>\r\n")?;
+                loop {
+                    let mut line = Vec::new();
+                    if reader.read_until(b'\n', &mut line)? == 0 {
+                        return Ok(());
+                    }
+                    match line.as_slice() {
+                        b".\r\n" => break,
+                        [b'.', rest @ ..] => envelope.data.extend_from_slice(rest),
+                        line => envelope.data.extend_from_slice(line),
+                    }
+                }
+                lock(state).received.push(core::mem::take(&mut envelope));
+                b"250 OK\r\n"
+            },
+            "QUIT" => {
+                writer.write_all(b"221 Bye\r\n")?;
+                return Ok(());
+            },
+            _ => b"502 Command not implemented\r\n",
+        };
+        writer.write_all(reply)?;
+    }
+}
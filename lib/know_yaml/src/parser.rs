@@ -20,7 +20,7 @@ impl<'de> Iterator for Parser<'de> {
     type Item = Result<Thing, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.0.next().map(|result| Thing::deserialize(result)) {
+        match self.0.next().map(Thing::deserialize) {
             Some(Ok(thing)) => Some(Ok(thing)),
             Some(Err(err)) => Some(Err(err.into())),
             None => None,